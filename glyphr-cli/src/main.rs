@@ -0,0 +1,387 @@
+//! # glyphr
+//!
+//! Command line front-end of the font generator: generates fonts outside of compilation and
+//! inspects TTF files.
+
+mod preview;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::rc::Rc;
+
+use glyphr::runtime;
+
+use glyphr_gen::atlas::Atlas;
+use glyphr_gen::config::{BitmapFormat, FontLoaded, ToFontLoaded, load_font, parse_char_set};
+use glyphr_gen::generator::GeneratedFont;
+use glyphr_gen::renderer;
+use glyphr_gen::toml_parser::TomlConfig;
+
+const USAGE: &str = "\
+Usage: glyphr <command> [options]
+
+Commands:
+  generate <fonts.toml> [--rust <file.rs>] [--blob <dir>] [--preview <dir>]
+        Generates every font of the toml file (same schema as `generate_fonts_from_toml!`)
+        and writes Rust source, binary blobs (`<dir>/<NAME>.glyr`) and/or PNG previews.
+  atlas <fonts.toml> --out <dir> [--metadata text|json] [--spacing <px>]
+        Packs the glyphs of every font of the toml file in a texture (`<dir>/<NAME>.png`)
+        described by BMFont metadata (`<dir>/<NAME>.fnt` or `<dir>/<NAME>.json`).
+  coverage <font.ttf>
+        Lists the characters covered by the font.
+  metrics <font.ttf> --size <px> [--characters <pattern>]
+        Shows line metrics, and glyph metrics of the given characters.
+  size <font.ttf> --size <px> --characters <pattern> [--format sdf|bitmap]
+       [--spread <f32>] [--padding <i32>]
+        Computes how many bytes the generated font takes.
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("generate") => generate(&args[1..]),
+        Some("atlas") => atlas(&args[1..]),
+        Some("coverage") => coverage(&args[1..]),
+        Some("metrics") => metrics(&args[1..]),
+        Some("size") => size(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command '{command}'\n\n{USAGE}")),
+        None => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Positional arguments and `--key value` options of a command.
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Args<'a> {
+    fn parse(args: &'a [String], known: &[&str]) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(key) if known.contains(&key) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| format!("missing value for '--{key}'"))?;
+                    options.push((key, value.as_str()));
+                }
+                Some(key) => return Err(format!("unknown option '--{key}'")),
+                None => positional.push(arg.as_str()),
+            }
+        }
+
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn input(&self) -> Result<&'a str, String> {
+        match self.positional.as_slice() {
+            [input] => Ok(input),
+            [] => Err("missing input file".to_string()),
+            _ => Err("too many positional arguments".to_string()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        self.get(key)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| format!("invalid value '{v}' for '--{key}'"))
+            })
+            .transpose()
+    }
+
+    fn required<T: std::str::FromStr>(&self, key: &str) -> Result<T, String> {
+        self.parsed(key)?
+            .ok_or_else(|| format!("missing '--{key}'"))
+    }
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["rust", "blob", "preview"])?;
+    let input = args.input()?;
+    let (rust, blobs, previews) = (args.get("rust"), args.get("blob"), args.get("preview"));
+    if rust.is_none() && blobs.is_none() && previews.is_none() {
+        return Err("nothing to do, pass at least one of --rust, --blob, --preview".to_string());
+    }
+
+    let generated: Vec<GeneratedFont> = TomlConfig::from_file(input)
+        .and_then(|config| config.to_font_loaded())
+        .and_then(|fonts| fonts.into_iter().map(GeneratedFont::new).collect())
+        .map_err(|e| e.to_string())?;
+
+    if let Some(rust) = rust {
+        let mut code = renderer::render_generated(&generated).map_err(|e| e.to_string())?;
+        let icons = generated.iter().flat_map(|font| font.font.font.icons());
+        code.push_str(&renderer::render_icon_constants(icons).map_err(|e| e.to_string())?);
+        write(Path::new(rust), code.as_bytes())?;
+    }
+
+    for font in &generated {
+        let encoded = runtime::encode(font);
+
+        if let Some(dir) = blobs {
+            write(&output_path(dir, &font.font.name, "glyr")?, &encoded)?;
+        }
+
+        if let Some(dir) = previews {
+            let image = preview::render(&encoded)?;
+            write(&output_path(dir, &font.font.name, "png")?, &image)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn atlas(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["out", "metadata", "spacing"])?;
+    let input = args.input()?;
+    let dir: String = args.required("out")?;
+    let spacing = args.parsed("spacing")?.unwrap_or(1);
+    let json = match args.get("metadata").unwrap_or("text") {
+        "text" | "fnt" => false,
+        "json" => true,
+        other => return Err(format!("unknown metadata format '{other}'")),
+    };
+
+    let fonts = TomlConfig::from_file(input)
+        .and_then(|config| config.to_font_loaded())
+        .map_err(|e| e.to_string())?;
+
+    for font in fonts {
+        let generated = GeneratedFont::new(font).map_err(|e| e.to_string())?;
+        let atlas = Atlas::new(&generated, spacing).map_err(|e| e.to_string())?;
+        let page = format!("{}.png", atlas.name);
+
+        write(&output_path(&dir, &atlas.name, "png")?, &atlas.to_png())?;
+        let metadata = match json {
+            true => atlas.to_json(&page),
+            false => atlas.to_fnt(&page),
+        };
+        let extension = if json { "json" } else { "fnt" };
+        write(
+            &output_path(&dir, &atlas.name, extension)?,
+            metadata.as_bytes(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn output_path(dir: &str, name: &str, extension: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("can't create '{dir}': {e}"))?;
+    Ok(Path::new(dir).join(format!("{name}.{extension}")))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("can't create '{}': {e}", parent.display()))?;
+    }
+    fs::write(path, content).map_err(|e| format!("can't write '{}': {e}", path.display()))?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+fn coverage(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[])?;
+    let font = load_font(args.input()?).map_err(|e| e.to_string())?;
+    let chars = font.chars();
+
+    let mut ranges: Vec<(char, char)> = Vec::new();
+    for c in chars.iter().copied() {
+        match ranges.last_mut() {
+            Some((_, end)) if *end as u32 + 1 == c as u32 => *end = c,
+            _ => ranges.push((c, c)),
+        }
+    }
+
+    for (start, end) in ranges {
+        if start == end {
+            println!("U+{:04X}", start as u32);
+        } else {
+            println!("U+{:04X}-U+{:04X}", start as u32, end as u32);
+        }
+    }
+    println!("{} characters", chars.len());
+
+    Ok(())
+}
+
+fn metrics(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["size", "characters"])?;
+    let font = load_font(args.input()?).map_err(|e| e.to_string())?;
+    let px: i32 = args.required("size")?;
+
+    println!("ascent:   {}", font.get_ascent(px as f32));
+    println!("descent:  {}", font.get_descent(px as f32));
+    println!("line gap: {}", font.get_line_gap(px as f32));
+
+    if let Some(pattern) = args.get("characters") {
+        println!();
+        println!("char     xmin  ymin width height advance");
+        for c in parse_char_set(pattern).map_err(|e| e.to_string())? {
+            match font.metrics(c, px as f32) {
+                Some(m) => println!(
+                    "U+{:04X} {:>5} {:>5} {:>5} {:>6} {:>7}",
+                    c as u32, m.xmin, m.ymin, m.width, m.height, m.advance_width
+                ),
+                None => println!("U+{:04X} missing", c as u32),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn size(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["size", "characters", "format", "spread", "padding"])?;
+    let font = load_font(args.input()?).map_err(|e| e.to_string())?;
+    let px: i32 = args.required("size")?;
+    let characters: String = args.required("characters")?;
+    let spread = args.parsed("spread")?.unwrap_or(4.0);
+    let padding = args.parsed("padding")?.unwrap_or(0);
+    let format = match args.get("format").unwrap_or("sdf") {
+        "sdf" | "SDF" => BitmapFormat::SDF { spread, padding },
+        "bitmap" | "Bitmap" => BitmapFormat::Bitmap { spread, padding },
+        other => return Err(format!("unknown format '{other}'")),
+    };
+
+    let generated = GeneratedFont::new(FontLoaded {
+        name: "SIZE".to_string(),
+        font: Rc::new(font),
+        px,
+        char_range: parse_char_set(&characters).map_err(|e| e.to_string())?,
+        format,
+        options: Default::default(),
+        family: None,
+    })
+    .map_err(|e| e.to_string())?;
+
+    // `glyphr::Glyph` on a 32 bit target: char, fat pointer and five i32.
+    const GLYPH_LEN: usize = 4 + 8 + 5 * 4;
+    let glyphs = generated.glyphs.len();
+    let bitmaps: usize = generated.glyphs.iter().map(|(b, _)| b.len()).sum();
+
+    println!("glyphs:      {glyphs}");
+    println!("bitmaps:     {bitmaps} bytes");
+    println!("glyph table: {} bytes (32 bit target)", glyphs * GLYPH_LEN);
+    println!("total:       {} bytes", bitmaps + glyphs * GLYPH_LEN);
+    println!("blob:        {} bytes", runtime::encode(&generated).len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO_TTF: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../glyphr-gen/tests/fonts/demo.ttf"
+    );
+
+    fn demo_font(format: BitmapFormat) -> GeneratedFont {
+        GeneratedFont::new(FontLoaded {
+            name: "DEMO".to_string(),
+            font: Rc::new(load_font(DEMO_TTF).unwrap()),
+            px: 32,
+            char_range: vec!['A'],
+            format,
+            options: Default::default(),
+            family: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_args_parse() {
+        let args: Vec<String> = ["a.ttf", "--size", "16"].map(String::from).to_vec();
+        let parsed = Args::parse(&args, &["size"]).unwrap();
+        assert_eq!(parsed.input().unwrap(), "a.ttf");
+        assert_eq!(parsed.required::<i32>("size").unwrap(), 16);
+        assert!(parsed.required::<i32>("padding").is_err());
+
+        assert!(Args::parse(&args, &[]).is_err());
+    }
+
+    #[test]
+    fn test_blob_loads_in_glyphr() {
+        let generated = demo_font(BitmapFormat::SDF {
+            spread: 20.0,
+            padding: 0,
+        });
+        let encoded = runtime::encode(&generated);
+        let font = glyphr::Font::from_bytes(&encoded).unwrap();
+
+        let (bitmap, entry) = &generated.glyphs[0];
+        let glyph = font.find_glyph('A').unwrap();
+        assert_eq!(glyph.bitmap, bitmap.as_slice());
+        assert_eq!((glyph.width, glyph.height), (entry.width, entry.height));
+        assert_eq!(glyph.advance_width, entry.advance_width);
+        assert_eq!(font.size, 32);
+        assert_eq!(font.ascent, generated.ascent());
+        assert!(matches!(font.format, glyphr::BitmapFormat::SDF));
+    }
+
+    #[test]
+    fn test_rust_output_has_icon_constants() {
+        let dir = std::env::temp_dir().join(format!("glyphr-cli-icons-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let icon = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../glyphr-gen/tests/icons/wifi.svg"
+        );
+        let config = format!(
+            "[[font]]\nname = \"DEMO\"\npath = {DEMO_TTF:?}\nsize = 16\ncharacters = \"A\"\n\
+             format = {{ Bitmap = {{ spread = 2.0, padding = 0 }} }}\n\
+             icons = {{ wifi = {{ path = {icon:?}, codepoint = 0xE000 }} }}\n"
+        );
+        let (toml, rust) = (dir.join("fonts.toml"), dir.join("fonts.rs"));
+        fs::write(&toml, config).unwrap();
+
+        let args = [toml.to_str().unwrap(), "--rust", rust.to_str().unwrap()];
+        generate(&args.map(String::from)).unwrap();
+        let code = fs::read_to_string(&rust).unwrap();
+        assert!(code.contains("ICON_WIFI"), "{code}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_preview_is_png() {
+        let generated = demo_font(BitmapFormat::Bitmap {
+            spread: 2.0,
+            padding: 0,
+        });
+        let image = preview::render(&runtime::encode(&generated)).unwrap();
+        assert_eq!(&image[1..4], b"PNG");
+    }
+}
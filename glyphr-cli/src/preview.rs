@@ -0,0 +1,84 @@
+//! # preview.rs
+//!
+//! Draws every glyph of a font blob on a grid with the glyphr renderer itself, and encodes the
+//! result as a grayscale PNG.
+
+use glyphr::{AlignH, AlignV, Font, Glyphr, RenderConfig, RenderTarget, TextAlign};
+
+const COLUMNS: usize = 16;
+const MARGIN: i32 = 4;
+
+/// Grayscale image keeping the coverage written by the renderer.
+struct GrayTarget {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl RenderTarget for GrayTarget {
+    fn write_pixel(&mut self, x: u32, y: u32, color: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let pixel = &mut self.pixels[(y * self.width + x) as usize];
+        *pixel = (*pixel).max((color >> 24) as u8);
+        true
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+/// Renders the preview of a font blob, returning the PNG file content.
+pub fn render(blob: &[u8]) -> Result<Vec<u8>, String> {
+    let font = Font::from_bytes(blob).map_err(|e| e.to_string())?;
+    let count = font.glyph_count();
+
+    let mut cell_w = 1;
+    for glyph in (0..count).filter_map(|i| font.glyph(i)) {
+        cell_w = cell_w
+            .max(glyph.advance_width)
+            .max(glyph.xmin + glyph.width);
+    }
+    let cell_w = cell_w + MARGIN;
+    let cell_h = (font.ascent - font.descent).max(1) + MARGIN;
+
+    let columns = count.clamp(1, COLUMNS);
+    let rows = count.div_ceil(COLUMNS).max(1);
+    let mut target = GrayTarget {
+        width: (columns as i32 * cell_w) as u32,
+        height: (rows as i32 * cell_h) as u32,
+        pixels: Vec::new(),
+    };
+    target.pixels = vec![0; (target.width * target.height) as usize];
+
+    let mut config = RenderConfig::default();
+    config.sdf.size = font.size as u32;
+    let renderer = Glyphr::with_config(config);
+    let align = TextAlign {
+        horizontal: AlignH::Left,
+        vertical: AlignV::Baseline,
+    };
+
+    let mut text = [0u8; 4];
+    for (i, glyph) in (0..count).filter_map(|i| font.glyph(i)).enumerate() {
+        let x = (i % COLUMNS) as i32 * cell_w + MARGIN / 2;
+        let y = (i / COLUMNS) as i32 * cell_h + MARGIN / 2 + font.ascent;
+        let text = glyph.character.encode_utf8(&mut text);
+        renderer
+            .render(&mut target, &*text, font, x, y, align)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, target.width, target.height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&target.pixels))
+        .map_err(|e| e.to_string())?;
+
+    Ok(png)
+}
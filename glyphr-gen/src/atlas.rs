@@ -0,0 +1,402 @@
+//! # atlas.rs
+//!
+//! Exports generated fonts for GPU renderers: every glyph packed in a single grayscale texture,
+//! described by AngelCode BMFont metadata (the text `.fnt` format, or its JSON form).
+
+use std::fmt::Write;
+
+use crate::Error;
+use crate::config::BitmapFormat;
+use crate::generator::GeneratedFont;
+use crate::text_image::rle_decode;
+
+/// A glyph of the atlas, with its BMFont metrics: offsets are from the top left corner of the
+/// line to the one of the glyph, y pointing down.
+#[derive(Debug, PartialEq)]
+pub struct AtlasGlyph {
+    pub character: char,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub xoffset: i32,
+    pub yoffset: i32,
+    pub xadvance: i32,
+}
+
+/// The glyphs of a font packed in a single texture.
+pub struct Atlas {
+    pub name: String,
+    pub size: i32,
+    pub line_height: i32,
+    /// Distance from the top of the line to the baseline.
+    pub base: i32,
+    /// Both sides are powers of two.
+    pub width: u32,
+    pub height: u32,
+    /// One byte per pixel: the distance field of SDF fonts, the coverage of Bitmap fonts.
+    pub pixels: Vec<u8>,
+    /// Transparent pixels between the glyphs and around the texture.
+    pub spacing: u32,
+    pub glyphs: Vec<AtlasGlyph>,
+    /// `(first, second, amount)`, the amount is added to the advance of `first`.
+    pub kerning: Vec<(char, char, i32)>,
+    /// Pixels between the field values 0 and 1 of SDF fonts.
+    pub distance_range: Option<f32>,
+}
+
+impl Atlas {
+    /// Packs the glyphs of `generated` in rows, tallest first, leaving `spacing` pixels between
+    /// them so texture filtering doesn't bleed. Color fonts can't be packed, their layers need
+    /// more than a channel.
+    pub fn new(generated: &GeneratedFont, spacing: u32) -> Result<Self, Error> {
+        let font = &generated.font;
+        if font.options.color {
+            return Err(Error::Atlas {
+                font: font.name.clone(),
+                message: "color glyphs can't be packed in a single channel texture".into(),
+            });
+        }
+
+        let mut order: Vec<usize> = (0..generated.glyphs.len()).collect();
+        order.sort_by_key(|&i| {
+            let (_, entry) = &generated.glyphs[i];
+            (-entry.height, -entry.width, entry.character)
+        });
+        let sizes: Vec<(u32, u32)> = order
+            .iter()
+            .map(|&i| {
+                let (bitmap, entry) = &generated.glyphs[i];
+                match bitmap.is_empty() {
+                    true => (0, 0),
+                    false => (entry.width as u32, entry.height as u32),
+                }
+            })
+            .collect();
+        let (width, height, positions) = pack(&sizes, spacing);
+
+        let mut pixels = vec![0; (width * height) as usize];
+        let mut glyphs = Vec::with_capacity(order.len());
+        let base = generated.ascent();
+        for (&i, ((x, y), (w, h))) in order.iter().zip(positions.into_iter().zip(sizes)) {
+            let (bitmap, entry) = &generated.glyphs[i];
+            let values: Vec<u8> = match font.format {
+                BitmapFormat::SDF { .. } => rle_decode(bitmap).collect(),
+                BitmapFormat::Bitmap { .. } => (0..(w * h) as usize)
+                    .map(|i| match bitmap.get(i / 8) {
+                        Some(byte) if byte & (0x80 >> (i % 8)) != 0 => 255,
+                        _ => 0,
+                    })
+                    .collect(),
+            };
+            for (row, line) in values
+                .chunks(w.max(1) as usize)
+                .take(h as usize)
+                .enumerate()
+            {
+                let start = ((y + row as u32) * width + x) as usize;
+                pixels[start..start + line.len()].copy_from_slice(line);
+            }
+
+            glyphs.push(AtlasGlyph {
+                character: entry.character,
+                x,
+                y,
+                width: w,
+                height: h,
+                xoffset: entry.xmin,
+                yoffset: base - entry.ymin - entry.height,
+                xadvance: entry.advance_width,
+            });
+        }
+        glyphs.sort_by_key(|glyph| glyph.character);
+
+        let chars: Vec<char> = glyphs.iter().map(|glyph| glyph.character).collect();
+        Ok(Self {
+            name: font.name.clone(),
+            size: font.px,
+            line_height: base - generated.descent() + font.font.get_line_gap(font.px as f32),
+            base,
+            width,
+            height,
+            pixels,
+            spacing,
+            glyphs,
+            kerning: font.font.kerning(&chars, font.px as f32),
+            distance_range: match font.format {
+                BitmapFormat::SDF { spread, .. } => Some(2.0 * spread),
+                BitmapFormat::Bitmap { .. } => None,
+            },
+        })
+    }
+
+    /// The texture as a grayscale PNG file.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .expect("writing to memory can't fail");
+        png
+    }
+
+    /// BMFont text metadata, `page` is the file name of the texture. The `.notdef` glyph keeps
+    /// its `NOTDEF` codepoint as id.
+    pub fn to_fnt(&self, page: &str) -> String {
+        let mut out = String::new();
+        let s = self.spacing;
+        // The texture is grayscale: the glyph is in the color channels and alpha is always 1.
+        let _ = writeln!(
+            out,
+            "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 \
+             smooth=1 aa=1 padding=0,0,0,0 spacing={s},{s}",
+            self.name, self.size
+        );
+        let _ = writeln!(
+            out,
+            "common lineHeight={} base={} scaleW={} scaleH={} pages=1 packed=0 alphaChnl=4 \
+             redChnl=0 greenChnl=0 blueChnl=0",
+            self.line_height, self.base, self.width, self.height
+        );
+        let _ = writeln!(out, "page id=0 file=\"{page}\"");
+        let _ = writeln!(out, "chars count={}", self.glyphs.len());
+        for g in &self.glyphs {
+            let _ = writeln!(
+                out,
+                "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} \
+                 page=0 chnl=15",
+                g.character as u32, g.x, g.y, g.width, g.height, g.xoffset, g.yoffset, g.xadvance
+            );
+        }
+        let _ = writeln!(out, "kernings count={}", self.kerning.len());
+        for (first, second, amount) in &self.kerning {
+            let _ = writeln!(
+                out,
+                "kerning first={} second={} amount={amount}",
+                *first as u32, *second as u32
+            );
+        }
+        out
+    }
+
+    /// BMFont JSON metadata, as written by `msdf-bmfont-xml`, with a `distanceField` entry for
+    /// SDF fonts.
+    pub fn to_json(&self, page: &str) -> String {
+        let chars: Vec<String> = self
+            .glyphs
+            .iter()
+            .map(|g| {
+                format!(
+                    "    {{\"id\": {}, \"char\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \
+                     \"height\": {}, \"xoffset\": {}, \"yoffset\": {}, \"xadvance\": {}, \
+                     \"page\": 0, \"chnl\": 15}}",
+                    g.character as u32,
+                    json_string(&g.character.to_string()),
+                    g.x,
+                    g.y,
+                    g.width,
+                    g.height,
+                    g.xoffset,
+                    g.yoffset,
+                    g.xadvance
+                )
+            })
+            .collect();
+        let kernings: Vec<String> = self
+            .kerning
+            .iter()
+            .map(|(first, second, amount)| {
+                format!(
+                    "    {{\"first\": {}, \"second\": {}, \"amount\": {amount}}}",
+                    *first as u32, *second as u32
+                )
+            })
+            .collect();
+        let distance_field = match self.distance_range {
+            Some(range) => format!(
+                "  \"distanceField\": {{\"fieldType\": \"sdf\", \"distanceRange\": {range}}},\n"
+            ),
+            None => String::new(),
+        };
+        let s = self.spacing;
+
+        format!(
+            "{{\n  \"pages\": [{}],\n  \"chars\": [\n{}\n  ],\n  \"info\": {{\"face\": {}, \
+             \"size\": {}, \"bold\": 0, \"italic\": 0, \"unicode\": 1, \"stretchH\": 100, \
+             \"smooth\": 1, \"aa\": 1, \"padding\": [0, 0, 0, 0], \"spacing\": [{s}, {s}]}},\n  \
+             \"common\": {{\"lineHeight\": {}, \"base\": {}, \"scaleW\": {}, \"scaleH\": {}, \
+             \"pages\": 1, \"packed\": 0, \"alphaChnl\": 4, \"redChnl\": 0, \"greenChnl\": 0, \
+             \"blueChnl\": 0}},\n{distance_field}  \"kernings\": [\n{}\n  ]\n}}\n",
+            json_string(page),
+            chars.join(",\n"),
+            json_string(&self.name),
+            self.size,
+            self.line_height,
+            self.base,
+            self.width,
+            self.height,
+            kernings.join(",\n"),
+        )
+    }
+}
+
+/// Places boxes of `sizes` in rows, in the given order, in the smallest power of two texture
+/// not taller than wide. Returns its size and the position of every box.
+fn pack(sizes: &[(u32, u32)], spacing: u32) -> (u32, u32, Vec<(u32, u32)>) {
+    let area: u32 = sizes
+        .iter()
+        .map(|(w, h)| (w + spacing) * (h + spacing))
+        .sum();
+    let widest = sizes.iter().map(|(w, _)| *w).max().unwrap_or(0);
+    let mut width = ((area as f64).sqrt().ceil() as u32)
+        .max(widest + 2 * spacing)
+        .max(1)
+        .next_power_of_two();
+
+    loop {
+        let mut positions = Vec::with_capacity(sizes.len());
+        let (mut x, mut y, mut row_height) = (spacing, spacing, 0);
+        for &(w, h) in sizes {
+            if w == 0 || h == 0 {
+                positions.push((0, 0));
+                continue;
+            }
+            if x + w + spacing > width {
+                (x, y, row_height) = (spacing, y + row_height + spacing, 0);
+            }
+            positions.push((x, y));
+            x += w + spacing;
+            row_height = row_height.max(h);
+        }
+        let height = (y + row_height + spacing).max(1).next_power_of_two();
+        if height <= width {
+            return (width, height, positions);
+        }
+        width *= 2;
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::config::{FontLoaded, GenerationOptions, load_font};
+
+    fn generate(
+        path: &str,
+        px: i32,
+        chars: &str,
+        format: BitmapFormat,
+        color: bool,
+    ) -> GeneratedFont {
+        let path = format!("{}/tests/fonts/{path}", env!("CARGO_MANIFEST_DIR"));
+        GeneratedFont::new(FontLoaded {
+            name: "ATLAS".into(),
+            font: Rc::new(load_font(path).unwrap()),
+            px,
+            char_range: chars.chars().collect(),
+            format,
+            options: GenerationOptions {
+                color,
+                ..Default::default()
+            },
+            family: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_atlas_keeps_glyph_pixels() {
+        let format = BitmapFormat::Bitmap {
+            spread: 1.0,
+            padding: 0,
+        };
+        let font = generate("bitmap.bdf", 10, " Ag", format, false);
+        let atlas = Atlas::new(&font, 1).unwrap();
+        assert!(atlas.width.is_power_of_two() && atlas.height.is_power_of_two());
+        assert_eq!((atlas.base, atlas.line_height), (8, 10));
+        assert_eq!(atlas.distance_range, None);
+
+        let [space, a, g] = &atlas.glyphs[..] else {
+            panic!("expected three glyphs");
+        };
+        assert_eq!((space.width, space.xadvance), (0, 6));
+        assert_eq!((a.width, a.height, a.yoffset), (5, 7, 1));
+        assert_eq!((g.xoffset, g.yoffset), (1, 3));
+        // 'A' and 'g' are as tall, so they share the first row.
+        assert_eq!((a.y, g.y), (1, 1));
+        assert!(a.x + a.width < g.x || g.x + g.width < a.x);
+        // `.###.`, the first row of 'A'.
+        let row = (a.y * atlas.width + a.x) as usize;
+        assert_eq!(&atlas.pixels[row..row + 5], [0, 255, 255, 255, 0]);
+
+        let png = atlas.to_png();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[test]
+    fn test_metadata_has_kerning() {
+        let format = BitmapFormat::SDF {
+            spread: 2.0,
+            padding: 1,
+        };
+        let font = generate("kern.ttf", 20, "A", format, false);
+        let atlas = Atlas::new(&font, 1).unwrap();
+        assert_eq!(atlas.kerning, [('A', 'A', -2)]);
+        assert_eq!(atlas.distance_range, Some(4.0));
+
+        let fnt = atlas.to_fnt("ATLAS.png");
+        let a = &atlas.glyphs[0];
+        assert!(fnt.starts_with("info face=\"ATLAS\" size=20 "));
+        assert!(fnt.contains("page id=0 file=\"ATLAS.png\"\nchars count=1\n"));
+        assert!(fnt.contains(&format!(
+            "char id=65 x={} y={} width={} height={} ",
+            a.x, a.y, a.width, a.height
+        )));
+        assert!(fnt.ends_with("kernings count=1\nkerning first=65 second=65 amount=-2\n"));
+
+        let json = atlas.to_json("ATLAS.png");
+        assert!(json.contains("\"pages\": [\"ATLAS.png\"]"));
+        assert!(json.contains("{\"id\": 65, \"char\": \"A\", "));
+        assert!(json.contains("\"distanceField\": {\"fieldType\": \"sdf\", \"distanceRange\": 4}"));
+        assert!(json.contains("{\"first\": 65, \"second\": 65, \"amount\": -2}"));
+    }
+
+    #[test]
+    fn test_color_fonts_are_rejected() {
+        let format = BitmapFormat::Bitmap {
+            spread: 1.0,
+            padding: 0,
+        };
+        let font = generate("color.ttf", 16, "A", format, true);
+        assert!(matches!(Atlas::new(&font, 1), Err(Error::Atlas { .. })));
+    }
+
+    #[test]
+    fn test_pack() {
+        let (width, height, positions) = pack(&[(6, 5), (6, 4), (0, 0), (3, 3)], 1);
+        assert_eq!((width, height), (16, 16));
+        assert_eq!(positions, [(1, 1), (8, 1), (0, 0), (1, 7)]);
+        assert_eq!(pack(&[], 0), (1, 1, vec![]));
+    }
+}
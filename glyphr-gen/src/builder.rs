@@ -0,0 +1,207 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+use crate::renderer;
+use crate::toml_parser::{TomlConfig, TomlFont};
+
+/// Generates fonts from a build script, with the same inputs accepted by the macros.
+///
+/// ```no_run
+/// // build.rs
+/// glyphr_gen::Builder::new()
+///     .toml("fonts/fonts.toml")
+///     .write_to_out_dir("fonts.rs")
+///     .unwrap();
+/// ```
+/// The fonts are then pulled in the crate with
+/// `include!(concat!(env!("OUT_DIR"), "/fonts.rs"));`.
+///
+/// Relative paths are resolved against the current directory, which for build scripts is the
+/// directory of the package's `Cargo.toml`.
+pub struct Builder {
+    tomls: Vec<PathBuf>,
+    fonts: Vec<TomlFont>,
+    rerun_if_changed: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self {
+            tomls: Vec::new(),
+            fonts: Vec::new(),
+            rerun_if_changed: true,
+        }
+    }
+
+    /// Adds every font of a `toml` file (same schema as `generate_fonts_from_toml!`).
+    pub fn toml(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tomls.push(path.into());
+        self
+    }
+
+    /// Adds a single font.
+    pub fn font(mut self, font: TomlFont) -> Self {
+        self.fonts.push(font);
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-changed` for every file read (enabled by default).
+    pub fn rerun_if_changed(mut self, enabled: bool) -> Self {
+        self.rerun_if_changed = enabled;
+        self
+    }
+
+    /// Generates the Rust code of all the fonts.
+    pub fn generate(&self) -> Result<String, Error> {
+        let mut config = TomlConfig { font: Vec::new() };
+        for path in &self.tomls {
+            config.font.extend(TomlConfig::from_file(path)?.font);
+            self.track(path);
+        }
+        config.font.extend(self.fonts.iter().cloned());
+
+        for font in &config.font {
+            self.track(Path::new(&font.path));
+            for text in &font.characters_from {
+                self.track(Path::new(text));
+            }
+            for icon in font.icons.values() {
+                self.track(Path::new(&icon.path));
+            }
+        }
+
+        renderer::render(config)
+    }
+
+    /// Generates the fonts and writes them to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        fs::write(path, self.generate()?).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Generates the fonts and writes them to `$OUT_DIR/file_name`, returning the full path.
+    pub fn write_to_out_dir(&self, file_name: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let out_dir = env::var_os("OUT_DIR").ok_or_else(|| Error::Io {
+            path: PathBuf::from("$OUT_DIR"),
+            source: std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "OUT_DIR is not set, is this running from a build script?",
+            ),
+        })?;
+        let path = Path::new(&out_dir).join(file_name);
+        self.write(&path)?;
+        Ok(path)
+    }
+
+    fn track(&self, path: &Path) {
+        if self.rerun_if_changed {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BitmapFormat, IconSource};
+    use crate::toml_parser::OneOrMany;
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+
+    fn demo(characters: &str) -> TomlFont {
+        TomlFont {
+            name: "DEMO".into(),
+            path: DEMO_TTF.into(),
+            face: Default::default(),
+            size: OneOrMany::One(16),
+            characters: characters.into(),
+            characters_from: Vec::new(),
+            format: OneOrMany::One(BitmapFormat::Bitmap {
+                spread: 2.0,
+                padding: 0,
+            }),
+            fill_rule: Default::default(),
+            missing: Default::default(),
+            include_notdef: false,
+            color: false,
+            instance: None,
+            axes: Default::default(),
+            icons: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_generate_font() {
+        let code = Builder::new()
+            .rerun_if_changed(false)
+            .font(demo("A"))
+            .generate()
+            .unwrap();
+        assert!(code.contains("pub static DEMO: ::glyphr::Font"));
+        assert!(code.contains("/// Characters: `A`"));
+    }
+
+    #[test]
+    fn test_generate_icons() {
+        let icon = |file: &str, codepoint| IconSource {
+            path: format!("{}/tests/icons/{file}", env!("CARGO_MANIFEST_DIR")),
+            codepoint,
+            baseline: 0,
+            advance: None,
+        };
+        let mut font = demo("A");
+        font.icons = [
+            ("wifi".into(), icon("wifi.svg", 0xE000)),
+            ("battery".into(), icon("battery.svg", 0xE001)),
+        ]
+        .into();
+        let code = Builder::new()
+            .rerun_if_changed(false)
+            .font(font.clone())
+            .generate()
+            .unwrap();
+        assert!(code.contains("pub const ICON_WIFI: char = '\\u{e000}';"));
+        assert!(code.contains("pub const ICON_BATTERY: char = '\\u{e001}';"));
+        assert!(code.contains("character: '\u{e001}'"));
+
+        font.icons
+            .insert("broken".into(), icon("missing.svg", 0xE002));
+        let err = Builder::new()
+            .rerun_if_changed(false)
+            .font(font)
+            .generate()
+            .unwrap_err();
+        assert!(matches!(err, Error::Icon { path, .. } if path.ends_with("missing.svg")));
+    }
+
+    #[test]
+    fn test_missing_glyphs_are_an_error() {
+        let err = Builder::new()
+            .rerun_if_changed(false)
+            .font(demo("ABC"))
+            .generate()
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingGlyphs { characters, .. } if characters == ['B', 'C']));
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let err = Builder::new()
+            .rerun_if_changed(false)
+            .toml("does/not/exist.toml")
+            .generate()
+            .unwrap_err();
+        assert!(matches!(err, Error::Io { .. }));
+    }
+}
@@ -0,0 +1,209 @@
+use sha2::{Digest, Sha256};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::FontLoaded;
+
+/// Revision of the generated output, part of every key. The generator's own source isn't
+/// hashed, so bump this whenever a change to it makes the same inputs generate different code.
+const REVISION: u32 = 1;
+
+/// Persistent cache of generated code, so fonts whose inputs didn't change are not generated
+/// again on every build.
+///
+/// Entries are keyed by a hash of everything that affects the output: font file, characters,
+/// size, format parameters, generation options, the generator version, `REVISION` and the
+/// templates. A changed generator that keeps both the version and the revision (a local patch or
+/// a git dependency, say) still hits the entries of the old one: clear the cache directory then.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Stores entries in `dir`, which is created when the first entry is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache used by the macros and build scripts: `$GLYPHR_CACHE_DIR` if set (an empty
+    /// value disables caching), otherwise `glyphr-cache` in the target directory of the
+    /// package being built. Outside of cargo there's no cache.
+    pub fn from_env() -> Option<Self> {
+        if let Some(dir) = env::var_os("GLYPHR_CACHE_DIR") {
+            return (!dir.is_empty()).then(|| Self::new(dir));
+        }
+
+        let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR")?);
+        let target_dir = target_dir(
+            &manifest_dir,
+            env::var_os("CARGO_TARGET_DIR").as_deref(),
+            env::var_os("OUT_DIR").as_deref(),
+        )?;
+        Some(Self::new(target_dir.join("glyphr-cache")))
+    }
+
+    /// Hashes the inputs of `fonts` into the key of their generated code.
+    pub fn key(fonts: &[FontLoaded]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(REVISION.to_le_bytes());
+        // Template changes between releases must not return stale code either.
+        hasher.update(include_str!("../templates/fonts.rs.j2"));
+        hasher.update(include_str!("../templates/family.rs.j2"));
+        for font in fonts {
+            hasher.update(font.font.digest());
+            hasher.update(format!(
+                "{}\0{:?}\0{}\0{:?}\0{:?}\0",
+                font.name, font.family, font.px, font.format, font.options
+            ));
+            for c in &font.char_range {
+                hasher.update((*c as u32).to_le_bytes());
+            }
+            hasher.update([0xff; 4]);
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.path(key)).ok()
+    }
+
+    /// Stores `code` under `key`. Failing to write the cache is not an error, the code will
+    /// just be generated again next time.
+    pub fn put(&self, key: &str, code: &str) {
+        // Write to a temporary file first, so concurrent builds never read a partial entry.
+        let tmp = self.dir.join(format!("{key}.{}.tmp", std::process::id()));
+        let written = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, code))
+            .and_then(|_| fs::rename(&tmp, self.path(key)));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        Path::new(&self.dir).join(format!("{key}.rs"))
+    }
+}
+
+/// Where cargo builds the package of `manifest_dir`: `CARGO_TARGET_DIR`, relative to the
+/// directory cargo runs in, or `target` at the root of the workspace. `OUT_DIR`, when set, is
+/// the fallback for a target directory that can't be found.
+fn target_dir(
+    manifest_dir: &Path,
+    cargo_target_dir: Option<&OsStr>,
+    out_dir: Option<&OsStr>,
+) -> Option<PathBuf> {
+    if let Some(dir) = cargo_target_dir {
+        return std::path::absolute(dir).ok();
+    }
+
+    // Don't look above the workspace root, a `target` there belongs to something else.
+    let root = manifest_dir
+        .ancestors()
+        .find(|dir| is_workspace_root(dir))
+        .unwrap_or(manifest_dir);
+    let target = root.join("target");
+    match target.is_dir() {
+        true => Some(target),
+        false => out_dir.map(PathBuf::from),
+    }
+}
+
+fn is_workspace_root(dir: &Path) -> bool {
+    fs::read_to_string(dir.join("Cargo.toml")).is_ok_and(|manifest| {
+        manifest
+            .lines()
+            .any(|line| line.trim_start().starts_with("[workspace"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BitmapFormat, expand_family, load_font};
+    use crate::renderer;
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+
+    fn demo(size: i32, characters: &[char]) -> Vec<FontLoaded> {
+        expand_family(
+            "DEMO",
+            load_font(DEMO_TTF).unwrap(),
+            &[size],
+            &[BitmapFormat::Bitmap {
+                spread: 2.0,
+                padding: 0,
+            }],
+            characters.to_vec(),
+            Default::default(),
+        )
+    }
+
+    fn temp_cache(name: &str) -> Cache {
+        let dir = env::temp_dir().join(format!("glyphr-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::new(dir)
+    }
+
+    #[test]
+    fn test_key_depends_on_inputs() {
+        let key = Cache::key(&demo(16, &['A']));
+        assert_eq!(key, Cache::key(&demo(16, &['A'])));
+        assert_ne!(key, Cache::key(&demo(17, &['A'])));
+        assert_ne!(key, Cache::key(&demo(16, &['A', ' '])));
+    }
+
+    #[test]
+    fn test_target_dir() {
+        let dir = env::temp_dir().join(format!("glyphr-target-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let member = dir.join("workspace/member");
+        fs::create_dir_all(&member).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        let out_dir = OsStr::new("/out");
+
+        // The `target` above the workspace isn't the one of the package.
+        fs::write(dir.join("workspace/Cargo.toml"), "[workspace]\n").unwrap();
+        assert_eq!(
+            target_dir(&member, None, Some(out_dir)),
+            Some(PathBuf::from("/out"))
+        );
+        fs::create_dir(dir.join("workspace/target")).unwrap();
+        assert_eq!(
+            target_dir(&member, None, Some(out_dir)),
+            Some(dir.join("workspace/target"))
+        );
+
+        // Without a workspace the package is the root.
+        fs::write(dir.join("workspace/Cargo.toml"), "[package]\n").unwrap();
+        assert_eq!(target_dir(&member, None, None), None);
+
+        let relative = target_dir(&member, Some(OsStr::new("build")), None).unwrap();
+        assert_eq!(relative, env::current_dir().unwrap().join("build"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_hit_returns_stored_code() {
+        let cache = temp_cache("hit");
+        let code = renderer::render_fonts(demo(16, &['A']), Some(&cache)).unwrap();
+        let key = Cache::key(&demo(16, &['A']));
+        assert_eq!(cache.get(&key).as_deref(), Some(code.as_str()));
+
+        // A hit doesn't generate anything, whatever is stored is returned.
+        cache.put(&key, "// cached");
+        let code = renderer::render_fonts(demo(16, &['A']), Some(&cache)).unwrap();
+        assert_eq!(code, "// cached");
+
+        let _ = fs::remove_dir_all(&cache.dir);
+    }
+}
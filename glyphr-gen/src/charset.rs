@@ -0,0 +1,446 @@
+//! Grammar of the `characters` pattern, shared by the macros, the `toml` and the CLI.
+//!
+//! - `a` is a character, `a-z` every character from `a` to `z`
+//! - `\-`, `\~`, `\{`, `\\` escape characters with a meaning in the pattern, `\u{2190}` is a
+//!   codepoint in hex
+//! - `U+2190` is a codepoint too, `U+2190-U+21FF` a range of codepoints
+//! - `{ascii}`, `{latin1}`, `{digits}`, `{greek}` and `{box-drawing}` are presets
+//! - `~` removes everything that follows from the set: `{latin1}~{digits}`
+//!
+//! A `-` at the start or at the end of the pattern is a plain character.
+//!
+//! Characters can also come from text files (`characters_from`), see `chars_from_files`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::Error;
+use crate::generator::NOTDEF;
+
+/// Expands a `characters` pattern into the sorted list of its characters.
+pub fn parse_char_set(pattern: &str) -> Result<Vec<char>, Error> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        pos: 0,
+    };
+    parser
+        .parse()
+        .map(|set| set.into_iter().collect())
+        .map_err(|(position, message)| Error::Charset {
+            pattern: pattern.to_string(),
+            position: position + 1,
+            message,
+        })
+}
+
+/// The characters of `pattern` together with every character of the files at `paths`.
+pub fn collect_char_set(pattern: &str, paths: &[impl AsRef<Path>]) -> Result<Vec<char>, Error> {
+    let mut set: BTreeSet<char> = parse_char_set(pattern)?.into_iter().collect();
+    set.extend(chars_from_files(paths)?);
+    Ok(set.into_iter().collect())
+}
+
+/// Every distinct character of the files at `paths`, sorted. Gettext catalogs (`.po`, `.pot`)
+/// only contribute the text of their messages, any other file all of its content. Control
+/// characters (line breaks, tabs...) and byte order marks are left out.
+pub fn chars_from_files(paths: &[impl AsRef<Path>]) -> Result<Vec<char>, Error> {
+    let mut set = BTreeSet::new();
+    for path in paths {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "po" || ext == "pot")
+        {
+            for message in po_messages(&content) {
+                set.extend(message.chars());
+            }
+        } else {
+            set.extend(content.chars());
+        }
+    }
+    set.retain(|c| !c.is_control() && *c != '\u{feff}');
+    Ok(set.into_iter().collect())
+}
+
+/// Writes `chars` back as a pattern, with runs of consecutive characters as ranges.
+pub fn format_char_set(chars: &[char]) -> String {
+    let mut pattern = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut end = i;
+        while chars
+            .get(end + 1)
+            .is_some_and(|&next| next as u32 == chars[end] as u32 + 1)
+        {
+            end += 1;
+        }
+
+        push_escaped(&mut pattern, chars[i]);
+        match end - i {
+            0 => {}
+            1 => push_escaped(&mut pattern, chars[end]),
+            _ => {
+                pattern.push('-');
+                push_escaped(&mut pattern, chars[end]);
+            }
+        }
+        i = end + 1;
+    }
+    pattern
+}
+
+fn push_escaped(pattern: &mut String, c: char) {
+    match c {
+        // `+` only to never read `U+` as a codepoint.
+        '-' | '~' | '{' | '\\' | '+' => {
+            pattern.push('\\');
+            pattern.push(c);
+        }
+        // Backticks would end the code span of the doc comments the pattern is shown in.
+        c if c.is_control() || c == '`' => pattern.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        c => pattern.push(c),
+    }
+}
+
+/// Text of the messages of a gettext catalog: `msgid`, `msgid_plural` and `msgstr` strings,
+/// without contexts and the metadata of the header entry.
+fn po_messages(content: &str) -> Vec<String> {
+    // Keywords with their string, continuation lines already appended.
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('"') {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push_str(&po_unquote(line));
+            }
+        } else if let Some((keyword, value)) = line.split_once(char::is_whitespace)
+            && keyword.starts_with("msg")
+        {
+            fields.push((keyword, po_unquote(value.trim())));
+        }
+    }
+
+    let mut messages = Vec::new();
+    let mut header = false;
+    for (keyword, value) in fields {
+        match keyword {
+            "msgctxt" => {}
+            "msgid" => {
+                header = value.is_empty();
+                messages.push(value);
+            }
+            _ if header => {}
+            _ => messages.push(value),
+        }
+    }
+    messages
+}
+
+/// Content of a quoted gettext string, with its escapes resolved.
+fn po_unquote(quoted: &str) -> String {
+    let inner = quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(quoted);
+
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+/// Presets usable as `{name}`.
+const PRESETS: &[(&str, &[(char, char)])] = &[
+    ("ascii", &[(' ', '~')]),
+    ("latin1", &[(' ', '~'), ('\u{a0}', '\u{ff}')]),
+    ("digits", &[('0', '9')]),
+    (
+        "greek",
+        &[
+            ('\u{391}', '\u{3a1}'),
+            ('\u{3a3}', '\u{3a9}'),
+            ('\u{3b1}', '\u{3c9}'),
+        ],
+    ),
+    ("box-drawing", &[('\u{2500}', '\u{257f}')]),
+];
+
+/// Position (in chars) and description of a malformed pattern.
+type ParseError = (usize, String);
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse(&mut self) -> Result<BTreeSet<char>, ParseError> {
+        let mut set = self.term()?;
+        while self.peek(0) == Some('~') {
+            self.pos += 1;
+            for c in self.term()? {
+                set.remove(&c);
+            }
+        }
+        Ok(set)
+    }
+
+    /// Characters up to the next `~`.
+    fn term(&mut self) -> Result<BTreeSet<char>, ParseError> {
+        let mut set = BTreeSet::new();
+        while let Some(c) = self.peek(0) {
+            if c == '~' {
+                break;
+            }
+            if c == '{' {
+                set.extend(self.preset()?);
+                continue;
+            }
+
+            let start_pos = self.pos;
+            let start = self.item()?;
+            if self.peek(0) == Some('-') && !matches!(self.peek(1), None | Some('~')) {
+                self.pos += 1;
+                if self.peek(0) == Some('{') {
+                    return Err((self.pos, "a range can't end with a preset".into()));
+                }
+                let end = self.item()?;
+                if end < start {
+                    return Err((start_pos, format!("range {start:?}-{end:?} goes backwards")));
+                }
+                set.extend(start..=end);
+            } else {
+                set.insert(start);
+            }
+        }
+        Ok(set)
+    }
+
+    /// A single character: literal, escaped or `U+XXXX`. `NOTDEF` is rejected, the `.notdef`
+    /// glyph is stored as it.
+    fn item(&mut self) -> Result<char, ParseError> {
+        let start = self.pos;
+        let c = self.raw_item()?;
+        if c == NOTDEF {
+            return Err((
+                start,
+                "U+10FFFF is reserved for the .notdef glyph, use include_notdef".into(),
+            ));
+        }
+        Ok(c)
+    }
+
+    fn raw_item(&mut self) -> Result<char, ParseError> {
+        let start = self.pos;
+        let c = self.next().ok_or((start, "expected a character".into()))?;
+        match c {
+            '\\' => match self.next() {
+                None => Err((start, "'\\' at the end of the pattern".into())),
+                Some('u') if self.peek(0) == Some('{') => {
+                    self.pos += 1;
+                    let digits = self.hex_digits();
+                    if self.next() != Some('}') {
+                        return Err((start, "expected '}' to close '\\u{'".into()));
+                    }
+                    codepoint(&digits).ok_or((start, format!("invalid codepoint '{digits}'")))
+                }
+                Some(escaped) => Ok(escaped),
+            },
+            'U' if self.peek(0) == Some('+')
+                && self.peek(1).is_some_and(|c| c.is_ascii_hexdigit()) =>
+            {
+                self.pos += 1;
+                let digits = self.hex_digits();
+                codepoint(&digits).ok_or((start, format!("invalid codepoint 'U+{digits}'")))
+            }
+            c => Ok(c),
+        }
+    }
+
+    fn preset(&mut self) -> Result<BTreeSet<char>, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut name = String::new();
+        loop {
+            match self.next() {
+                Some('}') => break,
+                Some(c) => name.push(c),
+                None => return Err((start, "'{' is never closed".into())),
+            }
+        }
+
+        let (_, ranges) = PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = PRESETS.iter().map(|(preset, _)| *preset).collect();
+                (
+                    start,
+                    format!(
+                        "unknown preset '{name}', expected one of {}",
+                        names.join(", ")
+                    ),
+                )
+            })?;
+        Ok(ranges
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .collect())
+    }
+
+    fn hex_digits(&mut self) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_hexdigit()) {
+            digits.push(c);
+            self.pos += 1;
+        }
+        digits
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        Some(c)
+    }
+}
+
+fn codepoint(digits: &str) -> Option<char> {
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(pattern: &str) -> String {
+        parse_char_set(pattern).unwrap().into_iter().collect()
+    }
+
+    fn error(pattern: &str) -> (usize, String) {
+        match parse_char_set(pattern) {
+            Err(Error::Charset {
+                position, message, ..
+            }) => (position, message),
+            _ => panic!("'{pattern}' should be invalid"),
+        }
+    }
+
+    #[test]
+    fn test_parse_char_set() {
+        let input = "A-Da-d0-3";
+        let output = parse_char_set(input).unwrap();
+        let predicted_output = ['0', '1', '2', '3', 'A', 'B', 'C', 'D', 'a', 'b', 'c', 'd'];
+        assert_eq!(output, predicted_output);
+    }
+
+    #[test]
+    fn test_escapes_and_codepoints() {
+        assert_eq!(chars(r"a\-c"), "-ac");
+        assert_eq!(chars(r"\u{2190}\~\\"), "\\~\u{2190}");
+        assert_eq!(chars("U+2190-U+2193"), "\u{2190}\u{2191}\u{2192}\u{2193}");
+        assert_eq!(chars(r"x-\u{7a}"), "xyz");
+        assert_eq!(chars("-a-"), "-a");
+        assert_eq!(chars("U+"), "+U");
+    }
+
+    #[test]
+    fn test_presets_and_subtraction() {
+        assert_eq!(chars("{digits}"), "0123456789");
+        assert_eq!(parse_char_set("{ascii}").unwrap().len(), 95);
+        assert_eq!(parse_char_set("{latin1}").unwrap().len(), 95 + 96);
+        assert_eq!(parse_char_set("{greek}").unwrap().len(), 24 + 25);
+        assert_eq!(parse_char_set("{box-drawing}").unwrap().len(), 128);
+        assert_eq!(chars("{digits}~3-7"), "01289");
+        assert_eq!(chars("a-f~b~d-e"), "acf");
+    }
+
+    #[test]
+    fn test_format_char_set_roundtrip() {
+        let chars = parse_char_set(r"a-eg+\-\~U{greek}`\u{7}").unwrap();
+        let pattern = format_char_set(&chars);
+        assert!(pattern.starts_with(r"\u{7}\+\-U\u{60}-eg\~"));
+        assert_eq!(parse_char_set(&pattern).unwrap(), chars);
+        assert_eq!(format_char_set(&['a', 'b', 'x']), "abx");
+    }
+
+    #[test]
+    fn test_chars_from_files() {
+        let dir = std::env::temp_dir().join(format!("glyphr-charset-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let po = dir.join("de.po");
+        let txt = dir.join("strings.txt");
+        fs::write(
+            &po,
+            concat!(
+                "# Kommentar\n",
+                "msgid \"\"\n",
+                "msgstr \"\"\n",
+                "\"Content-Type: text/plain; charset=UTF-8\\n\"\n",
+                "\n",
+                "msgctxt \"menu\"\n",
+                "msgid \"Open\"\n",
+                "msgstr \"\u{d6}ffnen \\\"\"\n",
+                "\"\u{2026}\"\n",
+            ),
+        )
+        .unwrap();
+        fs::write(&txt, "\u{feff}z\r\nz\t\n").unwrap();
+
+        let chars: String = chars_from_files(&[&po, &txt])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(chars, " \"Oefnpz\u{d6}\u{2026}");
+        let chars: String = collect_char_set("a-c", &[&txt])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(chars, "abcz");
+        assert!(matches!(
+            chars_from_files(&[dir.join("missing.txt")]),
+            Err(Error::Io { .. })
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_malformed_patterns() {
+        assert_eq!(error("ab{emoji}").0, 3);
+        assert!(error("ab{emoji}").1.contains("unknown preset 'emoji'"));
+        assert_eq!(error("z-a").0, 1);
+        assert_eq!(error("{digits").0, 1);
+        assert_eq!(error(r"a\").0, 2);
+        assert_eq!(error(r"\u{110000}").0, 1);
+        assert_eq!(error(r"\u{41").0, 1);
+        assert_eq!(error("a-{digits}").0, 3);
+        assert_eq!(error(r"a\u{10ffff}").0, 2);
+        assert!(
+            error("U+10000-U+10FFFF")
+                .1
+                .contains("reserved for the .notdef glyph")
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::Error;
+pub use crate::charset::{collect_char_set, parse_char_set};
+pub use crate::generator::font::FaceSelector;
+use crate::generator::font::{Font, FontSettings};
+use crate::generator::{raster, woff};
+
+/// Trait used internally to define which struct can define a font.
+pub trait ToFontLoaded {
+    fn to_font_loaded(&self) -> Result<Vec<FontLoaded>, Error>;
+}
+
+/// Reads and parses a font file (TTF/OTF, or either wrapped in WOFF/WOFF2).
+pub fn load_font(path: impl AsRef<Path>) -> Result<Font, Error> {
+    load_font_with(path, &FaceSelector::default(), FontSettings::default())
+}
+
+/// Reads and parses a font file, loading the `face` of a collection with the variation given by
+/// `settings` (its `collection_index` is replaced by the one of `face`).
+pub fn load_font_with(
+    path: impl AsRef<Path>,
+    face: &FaceSelector,
+    settings: FontSettings,
+) -> Result<Font, Error> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    load_font_from_bytes(&data, path, face, settings)
+}
+
+/// Like `load_font_with`, for a font file already in memory. `path` only names it in errors.
+pub fn load_font_from_bytes(
+    data: &[u8],
+    path: impl AsRef<Path>,
+    face: &FaceSelector,
+    mut settings: FontSettings,
+) -> Result<Font, Error> {
+    let font_error = |source| Error::FontParse {
+        path: path.as_ref().to_path_buf(),
+        source,
+    };
+    let data = woff::decode(data).map_err(font_error)?;
+    settings.collection_index = face.resolve(&data).map_err(font_error)?;
+    Font::from_bytes(&*data, settings).map_err(font_error)
+}
+
+/// An SVG or PNG file imported as the glyph of `codepoint`, e.g. in the Private Use Area from
+/// U+E000.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct IconSource {
+    pub path: String,
+    pub codepoint: u32,
+    /// PNG only: rows of the image below the baseline.
+    #[serde(default)]
+    pub baseline: i32,
+    /// PNG only: advance in pixels, the width of the image if not given.
+    #[serde(default)]
+    pub advance: Option<i32>,
+}
+
+/// Imports the SVG and PNG `icons` (by name) as glyphs of `font`, and adds their codepoints to
+/// the sorted `char_range`.
+pub fn add_icons<'a>(
+    font: &mut Font,
+    icons: impl IntoIterator<Item = (&'a String, &'a IconSource)>,
+    char_range: &mut Vec<char>,
+) -> Result<(), Error> {
+    let mut chars: BTreeSet<char> = char_range.drain(..).collect();
+    for (name, icon) in icons {
+        let icon_error = |message: String| Error::Icon {
+            path: icon.path.clone().into(),
+            message,
+        };
+        let codepoint = char::from_u32(icon.codepoint)
+            .ok_or_else(|| icon_error(format!("invalid codepoint {:#x}", icon.codepoint)))?;
+        let data = fs::read(&icon.path).map_err(|err| icon_error(err.to_string()))?;
+        if raster::is_png(&data) {
+            font.add_png_icon(name, codepoint, &data, icon.baseline, icon.advance)
+                .map_err(icon_error)?;
+        } else if icon.baseline != 0 || icon.advance.is_some() {
+            return Err(icon_error(
+                "baseline and advance only apply to PNG images".into(),
+            ));
+        } else {
+            let svg = String::from_utf8(data)
+                .map_err(|_| icon_error("neither a PNG nor an SVG file".into()))?;
+            font.add_svg_icon(name, codepoint, &svg)
+                .map_err(icon_error)?;
+        }
+        chars.insert(codepoint);
+    }
+    char_range.extend(chars);
+    Ok(())
+}
+
+/// Defines with which method to generate the font bitmap.
+#[derive(PartialEq, Deserialize, Copy, Clone, Debug)]
+pub enum BitmapFormat {
+    SDF { spread: f32, padding: i32 },
+    Bitmap { spread: f32, padding: i32 },
+}
+
+impl fmt::Display for BitmapFormat {
+    /// used on output to print out the format in the format used by Glyphr internally
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitmapFormat::Bitmap {
+                spread: _,
+                padding: _,
+            } => write!(f, "BitmapFormat::Bitmap"),
+            BitmapFormat::SDF {
+                spread: _,
+                padding: _,
+            } => {
+                write!(f, "BitmapFormat::SDF")
+            }
+        }
+    }
+}
+
+impl BitmapFormat {
+    /// The `glyphr::BitmapFormat` of the generated font, the color one for glyphs made of
+    /// layers.
+    pub fn runtime_format(&self, color: bool) -> &'static str {
+        match (self, color) {
+            (BitmapFormat::SDF { .. }, false) => "BitmapFormat::SDF",
+            (BitmapFormat::Bitmap { .. }, false) => "BitmapFormat::Bitmap",
+            (BitmapFormat::SDF { .. }, true) => "BitmapFormat::ColorSDF",
+            (BitmapFormat::Bitmap { .. }, true) => "BitmapFormat::ColorBitmap",
+        }
+    }
+
+    /// Suffix used to tell apart fonts of the same family and size.
+    fn suffix(&self) -> &'static str {
+        match self {
+            BitmapFormat::SDF { .. } => "SDF",
+            BitmapFormat::Bitmap { .. } => "BITMAP",
+        }
+    }
+}
+
+/// Decides which parts of the outline are inside the glyph.
+#[derive(PartialEq, Eq, Deserialize, Copy, Clone, Debug, Default)]
+pub enum FillRule {
+    /// Inside if the contours wind around the point at least once, in either direction.
+    /// Overlapping contours (common in variable fonts) are filled.
+    #[default]
+    NonZero,
+    /// Inside if a ray from the point crosses the contours an odd number of times.
+    /// Overlapping contours leave holes.
+    EvenOdd,
+}
+
+/// What to do with requested characters the font has no glyph for.
+#[derive(PartialEq, Eq, Deserialize, Copy, Clone, Debug, Default)]
+pub enum MissingGlyphs {
+    /// Fail the generation, listing every missing character.
+    #[default]
+    Error,
+    /// Leave them out of the font with a compiler warning.
+    Skip,
+    /// Leave them out and include `.notdef`, which is drawn in their place.
+    Notdef,
+}
+
+/// Options that change how glyphs are generated, independent of size and format.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct GenerationOptions {
+    pub fill_rule: FillRule,
+    pub missing: MissingGlyphs,
+    /// Always include the `.notdef` glyph, stored as `NOTDEF`.
+    pub include_notdef: bool,
+    /// Generate the layers of the COLR glyphs, for the `ColorSDF`/`ColorBitmap` formats.
+    pub color: bool,
+}
+
+/// Last stage of font informations before generation.
+pub struct FontLoaded {
+    pub name: String,
+    pub font: Rc<Font>,
+    pub px: i32,
+    pub char_range: Vec<char>,
+    pub format: BitmapFormat,
+    pub options: GenerationOptions,
+    /// Name of the `FontFamily` this font belongs to, if any.
+    pub family: Option<String>,
+}
+
+/// Checks that the members `expand_family` makes out of `sizes` and `formats` have different
+/// names: a size listed twice, or a format kind listed twice even with different parameters,
+/// would generate two fonts with the same name.
+pub fn check_family(name: &str, sizes: &[i32], formats: &[BitmapFormat]) -> Result<(), Error> {
+    for (i, &size) in sizes.iter().enumerate() {
+        if sizes[..i].contains(&size) {
+            return Err(Error::DuplicateSize {
+                font: name.to_string(),
+                size,
+            });
+        }
+    }
+    for (i, format) in formats.iter().enumerate() {
+        if formats[..i].iter().any(|f| f.suffix() == format.suffix()) {
+            return Err(Error::DuplicateFormat {
+                font: name.to_string(),
+                format: format.suffix(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Expands one font description into a font per (size, format) pair.
+/// With a single size and format the result is a plain font called `name`, otherwise every
+/// member is called `name_SIZE` (plus `_FORMAT` if more formats are given) and all of them are
+/// grouped in a family called `name`. Sizes and formats must pass `check_family`.
+pub fn expand_family(
+    name: &str,
+    font: Font,
+    sizes: &[i32],
+    formats: &[BitmapFormat],
+    char_range: Vec<char>,
+    options: GenerationOptions,
+) -> Vec<FontLoaded> {
+    let font = Rc::new(font);
+
+    if sizes.len() == 1 && formats.len() == 1 {
+        return vec![FontLoaded {
+            name: name.to_string(),
+            font,
+            px: sizes[0],
+            char_range,
+            format: formats[0],
+            options,
+            family: None,
+        }];
+    }
+
+    let mut fonts = Vec::with_capacity(sizes.len() * formats.len());
+    for &px in sizes {
+        for format in formats {
+            let member = match formats.len() {
+                1 => format!("{name}_{px}"),
+                _ => format!("{name}_{px}_{}", format.suffix()),
+            };
+            fonts.push(FontLoaded {
+                name: member,
+                font: font.clone(),
+                px,
+                char_range: char_range.clone(),
+                format: *format,
+                options,
+                family: Some(name.to_string()),
+            });
+        }
+    }
+    fonts
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::generator::font::FontError;
+
+/// Everything that can go wrong while generating fonts.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// A `toml` configuration is malformed.
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// A font file could not be parsed, or doesn't have the requested variation.
+    FontParse { path: PathBuf, source: FontError },
+    /// The font has no glyph for some of the requested characters.
+    MissingGlyphs { font: String, characters: Vec<char> },
+    /// A `characters` pattern is malformed, `position` counts chars from 1.
+    Charset {
+        pattern: String,
+        position: usize,
+        message: String,
+    },
+    /// The requested characters include `NOTDEF`, which only the `.notdef` glyph is stored as.
+    ReservedCharacter { font: String },
+    /// An icon (SVG or PNG) can't be read or converted.
+    Icon { path: PathBuf, message: String },
+    /// Fonts must be at least 1px.
+    InvalidSize { font: String, size: i32 },
+    /// BDF and PCF fonts can only be generated at the size they were drawn at.
+    FixedSize {
+        font: String,
+        size: i32,
+        pixel_size: i32,
+    },
+    /// The font can't be packed in a texture atlas.
+    Atlas { font: String, message: String },
+    /// A family lists the same size twice.
+    DuplicateSize { font: String, size: i32 },
+    /// A family lists the same format kind twice, whatever its parameters.
+    DuplicateFormat { font: String, format: &'static str },
+    /// The output code could not be rendered.
+    Template(minijinja::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "can't access '{}': {source}", path.display()),
+            Error::Toml { path, source } => {
+                write!(f, "can't parse '{}': {source}", path.display())
+            }
+            Error::FontParse { path, source } => {
+                write!(f, "can't load font '{}': {source}", path.display())
+            }
+            Error::MissingGlyphs { font, characters } => {
+                write!(f, "font '{font}' has no glyph for")?;
+                for (i, c) in characters.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    write!(f, "{separator} U+{:04X} {c:?}", *c as u32)?;
+                }
+                Ok(())
+            }
+            Error::Charset {
+                pattern,
+                position,
+                message,
+            } => write!(
+                f,
+                "invalid characters pattern '{pattern}' at position {position}: {message}"
+            ),
+            Error::ReservedCharacter { font } => write!(
+                f,
+                "font '{font}' requests U+10FFFF, which is reserved for the .notdef glyph \
+                 (use include_notdef to generate it)"
+            ),
+            Error::Icon { path, message } => {
+                write!(f, "can't import icon '{}': {message}", path.display())
+            }
+            Error::InvalidSize { font, size } => {
+                write!(f, "font '{font}' size must be at least 1px (got {size})")
+            }
+            Error::FixedSize {
+                font,
+                size,
+                pixel_size,
+            } => write!(
+                f,
+                "font '{font}' is a {pixel_size}px bitmap font, it can't be generated at {size}px"
+            ),
+            Error::DuplicateSize { font, size } => {
+                write!(f, "font '{font}' lists size {size} twice")
+            }
+            Error::DuplicateFormat { font, format } => write!(
+                f,
+                "font '{font}' lists the {format} format twice, family members are named by \
+                 size and format kind only"
+            ),
+            Error::Atlas { font, message } => {
+                write!(f, "can't pack font '{font}' in an atlas: {message}")
+            }
+            Error::Template(source) => write!(f, "can't render the generated code: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Toml { source, .. } => Some(source),
+            Error::FontParse { source, .. } => Some(source),
+            Error::Template(source) => Some(source),
+            Error::MissingGlyphs { .. }
+            | Error::Charset { .. }
+            | Error::ReservedCharacter { .. }
+            | Error::Icon { .. }
+            | Error::InvalidSize { .. }
+            | Error::FixedSize { .. }
+            | Error::DuplicateSize { .. }
+            | Error::DuplicateFormat { .. }
+            | Error::Atlas { .. } => None,
+        }
+    }
+}
+
+impl From<minijinja::Error> for Error {
+    fn from(source: minijinja::Error) -> Self {
+        Error::Template(source)
+    }
+}
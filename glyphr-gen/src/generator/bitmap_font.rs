@@ -0,0 +1,730 @@
+//! Pixel fonts in the BDF (text) and PCF (binary, X11) formats, possibly gzip compressed as
+//! they're usually distributed. Their glyphs are kept pixel by pixel, at the size they were drawn
+//! at.
+
+use std::collections::HashMap;
+
+use crate::generator::raster::RasterGlyph;
+
+const PCF_MAGIC: &[u8] = b"\x01fcp";
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+/// A decoded BDF or PCF font.
+pub(crate) struct BitmapFont {
+    /// Size the font was drawn at, in pixels.
+    pub pixel_size: i32,
+    pub ascent: i32,
+    pub descent: i32,
+    pub glyphs: Vec<(char, RasterGlyph)>,
+    /// Character whose glyph is drawn for the ones the font doesn't have.
+    pub default_char: Option<char>,
+}
+
+/// Whether `data` looks like a BDF or PCF font, gzip compressed or not.
+pub(crate) fn is_bitmap_font(data: &[u8]) -> bool {
+    data.starts_with(b"STARTFONT") || data.starts_with(PCF_MAGIC) || data.starts_with(GZIP_MAGIC)
+}
+
+pub(crate) fn parse(data: &[u8]) -> Result<BitmapFont, String> {
+    if data.starts_with(GZIP_MAGIC) {
+        return parse(&gunzip(data)?);
+    }
+    if data.starts_with(PCF_MAGIC) {
+        return parse_pcf(data);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| "BDF file is not valid UTF-8".to_string())?;
+    parse_bdf(text)
+}
+
+/// Largest decompressed size of a gzip compressed font, far more than any pixel font needs.
+const MAX_GUNZIP_LEN: usize = 64 << 20;
+
+/// Decompresses a gzip member to the size its `ISIZE` trailer declares, if not too large.
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+    const FHCRC: u8 = 2;
+    let truncated = || "truncated gzip header".to_string();
+
+    let flags = *data.get(3).ok_or_else(truncated)?;
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = data
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0));
+            pos += end.ok_or_else(truncated)? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+    // The member ends with the CRC32 and ISIZE (length modulo 2^32) of the decompressed data.
+    let trailer = data
+        .len()
+        .checked_sub(8)
+        .filter(|&trailer| trailer >= pos)
+        .ok_or_else(truncated)?;
+    let len = u32::from_le_bytes(data[trailer + 4..].try_into().unwrap()) as usize;
+    if len > MAX_GUNZIP_LEN {
+        return Err(format!(
+            "gzip data expands to {len} bytes, more than the {MAX_GUNZIP_LEN} allowed"
+        ));
+    }
+    miniz_oxide::inflate::decompress_to_vec_with_limit(&data[pos..trailer], len)
+        .ok()
+        .filter(|font| font.len() == len)
+        .ok_or_else(|| "can't decompress gzip data".to_string())
+}
+
+/// Font wide values of both formats, from the properties and the header.
+#[derive(Default)]
+struct Properties {
+    ascent: Option<i32>,
+    descent: Option<i32>,
+    pixel_size: Option<i32>,
+    default_char: Option<u32>,
+    registry: Option<String>,
+    encoding: Option<String>,
+}
+
+impl Properties {
+    fn set_int(&mut self, name: &str, value: i32) {
+        match name {
+            "FONT_ASCENT" => self.ascent = Some(value),
+            "FONT_DESCENT" => self.descent = Some(value),
+            "PIXEL_SIZE" => self.pixel_size = Some(value),
+            "DEFAULT_CHAR" => self.default_char = u32::try_from(value).ok(),
+            _ => {}
+        }
+    }
+
+    fn set_string(&mut self, name: &str, value: &str) {
+        match name {
+            "CHARSET_REGISTRY" => self.registry = Some(value.to_string()),
+            "CHARSET_ENCODING" => self.encoding = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    /// Glyphs are stored by encoding, which is Unicode for ISO 10646 fonts and for Latin-1 ones
+    /// (whose encodings are the first 256 codepoints).
+    fn check_charset(&self) -> Result<(), String> {
+        let registry = self.registry.as_deref().unwrap_or("ISO10646");
+        let encoding = self.encoding.as_deref().unwrap_or("1");
+        if registry.eq_ignore_ascii_case("ISO10646")
+            || (registry.eq_ignore_ascii_case("ISO8859") && encoding == "1")
+        {
+            return Ok(());
+        }
+        Err(format!(
+            "unsupported charset {registry}-{encoding}, only ISO10646-1 (Unicode) and \
+             ISO8859-1 fonts can be imported"
+        ))
+    }
+
+    fn into_font(
+        self,
+        glyphs: Vec<(char, RasterGlyph)>,
+        ascent: Option<i32>,
+        descent: Option<i32>,
+    ) -> Result<BitmapFont, String> {
+        self.check_charset()?;
+        let ascent = self
+            .ascent
+            .or(ascent)
+            .ok_or("the font has no FONT_ASCENT")?;
+        let descent = self
+            .descent
+            .or(descent)
+            .ok_or("the font has no FONT_DESCENT")?;
+        let default_char = self
+            .default_char
+            .and_then(char::from_u32)
+            .filter(|c| glyphs.iter().any(|(glyph, _)| glyph == c));
+        Ok(BitmapFont {
+            pixel_size: self.pixel_size.unwrap_or(ascent + descent),
+            ascent,
+            descent,
+            glyphs,
+            default_char,
+        })
+    }
+}
+
+/// Mask of a glyph stored in rows of `row_len` bytes, most significant bit first. `None` if
+/// `rows` is shorter than the glyph.
+fn unpack_rows(rows: &[u8], row_len: usize, width: u32, height: u32) -> Option<Vec<bool>> {
+    if width == 0 || height == 0 {
+        return Some(Vec::new());
+    }
+    // The sizes come from the file, check them against the data before allocating.
+    let len = row_len
+        .checked_mul(height as usize)
+        .filter(|&len| len <= rows.len())?;
+    let mut mask = Vec::with_capacity((width as usize).checked_mul(height as usize)?);
+    for row in rows[..len].chunks_exact(row_len) {
+        mask.extend((0..width as usize).map(|x| row[x / 8] & (0x80 >> (x % 8)) != 0));
+    }
+    Some(mask)
+}
+
+fn parse_bdf(text: &str) -> Result<BitmapFont, String> {
+    let mut properties = Properties::default();
+    let mut font_box = None;
+    let mut font_dwidth = None;
+    let mut glyphs = Vec::new();
+
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    while let Some((number, line)) = lines.next() {
+        let error = |message: &str| format!("line {number}: {message}");
+        let (keyword, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match keyword {
+            "FONTBOUNDINGBOX" => {
+                font_box =
+                    Some(integers::<4>(args).ok_or_else(|| error("invalid FONTBOUNDINGBOX"))?)
+            }
+            "DWIDTH" => {
+                font_dwidth = Some(integers::<2>(args).ok_or_else(|| error("invalid DWIDTH"))?[0])
+            }
+            "SIZE" => {
+                // Point size and resolution, the pixel size unless PIXEL_SIZE says otherwise.
+                if let Some([points, _, ydpi]) = integers::<3>(args) {
+                    properties
+                        .pixel_size
+                        .get_or_insert((points * ydpi + 36) / 72);
+                }
+            }
+            "STARTPROPERTIES" => {
+                for (number, line) in lines.by_ref() {
+                    if line == "ENDPROPERTIES" {
+                        break;
+                    }
+                    let Some((name, value)) = line.split_once(char::is_whitespace) else {
+                        return Err(format!("line {number}: property without value"));
+                    };
+                    let value = value.trim();
+                    match value.strip_prefix('"') {
+                        Some(string) => properties
+                            .set_string(name, &string.trim_end_matches('"').replace("\"\"", "\"")),
+                        None => {
+                            if let Ok(value) = value.parse() {
+                                properties.set_int(name, value);
+                            }
+                        }
+                    }
+                }
+            }
+            "STARTCHAR" => {
+                let mut encoding = None;
+                let mut dwidth = font_dwidth;
+                let mut bbx = font_box;
+                let mut bitmap = None;
+                while let Some((number, line)) = lines.next() {
+                    let error = |message: &str| format!("line {number}: {message}");
+                    let (keyword, args) =
+                        line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                    match keyword {
+                        "ENCODING" => {
+                            // `-1 n` is a glyph outside of the standard encoding, left out.
+                            let value: i64 = args
+                                .split_whitespace()
+                                .next()
+                                .and_then(|v| v.parse().ok())
+                                .ok_or_else(|| error("invalid ENCODING"))?;
+                            encoding = Some(value);
+                        }
+                        "DWIDTH" => {
+                            dwidth =
+                                Some(integers::<2>(args).ok_or_else(|| error("invalid DWIDTH"))?[0])
+                        }
+                        "BBX" => {
+                            bbx = Some(integers::<4>(args).ok_or_else(|| error("invalid BBX"))?)
+                        }
+                        "BITMAP" => {
+                            let [width, height, ..] =
+                                bbx.ok_or_else(|| error("BITMAP before BBX"))?;
+                            if width < 0 || height < 0 {
+                                return Err(error("negative BBX size"));
+                            }
+                            let row_len = (width as usize).div_ceil(8);
+                            let mut rows = Vec::new();
+                            for (number, line) in lines.by_ref().take(height as usize) {
+                                let row = hex_row(line, row_len)
+                                    .ok_or_else(|| format!("line {number}: invalid bitmap row"))?;
+                                rows.extend(row);
+                            }
+                            bitmap = Some(
+                                unpack_rows(&rows, row_len, width as u32, height as u32)
+                                    .ok_or_else(|| error("truncated BITMAP"))?,
+                            );
+                        }
+                        "ENDCHAR" => break,
+                        _ => {}
+                    }
+                }
+
+                let encoding = encoding.ok_or_else(|| error("glyph without ENCODING"))?;
+                let Some(c) = u32::try_from(encoding).ok().and_then(char::from_u32) else {
+                    continue;
+                };
+                let [width, height, xmin, ymin] = bbx.ok_or_else(|| error("glyph without BBX"))?;
+                glyphs.push((
+                    c,
+                    RasterGlyph {
+                        width: width as u32,
+                        height: height as u32,
+                        xmin,
+                        baseline: -ymin,
+                        advance: dwidth.ok_or_else(|| error("glyph without DWIDTH"))?,
+                        mask: bitmap.ok_or_else(|| error("glyph without BITMAP"))?,
+                    },
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    if font_box.is_none() {
+        return Err("not a BDF font, FONTBOUNDINGBOX is missing".into());
+    }
+    // Without FONT_ASCENT/FONT_DESCENT the bounding box of the font is the best guess.
+    let ascent = font_box.map(|[_, height, _, ymin]| height + ymin);
+    let descent = font_box.map(|[.., ymin]| -ymin);
+    properties.into_font(glyphs, ascent, descent)
+}
+
+/// Parses `N` whitespace separated integers.
+fn integers<const N: usize>(args: &str) -> Option<[i32; N]> {
+    let mut values = [0; N];
+    let mut args = args.split_whitespace();
+    for value in &mut values {
+        *value = args.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// A BDF bitmap row: hex digits for at least `len` bytes, extra ones are padding.
+fn hex_row(line: &str, len: usize) -> Option<Vec<u8>> {
+    let digits = line.as_bytes();
+    if digits.len() < len * 2 {
+        return None;
+    }
+    (0..len)
+        .map(|i| u8::from_str_radix(std::str::from_utf8(&digits[i * 2..i * 2 + 2]).ok()?, 16).ok())
+        .collect()
+}
+
+/// Types of the PCF tables this module reads.
+const PCF_PROPERTIES: u32 = 1 << 0;
+const PCF_ACCELERATORS: u32 = 1 << 1;
+const PCF_METRICS: u32 = 1 << 2;
+const PCF_BITMAPS: u32 = 1 << 3;
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+
+/// Bits of the format of PCF tables.
+const PCF_GLYPH_PAD_MASK: u32 = 3;
+const PCF_BYTE_MASK: u32 = 1 << 2;
+const PCF_BIT_MASK: u32 = 1 << 3;
+const PCF_SCAN_UNIT_MASK: u32 = 3 << 4;
+const PCF_COMPRESSED_METRICS: u32 = 1 << 8;
+
+/// Reads the values of a PCF table, in the byte order of its format.
+struct PcfReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: u32,
+}
+
+impl<'a> PcfReader<'a> {
+    /// Reader of the table of `type` among the `tables` of `data`, after its format.
+    fn table(data: &'a [u8], tables: &[(u32, u32, usize, usize)], table_type: u32) -> Option<Self> {
+        let &(_, format, size, offset) = tables.iter().find(|(t, ..)| *t == table_type)?;
+        let data = data.get(offset..offset.checked_add(size)?)?;
+        // The format is repeated at the start of the table, always little-endian.
+        (data.get(..4)? == format.to_le_bytes()).then_some(Self {
+            data,
+            pos: 4,
+            format,
+        })
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        let bytes = self.bytes(2)?.try_into().ok()?;
+        Some(match self.format & PCF_BYTE_MASK {
+            0 => i16::from_le_bytes(bytes),
+            _ => i16::from_be_bytes(bytes),
+        })
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        let bytes = self.bytes(4)?.try_into().ok()?;
+        Some(match self.format & PCF_BYTE_MASK {
+            0 => i32::from_le_bytes(bytes),
+            _ => i32::from_be_bytes(bytes),
+        })
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        usize::try_from(self.i32()?).ok()
+    }
+}
+
+/// Metrics of a PCF glyph.
+#[derive(Clone, Copy)]
+struct PcfMetrics {
+    left_bearing: i32,
+    right_bearing: i32,
+    width: i32,
+    ascent: i32,
+    descent: i32,
+}
+
+fn parse_pcf(data: &[u8]) -> Result<BitmapFont, String> {
+    let invalid = |table: &str| format!("invalid or missing PCF {table} table");
+    let read_u32 = |at: usize| {
+        let bytes = data.get(at..at + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let table_count = read_u32(4).ok_or_else(|| invalid("header"))?;
+    let tables = (0..table_count as usize)
+        .map(|i| {
+            let at = 8 + i * 16;
+            Some((
+                read_u32(at)?,
+                read_u32(at + 4)?,
+                read_u32(at + 8)? as usize,
+                read_u32(at + 12)? as usize,
+            ))
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid("header"))?;
+
+    let properties = pcf_properties(data, &tables).ok_or_else(|| invalid("properties"))?;
+    let metrics = pcf_metrics(data, &tables).ok_or_else(|| invalid("metrics"))?;
+    let bitmaps = pcf_bitmaps(data, &tables, &metrics).ok_or_else(|| invalid("bitmaps"))?;
+    let (encodings, default_char) =
+        pcf_encodings(data, &tables).ok_or_else(|| invalid("encodings"))?;
+    // The accelerators hold the ascent and descent when the properties don't.
+    let accelerators = PcfReader::table(data, &tables, PCF_BDF_ACCELERATORS)
+        .or_else(|| PcfReader::table(data, &tables, PCF_ACCELERATORS))
+        .and_then(|mut reader| {
+            reader.bytes(8)?;
+            Some((reader.i32()?, reader.i32()?))
+        });
+
+    let mut glyphs = Vec::with_capacity(encodings.len());
+    for (codepoint, index) in encodings {
+        let (Some(c), Some(metrics), Some(mask)) = (
+            char::from_u32(codepoint),
+            metrics.get(index),
+            bitmaps.get(index),
+        ) else {
+            continue;
+        };
+        glyphs.push((
+            c,
+            RasterGlyph {
+                width: (metrics.right_bearing - metrics.left_bearing).max(0) as u32,
+                height: (metrics.ascent + metrics.descent).max(0) as u32,
+                xmin: metrics.left_bearing,
+                baseline: metrics.descent,
+                advance: metrics.width,
+                mask: mask.clone(),
+            },
+        ));
+    }
+
+    let mut properties = properties;
+    properties.default_char = properties.default_char.or(default_char);
+    properties.into_font(
+        glyphs,
+        accelerators.map(|(ascent, _)| ascent),
+        accelerators.map(|(_, descent)| descent),
+    )
+}
+
+fn pcf_properties(data: &[u8], tables: &[(u32, u32, usize, usize)]) -> Option<Properties> {
+    let mut reader = PcfReader::table(data, tables, PCF_PROPERTIES)?;
+    let count = reader.count()?;
+    // Not preallocated, `count` comes from the file: a bad one ends with the table instead.
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        entries.push((reader.i32()?, reader.u8()? != 0, reader.i32()?));
+    }
+    // Entries are padded to 4 bytes.
+    reader.bytes((4 - count % 4) % 4)?;
+    let strings_len = reader.count()?;
+    let strings = reader.bytes(strings_len)?;
+    let string = |offset: i32| {
+        let rest = strings.get(usize::try_from(offset).ok()?..)?;
+        let end = rest.iter().position(|&b| b == 0)?;
+        std::str::from_utf8(&rest[..end]).ok()
+    };
+
+    let mut properties = Properties::default();
+    for (name, is_string, value) in entries {
+        let name = string(name)?;
+        match is_string {
+            true => properties.set_string(name, string(value)?),
+            false => properties.set_int(name, value),
+        }
+    }
+    Some(properties)
+}
+
+fn pcf_metrics(data: &[u8], tables: &[(u32, u32, usize, usize)]) -> Option<Vec<PcfMetrics>> {
+    let mut reader = PcfReader::table(data, tables, PCF_METRICS)?;
+    if reader.format & PCF_COMPRESSED_METRICS != 0 {
+        let count = usize::try_from(reader.i16()?).ok()?;
+        (0..count)
+            .map(|_| {
+                let mut value = || Some(reader.u8()? as i32 - 0x80);
+                Some(PcfMetrics {
+                    left_bearing: value()?,
+                    right_bearing: value()?,
+                    width: value()?,
+                    ascent: value()?,
+                    descent: value()?,
+                })
+            })
+            .collect()
+    } else {
+        let count = reader.count()?;
+        (0..count)
+            .map(|_| {
+                let metrics = PcfMetrics {
+                    left_bearing: reader.i16()? as i32,
+                    right_bearing: reader.i16()? as i32,
+                    width: reader.i16()? as i32,
+                    ascent: reader.i16()? as i32,
+                    descent: reader.i16()? as i32,
+                };
+                // Attributes, unused.
+                reader.i16()?;
+                Some(metrics)
+            })
+            .collect()
+    }
+}
+
+/// Masks of the glyphs, in the order of the metrics.
+fn pcf_bitmaps(
+    data: &[u8],
+    tables: &[(u32, u32, usize, usize)],
+    metrics: &[PcfMetrics],
+) -> Option<Vec<Vec<bool>>> {
+    let mut reader = PcfReader::table(data, tables, PCF_BITMAPS)?;
+    let format = reader.format;
+    let count = reader.count()?;
+    let offsets = (0..count)
+        .map(|_| usize::try_from(reader.i32()?).ok())
+        .collect::<Option<Vec<_>>>()?;
+    let sizes = [reader.i32()?, reader.i32()?, reader.i32()?, reader.i32()?];
+    let pad = (format & PCF_GLYPH_PAD_MASK) as usize;
+    let mut bitmaps = reader.bytes(usize::try_from(sizes[pad]).ok()?)?.to_vec();
+
+    // Bring the data to bytes with the most significant bit first, as in BDF files.
+    let scan_unit = 1 << ((format & PCF_SCAN_UNIT_MASK) >> 4);
+    if (format & PCF_BYTE_MASK != 0) != (format & PCF_BIT_MASK != 0) && scan_unit > 1 {
+        for unit in bitmaps.chunks_exact_mut(scan_unit) {
+            unit.reverse();
+        }
+    }
+    if format & PCF_BIT_MASK == 0 {
+        for byte in &mut bitmaps {
+            *byte = byte.reverse_bits();
+        }
+    }
+
+    let pad_bytes = 1 << pad;
+    offsets
+        .iter()
+        .zip(metrics)
+        .map(|(&offset, metrics)| {
+            let width = (metrics.right_bearing - metrics.left_bearing).max(0) as u32;
+            let height = (metrics.ascent + metrics.descent).max(0) as u32;
+            let row_len = (width as usize).div_ceil(8).next_multiple_of(pad_bytes);
+            unpack_rows(bitmaps.get(offset..)?, row_len, width, height)
+        })
+        .collect()
+}
+
+/// Codepoints with the index of their glyph, and the default character.
+#[allow(clippy::type_complexity)]
+fn pcf_encodings(
+    data: &[u8],
+    tables: &[(u32, u32, usize, usize)],
+) -> Option<(HashMap<u32, usize>, Option<u32>)> {
+    let mut reader = PcfReader::table(data, tables, PCF_BDF_ENCODINGS)?;
+    let min_byte2 = reader.i16()? as u32;
+    let max_byte2 = reader.i16()? as u32;
+    let min_byte1 = reader.i16()? as u32;
+    let max_byte1 = reader.i16()? as u32;
+    let default_char = reader.i16()? as u16 as u32;
+    if min_byte2 > max_byte2 || min_byte1 > max_byte1 {
+        return None;
+    }
+
+    let mut encodings = HashMap::new();
+    for byte1 in min_byte1..=max_byte1 {
+        for byte2 in min_byte2..=max_byte2 {
+            let index = reader.i16()? as u16;
+            if index != 0xFFFF {
+                encodings.insert(byte1 << 8 | byte2, index as usize);
+            }
+        }
+    }
+    Some((encodings, Some(default_char)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(file: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/tests/fonts/{file}", env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    /// Rows of `#` (inked) and `.` of a glyph.
+    fn rows(glyph: &RasterGlyph) -> Vec<String> {
+        glyph
+            .mask
+            .chunks(glyph.width.max(1) as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|&inked| if inked { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn glyph(font: &BitmapFont, c: char) -> &RasterGlyph {
+        &font.glyphs.iter().find(|(glyph, _)| *glyph == c).unwrap().1
+    }
+
+    #[test]
+    fn test_parse_bdf() {
+        let font = parse(&fixture("bitmap.bdf")).unwrap();
+        assert_eq!((font.pixel_size, font.ascent, font.descent), (10, 8, 2));
+        assert_eq!(font.default_char, Some('\0'));
+
+        let a = glyph(&font, 'A');
+        assert_eq!(
+            (a.width, a.height, a.xmin, a.baseline, a.advance),
+            (5, 7, 0, 0, 6)
+        );
+        assert_eq!(
+            rows(a),
+            [
+                ".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"
+            ]
+        );
+        let g = glyph(&font, 'g');
+        assert_eq!((g.width, g.height, g.xmin, g.baseline), (4, 7, 1, 2));
+        let space = glyph(&font, ' ');
+        assert_eq!((space.width, space.height, space.advance), (0, 0, 6));
+        // Glyphs outside of the encoding are left out.
+        assert_eq!(font.glyphs.len(), 4);
+    }
+
+    #[test]
+    fn test_pcf_matches_bdf() {
+        let bdf = parse(&fixture("bitmap.bdf")).unwrap();
+        for file in ["bitmap.pcf", "bitmap.pcf.gz"] {
+            let pcf = parse(&fixture(file)).unwrap();
+            assert_eq!(
+                (pcf.pixel_size, pcf.ascent, pcf.descent, pcf.default_char),
+                (bdf.pixel_size, bdf.ascent, bdf.descent, bdf.default_char)
+            );
+            assert_eq!(pcf.glyphs.len(), bdf.glyphs.len());
+            for (c, expected) in &bdf.glyphs {
+                let glyph = glyph(&pcf, *c);
+                assert_eq!(
+                    (
+                        glyph.width,
+                        glyph.height,
+                        glyph.xmin,
+                        glyph.baseline,
+                        glyph.advance
+                    ),
+                    (
+                        expected.width,
+                        expected.height,
+                        expected.xmin,
+                        expected.baseline,
+                        expected.advance
+                    )
+                );
+                assert_eq!(glyph.mask, expected.mask, "{c:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_bitmap_fonts() {
+        assert!(parse(b"STARTFONT 2.1\nENDFONT\n").is_err());
+        let koi8 = String::from_utf8(fixture("bitmap.bdf"))
+            .unwrap()
+            .replace("\"ISO10646\"", "\"KOI8\"");
+        assert!(matches!(parse(koi8.as_bytes()), Err(err) if err.contains("KOI8-1")));
+        assert!(parse(&fixture("bitmap.pcf")[..64]).is_err());
+        assert!(parse(b"\x1f\x8b\x08\x00").is_err());
+
+        // Huge sizes without the data to back them are errors, not overflows or allocations.
+        let huge = String::from_utf8(fixture("bitmap.bdf")).unwrap().replacen(
+            "BBX 5 7 0 0",
+            "BBX 70000 70000 0 0",
+            1,
+        );
+        assert!(parse(huge.as_bytes()).is_err());
+        assert!(unpack_rows(&[0; 4], 1 << 29, 1 << 31, 1 << 31).is_none());
+
+        // A properties count far beyond the table ends with the table.
+        let mut pcf = fixture("bitmap.pcf");
+        let read = |pcf: &[u8], at: usize| u32::from_le_bytes(pcf[at..at + 4].try_into().unwrap());
+        let entry = (0..read(&pcf, 4) as usize)
+            .map(|i| 8 + i * 16)
+            .find(|&at| read(&pcf, at) == PCF_PROPERTIES)
+            .unwrap();
+        let (format, offset) = (read(&pcf, entry + 4), read(&pcf, entry + 12) as usize);
+        let count = match format & PCF_BYTE_MASK {
+            0 => i32::MAX.to_le_bytes(),
+            _ => i32::MAX.to_be_bytes(),
+        };
+        pcf[offset + 4..offset + 8].copy_from_slice(&count);
+        assert!(matches!(parse(&pcf), Err(err) if err.contains("properties")));
+
+        // The decompressed size must match ISIZE and stay below the limit.
+        let gz = fixture("bitmap.pcf.gz");
+        let isize_at = gz.len() - 4;
+        for len in [1, MAX_GUNZIP_LEN as u32 + 1] {
+            let mut patched = gz.clone();
+            patched[isize_at..].copy_from_slice(&len.to_le_bytes());
+            assert!(
+                matches!(parse(&patched), Err(err) if err.contains("gzip")),
+                "{len}"
+            );
+        }
+        assert!(!is_bitmap_font(b"\x00\x01\x00\x00"));
+    }
+}
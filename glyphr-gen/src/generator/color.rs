@@ -0,0 +1,213 @@
+//! Color glyphs: layers of the COLR (version 0) table with the colors of the first CPAL palette,
+//! and their packing in the layout of the runtime color formats.
+
+use std::collections::HashMap;
+
+use ttf_parser::{Face, GlyphId, Tag};
+
+use crate::generator::font::Metrics;
+
+/// Palette index of the layers drawn in the text color, must match `glyphr::color`.
+pub const FOREGROUND: u16 = 0xFFFF;
+/// Color of the layers whose palette index is out of the palette: opaque black.
+const MISSING_COLOR: u32 = 0xFF00_0000;
+
+/// Glyph and palette index of every layer, bottom first, by base glyph.
+pub(crate) fn layers(face: &Face) -> HashMap<GlyphId, Vec<(GlyphId, u16)>> {
+    face.raw_face()
+        .table(Tag::from_bytes(b"COLR"))
+        .and_then(parse_colr)
+        .unwrap_or_default()
+}
+
+fn parse_colr(data: &[u8]) -> Option<HashMap<GlyphId, Vec<(GlyphId, u16)>>> {
+    let base_count = read_u16(data, 2)? as usize;
+    let base_offset = read_u32(data, 4)? as usize;
+    let layer_offset = read_u32(data, 8)? as usize;
+    let layer_count = read_u16(data, 12)? as usize;
+
+    let mut layers = HashMap::with_capacity(base_count);
+    for record in 0..base_count {
+        let at = base_offset + record * 6;
+        let (glyph, first, count) = (
+            read_u16(data, at)?,
+            read_u16(data, at + 2)?,
+            read_u16(data, at + 4)?,
+        );
+        let (first, count) = (first as usize, count as usize);
+        if first + count > layer_count {
+            return None;
+        }
+        let glyph_layers = (first..first + count)
+            .map(|layer| {
+                let at = layer_offset + layer * 4;
+                Some((GlyphId(read_u16(data, at)?), read_u16(data, at + 2)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        layers.insert(GlyphId(glyph), glyph_layers);
+    }
+    Some(layers)
+}
+
+/// Colors of the first palette, as `0xAARRGGBB`.
+pub(crate) fn palette(face: &Face) -> Vec<u32> {
+    face.raw_face()
+        .table(Tag::from_bytes(b"CPAL"))
+        .and_then(parse_cpal)
+        .unwrap_or_default()
+}
+
+fn parse_cpal(data: &[u8]) -> Option<Vec<u32>> {
+    let entries = read_u16(data, 2)? as usize;
+    let records_offset = read_u32(data, 8)? as usize;
+    let first = read_u16(data, 12)? as usize;
+    (first..first + entries)
+        .map(|record| {
+            let at = records_offset + record * 4;
+            let [b, g, r, a] = data.get(at..at + 4)?.try_into().ok()?;
+            Some(u32::from_be_bytes([a, r, g, b]))
+        })
+        .collect()
+}
+
+/// Color of the palette entry `index`, `0` for the text color.
+pub(crate) fn palette_color(palette: &[u32], index: u16) -> u32 {
+    match index {
+        FOREGROUND => 0,
+        index => palette
+            .get(index as usize)
+            .copied()
+            .unwrap_or(MISSING_COLOR),
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// A rasterized layer, with its bitmap already encoded for the output format.
+pub(crate) struct RasterLayer {
+    pub palette_index: u16,
+    pub color: u32,
+    pub metrics: Metrics,
+    pub bitmap: Vec<u8>,
+}
+
+/// Packs `layers` in the bitmap of a color glyph, returning it with the metrics of the box
+/// around them. Empty layers are left out, the advance is the one of `base`.
+pub(crate) fn pack_layers(base: Metrics, layers: &[RasterLayer]) -> (Metrics, Vec<u8>) {
+    let drawn: Vec<&RasterLayer> = layers
+        .iter()
+        .filter(|layer| layer.metrics.width > 0 && layer.metrics.height > 0)
+        .collect();
+    if drawn.is_empty() {
+        return (base, Vec::new());
+    }
+
+    let xmin = drawn.iter().map(|l| l.metrics.xmin).min().unwrap_or(0);
+    let ymin = drawn.iter().map(|l| l.metrics.ymin).min().unwrap_or(0);
+    let xmax = drawn
+        .iter()
+        .map(|l| l.metrics.xmin + l.metrics.width)
+        .max()
+        .unwrap_or(0);
+    let ymax = drawn
+        .iter()
+        .map(|l| l.metrics.ymin + l.metrics.height)
+        .max()
+        .unwrap_or(0);
+
+    let mut bitmap = Vec::new();
+    for layer in drawn {
+        let metrics = layer.metrics;
+        bitmap.extend(layer.palette_index.to_le_bytes());
+        bitmap.extend(layer.color.to_le_bytes());
+        // Offsets from the top left corner of the glyph box, y pointing down as in bitmaps.
+        bitmap.extend(((metrics.xmin - xmin) as i16).to_le_bytes());
+        bitmap.extend(((ymax - metrics.ymin - metrics.height) as i16).to_le_bytes());
+        bitmap.extend((metrics.width as u16).to_le_bytes());
+        bitmap.extend((metrics.height as u16).to_le_bytes());
+        bitmap.extend((layer.bitmap.len() as u32).to_le_bytes());
+        bitmap.extend_from_slice(&layer.bitmap);
+    }
+
+    let metrics = Metrics {
+        xmin,
+        ymin,
+        width: xmax - xmin,
+        height: ymax - ymin,
+        advance_width: base.advance_width,
+    };
+    (metrics, bitmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/color.ttf");
+
+    #[test]
+    fn test_parse_colr_and_cpal() {
+        let data = std::fs::read(COLOR_TTF).unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let layers = layers(&face);
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            layers[&GlyphId(1)],
+            [(GlyphId(0), 0), (GlyphId(1), FOREGROUND)]
+        );
+        assert_eq!(palette(&face), [0xFFFF_0000, 0x8000_8000]);
+        assert_eq!(palette_color(&palette(&face), 5), MISSING_COLOR);
+        assert_eq!(palette_color(&palette(&face), FOREGROUND), 0);
+
+        // Truncated tables are ignored.
+        assert!(parse_colr(&[0, 0, 0, 1]).is_none());
+        assert!(parse_cpal(&[0, 0, 0, 2, 0, 1, 0, 2, 0, 0, 0, 99, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_pack_layers() {
+        let layer = |palette_index, xmin, ymin, width, height| RasterLayer {
+            palette_index,
+            color: 0xFF12_3456,
+            metrics: Metrics {
+                xmin,
+                ymin,
+                width,
+                height,
+                advance_width: 0,
+            },
+            bitmap: vec![7; 3],
+        };
+        let base = Metrics {
+            advance_width: 9,
+            ..Default::default()
+        };
+        let (metrics, bitmap) = pack_layers(
+            base,
+            &[
+                layer(0, 1, -2, 4, 6),
+                layer(3, 0, 0, 0, 0),
+                layer(FOREGROUND, 2, 0, 5, 5),
+            ],
+        );
+        assert_eq!(
+            (metrics.xmin, metrics.ymin, metrics.width, metrics.height),
+            (1, -2, 6, 7)
+        );
+        assert_eq!(metrics.advance_width, 9);
+        // Two layers of 18 bytes of header and 3 of data, the empty one is left out.
+        assert_eq!(bitmap.len(), 2 * 21);
+        assert_eq!(&bitmap[..6], [0, 0, 0x56, 0x34, 0x12, 0xFF]);
+        assert_eq!(&bitmap[6..10], [0, 0, 1, 0]);
+        assert_eq!(&bitmap[21..23], [0xFF, 0xFF]);
+        assert_eq!(&bitmap[27..31], [1, 0, 0, 0]);
+
+        assert!(pack_layers(base, &[]).1.is_empty());
+    }
+}
@@ -0,0 +1,843 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt, ops::Deref};
+use ttf_parser::{Face, FaceParsingError, GlyphId, OutlineBuilder, name_id};
+
+use crate::config::FillRule;
+use crate::generator::{
+    NOTDEF,
+    bitmap_font::{self, BitmapFont},
+    color,
+    font_geometry::{FontGeometry, OutlineBounds},
+    kerning::Kerning,
+    line::Line,
+    raster::{self, RasterGlyph},
+    sdf_generation::{SdfRaster, sdf_generate},
+    svg::{self, Segment},
+    variation,
+};
+
+#[derive(Clone, Default, Debug)]
+pub struct FontSettings {
+    pub collection_index: u32,
+    /// Named instance of a variable font (e.g. `"SemiBold"`), applied before `axes`.
+    pub instance: Option<String>,
+    /// Coordinates of variation axes, e.g. `("wght", 600.0)`.
+    pub axes: Vec<(String, f32)>,
+}
+
+/// A face of a font collection (`.ttc`/`.otc`), by index or by name. Plain font files have
+/// a single face, at index 0.
+#[derive(PartialEq, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum FaceSelector {
+    Index(u32),
+    /// PostScript name (`NotoSansCJKjp-Bold`), full name or family and subfamily
+    /// (`Noto Sans CJK JP Bold`), ignoring ASCII case.
+    Name(String),
+}
+
+impl Default for FaceSelector {
+    fn default() -> Self {
+        FaceSelector::Index(0)
+    }
+}
+
+impl FaceSelector {
+    /// Index of the selected face in the font file `data`.
+    pub fn resolve(&self, data: &[u8]) -> Result<u32, FontError> {
+        let count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+        let found = match self {
+            FaceSelector::Index(index) => (*index < count).then_some(*index),
+            FaceSelector::Name(name) => (0..count).find(|&index| {
+                Face::parse(data, index).is_ok_and(|face| {
+                    face_names(&face)
+                        .iter()
+                        .any(|candidate| candidate.eq_ignore_ascii_case(name))
+                })
+            }),
+        };
+
+        found.ok_or_else(|| {
+            let faces: Vec<String> = (0..count)
+                .map(|index| match Face::parse(data, index) {
+                    Ok(face) => match face_names(&face).as_slice() {
+                        [] => format!("{index}: <unnamed>"),
+                        [postscript, rest @ ..] => match rest.last() {
+                            Some(full) => format!("{index}: {postscript} ({full})"),
+                            None => format!("{index}: {postscript}"),
+                        },
+                    },
+                    Err(err) => format!("{index}: <{err}>"),
+                })
+                .collect();
+            let selected = match self {
+                FaceSelector::Index(index) => format!("no face {index}"),
+                FaceSelector::Name(name) => format!("no face named '{name}'"),
+            };
+            FontError::Face(format!("{selected}, the font has {}", faces.join(", ")))
+        })
+    }
+}
+
+/// Names a face can be selected with: PostScript name, full name, and family plus subfamily.
+fn face_names(face: &Face) -> Vec<String> {
+    let name = |id: u16| {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == id && name.is_unicode())
+            .find_map(|name| name.to_string())
+    };
+
+    let mut names: Vec<String> = [name_id::POST_SCRIPT_NAME, name_id::FULL_NAME]
+        .into_iter()
+        .filter_map(name)
+        .collect();
+    let family = name(name_id::TYPOGRAPHIC_FAMILY).or_else(|| name(name_id::FAMILY));
+    let subfamily = name(name_id::TYPOGRAPHIC_SUBFAMILY).or_else(|| name(name_id::SUBFAMILY));
+    if let (Some(family), Some(subfamily)) = (family, subfamily) {
+        names.push(format!("{family} {subfamily}"));
+    }
+    names.dedup();
+    names
+}
+
+/// Why a font file can't be loaded.
+#[derive(Debug)]
+pub enum FontError {
+    /// The data is not a valid TTF/OTF font.
+    Parse(FaceParsingError),
+    /// The requested face doesn't exist in the collection.
+    Face(String),
+    /// The requested instance or axis coordinates don't exist in the font.
+    Variation(String),
+    /// The data is a WOFF/WOFF2 file that can't be decoded.
+    Woff(String),
+    /// The data is a BDF/PCF file that can't be parsed.
+    Bitmap(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Parse(source) => write!(f, "{source}"),
+            FontError::Face(message)
+            | FontError::Variation(message)
+            | FontError::Woff(message)
+            | FontError::Bitmap(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Parse(source) => Some(source),
+            FontError::Face(_)
+            | FontError::Variation(_)
+            | FontError::Woff(_)
+            | FontError::Bitmap(_) => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct LineMetrics {
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    pub new_line_size: f32,
+}
+
+impl LineMetrics {
+    fn new(ascent: i16, descent: i16, line_gap: i16) -> LineMetrics {
+        let (ascent, descent, line_gap) = (ascent as i32, descent as i32, line_gap as i32);
+        LineMetrics {
+            ascent: ascent as f32,
+            descent: descent as f32,
+            line_gap: line_gap as f32,
+            new_line_size: (ascent - descent + line_gap) as f32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct Metrics {
+    pub xmin: i32,
+    pub ymin: i32,
+    pub width: i32,
+    pub height: i32,
+    pub advance_width: i32,
+}
+
+#[derive(Default)]
+pub(crate) struct Glyph {
+    pub bounds: OutlineBounds,
+    pub advance_width: f32,
+    pub lines: Vec<Line>,
+    /// Rule the outline is filled with instead of the font's one, for SVG icons.
+    pub fill_rule: Option<FillRule>,
+}
+
+/// A layer of a color glyph, from the COLR table.
+pub struct ColorLayer {
+    /// Entry of the palette the layer is drawn with, `FOREGROUND` for the text color.
+    pub palette_index: u16,
+    /// Color of the entry in the first palette, `0xAARRGGBB`.
+    pub color: u32,
+    pub(crate) glyph: Glyph,
+}
+
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    /// Glyphs imported from PNG images, used instead of `glyphs`.
+    images: HashMap<char, RasterGlyph>,
+    /// Layers of the color glyphs, bottom first.
+    color_layers: HashMap<char, Vec<ColorLayer>>,
+    horizontal_line_metrics: LineMetrics,
+    units_per_em: f32,
+    digest: [u8; 32],
+    /// Names and codepoints of the glyphs imported from SVG and PNG files.
+    icons: Vec<(String, char)>,
+    /// Size of BDF and PCF fonts, the only one they can be generated at.
+    pixel_size: Option<i32>,
+    /// Font file and face index, kept to look up kerning pairs. Empty for BDF and PCF fonts.
+    data: Vec<u8>,
+    face_index: u32,
+    /// Glyph of every character mapped by the font, without `.notdef` and icons.
+    glyph_ids: HashMap<char, GlyphId>,
+}
+
+impl Font {
+    pub fn from_bytes<D: Deref<Target = [u8]>>(
+        data: D,
+        settings: FontSettings,
+    ) -> Result<Self, FontError> {
+        if bitmap_font::is_bitmap_font(&data) {
+            let font = bitmap_font::parse(&data).map_err(FontError::Bitmap)?;
+            return Self::from_bitmap_font(font, &data, &settings);
+        }
+
+        let mut face = Face::parse(&data, settings.collection_index).map_err(FontError::Parse)?;
+        variation::apply(&mut face, settings.instance.as_deref(), &settings.axes)
+            .map_err(FontError::Variation)?;
+        let units_per_em = face.units_per_em() as f32;
+
+        let glyph_count = face.number_of_glyphs();
+        let mut glyph_id_mapping = HashMap::with_capacity(glyph_count as usize);
+        if let Some(subtable) = face.tables().cmap {
+            for subtable in subtable.subtables {
+                subtable.codepoints(|codepoint| {
+                    if let Some(mapping) = subtable.glyph_index(codepoint) {
+                        glyph_id_mapping.insert(codepoint, mapping);
+                    }
+                })
+            }
+        }
+
+        // `.notdef` is always the first glyph, and no character maps to it.
+        glyph_id_mapping
+            .entry(NOTDEF as u32)
+            .or_insert(ttf_parser::GlyphId(0));
+
+        let outline = |glyph_id| {
+            let mut geometry = FontGeometry::new();
+            face.outline_glyph(glyph_id, &mut geometry);
+            geometry.finalize();
+
+            Glyph {
+                bounds: geometry.bounds,
+                advance_width: face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32,
+                lines: geometry.lines,
+                fill_rule: None,
+            }
+        };
+
+        let base_glyphs = color::layers(&face);
+        let palette = color::palette(&face);
+        let mut glyphs = HashMap::with_capacity(glyph_id_mapping.len());
+        let mut color_layers = HashMap::with_capacity(base_glyphs.len());
+        let mut glyph_ids = HashMap::with_capacity(glyph_id_mapping.len());
+        for (codepoint, glyph_id) in glyph_id_mapping {
+            let char = match char::from_u32(codepoint) {
+                Some(c) => c,
+                None => continue,
+            };
+            if char != NOTDEF {
+                glyph_ids.insert(char, glyph_id);
+            }
+
+            if let Some(layers) = base_glyphs.get(&glyph_id) {
+                let layers = layers
+                    .iter()
+                    .map(|&(layer_id, palette_index)| ColorLayer {
+                        palette_index,
+                        color: color::palette_color(&palette, palette_index),
+                        glyph: outline(layer_id),
+                    })
+                    .collect();
+                color_layers.insert(char, layers);
+            }
+            glyphs.insert(char, outline(glyph_id));
+        }
+
+        let horizontal_line_metrics =
+            LineMetrics::new(face.ascender(), face.descender(), face.line_gap());
+
+        let mut hasher = Sha256::new();
+        hasher.update(&*data);
+        hasher.update(settings.collection_index.to_le_bytes());
+        for coordinate in face.variation_coordinates() {
+            hasher.update(coordinate.get().to_le_bytes());
+        }
+
+        let font = Font {
+            glyphs,
+            images: HashMap::new(),
+            color_layers,
+            units_per_em,
+            horizontal_line_metrics,
+            digest: hasher.finalize().into(),
+            icons: Vec::new(),
+            pixel_size: None,
+            data: data.to_vec(),
+            face_index: settings.collection_index,
+            glyph_ids,
+        };
+
+        Ok(font)
+    }
+
+    /// A font with the glyphs of a BDF or PCF font, drawn as they are like PNG icons. Font units
+    /// are pixels of its size.
+    fn from_bitmap_font(
+        font: BitmapFont,
+        data: &[u8],
+        settings: &FontSettings,
+    ) -> Result<Self, FontError> {
+        if settings.instance.is_some() || !settings.axes.is_empty() {
+            return Err(FontError::Variation(
+                "the font is not a variable font".into(),
+            ));
+        }
+
+        let mut images: HashMap<char, RasterGlyph> = font.glyphs.into_iter().collect();
+        if let Some(default) = font.default_char.and_then(|c| images.get(&c)) {
+            let notdef = RasterGlyph {
+                mask: default.mask.clone(),
+                ..*default
+            };
+            images.insert(NOTDEF, notdef);
+        }
+
+        Ok(Font {
+            glyphs: HashMap::new(),
+            images,
+            color_layers: HashMap::new(),
+            horizontal_line_metrics: LineMetrics::new(font.ascent as i16, -font.descent as i16, 0),
+            units_per_em: font.pixel_size as f32,
+            digest: Sha256::digest(data).into(),
+            icons: Vec::new(),
+            pixel_size: Some(font.pixel_size),
+            data: Vec::new(),
+            face_index: 0,
+            glyph_ids: HashMap::new(),
+        })
+    }
+
+    pub fn metrics(&self, c: char, px: f32) -> Option<Metrics> {
+        if let Some(image) = self.images.get(&c) {
+            return Some(Metrics {
+                xmin: image.xmin,
+                ymin: -image.baseline,
+                width: image.width as i32,
+                height: image.height as i32,
+                advance_width: image.advance,
+            });
+        }
+
+        Some(self.outline_metrics(self.glyphs.get(&c)?, px))
+    }
+
+    fn outline_metrics(&self, glyph: &Glyph, px: f32) -> Metrics {
+        let scale = self.scale_factor(px);
+
+        // Snap the outline to whole pixels, covering it entirely.
+        let bounds = glyph.bounds.scale(scale);
+        let xmin = bounds.xmin.floor();
+        let ymin = bounds.ymin.floor();
+        let xmax = (bounds.xmin + bounds.width).ceil();
+        let ymax = (bounds.ymin + bounds.height).ceil();
+        Metrics {
+            xmin: xmin as i32,
+            ymin: ymin as i32,
+            width: (xmax - xmin) as i32,
+            height: (ymax - ymin) as i32,
+            advance_width: (glyph.advance_width * scale) as i32,
+        }
+    }
+
+    pub fn sdf_generate(
+        &self,
+        px: f32,
+        padding: i32,
+        spread: f32,
+        fill_rule: FillRule,
+        c: char,
+    ) -> Option<(Metrics, SdfRaster)> {
+        if px < 1.0 {
+            panic!("Sdf render size cannot be smaller than 1.0 (got {px:?})");
+        }
+
+        if let Some(image) = self.images.get(&c) {
+            let mut metrics = self.metrics(c, px)?;
+            let sdf =
+                raster::distance_field(&image.mask, image.width, image.height, padding, spread);
+            metrics.xmin -= padding;
+            metrics.ymin -= padding;
+            metrics.width += padding * 2;
+            metrics.height += padding * 2;
+            return Some((metrics, sdf));
+        }
+
+        let glyph = self.glyphs.get(&c)?;
+        Some(self.rasterize(glyph, px, padding, spread, fill_rule))
+    }
+
+    /// Layers of the color glyph of `c`, bottom first, empty if it has no colors.
+    pub fn color_layers(&self, c: char) -> &[ColorLayer] {
+        self.color_layers.get(&c).map_or(&[], Vec::as_slice)
+    }
+
+    /// Like `sdf_generate`, for a layer returned by `color_layers`. The metrics are the ones of
+    /// the layer, placed as in the color glyph.
+    pub fn sdf_generate_layer(
+        &self,
+        px: f32,
+        padding: i32,
+        spread: f32,
+        fill_rule: FillRule,
+        layer: &ColorLayer,
+    ) -> (Metrics, SdfRaster) {
+        self.rasterize(&layer.glyph, px, padding, spread, fill_rule)
+    }
+
+    fn rasterize(
+        &self,
+        glyph: &Glyph,
+        px: f32,
+        padding: i32,
+        spread: f32,
+        fill_rule: FillRule,
+    ) -> (Metrics, SdfRaster) {
+        let mut metrics = self.outline_metrics(glyph, px);
+        let fill_rule = glyph.fill_rule.unwrap_or(fill_rule);
+        if glyph.lines.is_empty() {
+            let sdf = sdf_generate(0, 0, spread, fill_rule, &[]);
+            return (metrics, sdf);
+        }
+
+        // Outlines are stored normalized to their bounds with y pointing down: place them on the
+        // pixel grid, at the same scale on both axes, leaving `padding` pixels on every side.
+        let bounds = glyph.bounds.scale(self.scale_factor(px));
+        let offset_x = padding as f32 + bounds.xmin - metrics.xmin as f32;
+        let offset_y =
+            padding as f32 + (metrics.ymin + metrics.height) as f32 - (bounds.ymin + bounds.height);
+        let lines: Vec<Line> = glyph
+            .lines
+            .iter()
+            .map(|line| {
+                line.denormalize_to_with_offset(offset_x, offset_y, bounds.width, bounds.height)
+            })
+            .collect();
+
+        metrics.xmin -= padding;
+        metrics.ymin -= padding;
+        metrics.width += padding * 2;
+        metrics.height += padding * 2;
+
+        let sdf = sdf_generate(
+            metrics.width as u32,
+            metrics.height as u32,
+            spread,
+            fill_rule,
+            &lines,
+        );
+
+        (metrics, sdf)
+    }
+
+    /// Imports an SVG icon as the glyph of `codepoint`, replacing the font's one if any. The
+    /// height of the SVG canvas spans from the descender to the ascender and its width is the
+    /// advance, so icons line up with the text around them.
+    pub fn add_svg_icon(&mut self, name: &str, codepoint: char, svg: &str) -> Result<(), String> {
+        let icon = svg::parse(svg)?;
+        if icon.segments.is_empty() {
+            return Err(
+                "no filled shapes, strokes aren't supported (convert them to paths)".into(),
+            );
+        }
+        let [x, y, width, height] = icon.view_box;
+        let metrics = self.horizontal_line_metrics;
+        let scale = (metrics.ascent - metrics.descent) / height;
+        // SVG coordinates point down, font units up.
+        let map = |[px, py]: [f32; 2]| ((px - x) * scale, metrics.ascent - (py - y) * scale);
+
+        let mut geometry = FontGeometry::new();
+        let mut open = false;
+        for segment in icon.segments {
+            match segment {
+                Segment::MoveTo(p) => {
+                    // Unclosed subpaths are filled as if they were closed.
+                    if open {
+                        geometry.close();
+                    }
+                    let (x, y) = map(p);
+                    geometry.move_to(x, y);
+                    open = true;
+                }
+                Segment::LineTo(p) => {
+                    let (x, y) = map(p);
+                    geometry.line_to(x, y);
+                }
+                Segment::QuadTo(c, p) => {
+                    let ((cx, cy), (x, y)) = (map(c), map(p));
+                    geometry.quad_to(cx, cy, x, y);
+                }
+                Segment::CurveTo(c1, c2, p) => {
+                    let ((x1, y1), (x2, y2), (x, y)) = (map(c1), map(c2), map(p));
+                    geometry.curve_to(x1, y1, x2, y2, x, y);
+                }
+                Segment::Close => {
+                    geometry.close();
+                    open = false;
+                }
+            }
+        }
+        if open {
+            geometry.close();
+        }
+        geometry.finalize();
+
+        self.images.remove(&codepoint);
+        self.color_layers.remove(&codepoint);
+        self.glyphs.insert(
+            codepoint,
+            Glyph {
+                bounds: geometry.bounds,
+                advance_width: width * scale,
+                lines: geometry.lines,
+                fill_rule: Some(icon.fill_rule),
+            },
+        );
+        self.add_icon(name, codepoint, svg.as_bytes());
+        Ok(())
+    }
+
+    /// Imports a PNG image as the glyph of `codepoint`, replacing the font's one if any. Its
+    /// pixels are drawn as they are at every size: inked where at least half opaque (or dark, for
+    /// images without transparency). `baseline` rows of the image go below the baseline, and the
+    /// advance defaults to the width of the image.
+    pub fn add_png_icon(
+        &mut self,
+        name: &str,
+        codepoint: char,
+        png: &[u8],
+        baseline: i32,
+        advance: Option<i32>,
+    ) -> Result<(), String> {
+        let (width, height, mask) = raster::decode_png(png)?;
+        self.glyphs.remove(&codepoint);
+        self.color_layers.remove(&codepoint);
+        self.images.insert(
+            codepoint,
+            RasterGlyph {
+                width,
+                height,
+                xmin: 0,
+                baseline,
+                advance: advance.unwrap_or(width as i32),
+                mask,
+            },
+        );
+
+        let mut source = png.to_vec();
+        source.extend(baseline.to_le_bytes());
+        source.extend(advance.unwrap_or(-1).to_le_bytes());
+        self.add_icon(name, codepoint, &source);
+        Ok(())
+    }
+
+    /// Records an imported glyph and adds its `source` to the digest.
+    fn add_icon(&mut self, name: &str, codepoint: char, source: &[u8]) {
+        self.glyph_ids.remove(&codepoint);
+        self.icons.retain(|(_, c)| *c != codepoint);
+        self.icons.push((name.to_string(), codepoint));
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.digest);
+        hasher.update((codepoint as u32).to_le_bytes());
+        hasher.update(source);
+        self.digest = hasher.finalize().into();
+    }
+
+    /// Kerning of every pair of `chars` the font adjusts, as `(left, right, amount)` with the
+    /// amount added to the advance of `left`, in pixels at `px`. Variations don't apply to it.
+    pub fn kerning(&self, chars: &[char], px: f32) -> Vec<(char, char, i32)> {
+        let Ok(face) = Face::parse(&self.data, self.face_index) else {
+            return Vec::new();
+        };
+        let kerning = Kerning::new(&face);
+        let glyphs: Vec<(char, GlyphId)> = chars
+            .iter()
+            .filter_map(|c| Some((*c, *self.glyph_ids.get(c)?)))
+            .collect();
+
+        let mut pairs = Vec::new();
+        for &(left, left_id) in glyphs.iter().filter(|(_, id)| kerning.covers(*id)) {
+            for &(right, right_id) in &glyphs {
+                let amount = kerning.get(left_id, right_id) as f32 * self.scale_factor(px);
+                let amount = amount.round() as i32;
+                if amount != 0 {
+                    pairs.push((left, right, amount));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Size of BDF and PCF fonts in pixels, the only one they can be generated at.
+    pub fn pixel_size(&self) -> Option<i32> {
+        self.pixel_size
+    }
+
+    /// Names and codepoints of the icons added with `add_svg_icon` and `add_png_icon`.
+    pub fn icons(&self) -> &[(String, char)] {
+        &self.icons
+    }
+
+    /// SHA-256 of the font file, of the settings it was loaded with (face and variation) and of
+    /// the icons added to it.
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    /// Returns every character that has a glyph in the font, sorted.
+    pub fn chars(&self) -> Vec<char> {
+        let mut chars: Vec<char> = self
+            .glyphs
+            .keys()
+            .chain(self.images.keys())
+            .copied()
+            .filter(|&c| c != NOTDEF)
+            .collect();
+        chars.sort_unstable();
+        chars
+    }
+
+    fn scale_factor(&self, px: f32) -> f32 {
+        px / self.units_per_em
+    }
+
+    pub fn get_ascent(&self, px: f32) -> i32 {
+        (self.horizontal_line_metrics.ascent * self.scale_factor(px)) as i32
+    }
+
+    pub fn get_descent(&self, px: f32) -> i32 {
+        (self.horizontal_line_metrics.descent * self.scale_factor(px)) as i32
+    }
+
+    pub fn get_line_gap(&self, px: f32) -> i32 {
+        (self.horizontal_line_metrics.line_gap * self.scale_factor(px)) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+    const VARIABLE_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/variable.ttf");
+    const COLLECTION_TTC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/collection.ttc");
+
+    fn variable(instance: Option<&str>, axes: &[(&str, f32)]) -> Result<Font, FontError> {
+        let data = std::fs::read(VARIABLE_TTF).unwrap();
+        let settings = FontSettings {
+            instance: instance.map(String::from),
+            axes: axes
+                .iter()
+                .map(|&(tag, value)| (tag.into(), value))
+                .collect(),
+            ..Default::default()
+        };
+        Font::from_bytes(data.as_slice(), settings)
+    }
+
+    #[test]
+    fn test_variation_changes_outlines() {
+        let width = |font: &Font| font.metrics('A', 100.0).unwrap().width;
+        let regular = variable(None, &[]).unwrap();
+        let semibold = variable(None, &[("wght", 650.0)]).unwrap();
+        let black = variable(Some("Black"), &[]).unwrap();
+
+        assert!(width(&regular) < width(&semibold));
+        assert!(width(&semibold) < width(&black));
+        assert_eq!(
+            black.digest(),
+            variable(None, &[("wght", 900.0)]).unwrap().digest()
+        );
+        assert_ne!(regular.digest(), semibold.digest());
+        // Axes are applied on top of the instance.
+        assert_eq!(
+            variable(Some("Thin"), &[("wght", 650.0)]).unwrap().digest(),
+            semibold.digest()
+        );
+    }
+
+    #[test]
+    fn test_select_collection_face() {
+        let data = std::fs::read(COLLECTION_TTC).unwrap();
+        let resolve = |face: FaceSelector| face.resolve(&data);
+        assert_eq!(resolve(FaceSelector::Index(1)).unwrap(), 1);
+        assert_eq!(resolve(FaceSelector::Name("Demo-Wide".into())).unwrap(), 1);
+        assert_eq!(
+            resolve(FaceSelector::Name("demo regular".into())).unwrap(),
+            0
+        );
+
+        let listed = "the font has 0: Demo-Regular (Demo Regular), 1: Demo-Wide (Demo Wide)";
+        for face in [
+            FaceSelector::Index(2),
+            FaceSelector::Name("Demo Bold".into()),
+        ] {
+            match resolve(face) {
+                Err(FontError::Face(message)) => assert!(message.ends_with(listed), "{message}"),
+                _ => panic!("expected a face error"),
+            }
+        }
+
+        // The selected face is the one loaded.
+        let settings = FontSettings {
+            collection_index: 1,
+            instance: Some("Black".into()),
+            ..Default::default()
+        };
+        assert!(Font::from_bytes(data.as_slice(), settings).is_ok());
+
+        // Plain fonts have a single face.
+        let data = std::fs::read(DEMO_TTF).unwrap();
+        assert_eq!(FaceSelector::default().resolve(&data).unwrap(), 0);
+        assert!(FaceSelector::Index(1).resolve(&data).is_err());
+    }
+
+    #[test]
+    fn test_unknown_variation() {
+        let message = |result: Result<Font, FontError>| match result {
+            Err(FontError::Variation(message)) => message,
+            _ => panic!("expected a variation error"),
+        };
+        assert_eq!(
+            message(variable(Some("Bold"), &[])),
+            "no instance 'Bold', the font has Thin, Black"
+        );
+        assert_eq!(
+            message(variable(None, &[("wdth", 85.0)])),
+            "no axis 'wdth', the font has wght (100-900)"
+        );
+        assert!(message(variable(None, &[("wght", 1000.0)])).contains("out of the axis range"));
+
+        let data = std::fs::read(DEMO_TTF).unwrap();
+        let settings = FontSettings {
+            instance: Some("Black".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Font::from_bytes(data.as_slice(), settings),
+            Err(FontError::Variation(_))
+        ));
+    }
+
+    #[test]
+    fn test_padding_adjusts_metrics() {
+        let data = std::fs::read(DEMO_TTF).unwrap();
+        let font = Font::from_bytes(data.as_slice(), FontSettings::default()).unwrap();
+
+        let (plain, plain_sdf) = font
+            .sdf_generate(32.0, 0, 4.0, FillRule::NonZero, 'A')
+            .unwrap();
+        let (padded, padded_sdf) = font
+            .sdf_generate(32.0, 3, 4.0, FillRule::NonZero, 'A')
+            .unwrap();
+        assert_eq!(padded.xmin, plain.xmin - 3);
+        assert_eq!(padded.ymin, plain.ymin - 3);
+        assert_eq!(padded.width, plain.width + 6);
+        assert_eq!(padded.height, plain.height + 6);
+        assert_eq!(padded.advance_width, plain.advance_width);
+        assert_eq!(padded_sdf.width as i32, padded.width);
+
+        // The outline doesn't move: the padded field matches the plain one shifted by 3px.
+        for y in 0..plain_sdf.height {
+            for x in 0..plain_sdf.width {
+                let a = plain_sdf.buffer[(x + y * plain_sdf.width) as usize];
+                let b = padded_sdf.buffer[(x + 3 + (y + 3) * padded_sdf.width) as usize];
+                assert!((a - b).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_svg_icon_placement() {
+        let data = std::fs::read(DEMO_TTF).unwrap();
+        let mut font = Font::from_bytes(data.as_slice(), FontSettings::default()).unwrap();
+        let digest = *font.digest();
+        // The top half of the canvas, which spans from the descender to the ascender.
+        let svg = r#"<svg viewBox="0 0 10 10"><rect width="10" height="5"/></svg>"#;
+        font.add_svg_icon("half", '\u{e000}', svg).unwrap();
+
+        let LineMetrics {
+            ascent, descent, ..
+        } = font.horizontal_line_metrics;
+        let scale = (ascent - descent) / 10.0;
+        let glyph = &font.glyphs[&'\u{e000}'];
+        let expected = OutlineBounds {
+            xmin: 0.0,
+            ymin: (ascent + descent) / 2.0,
+            width: 10.0 * scale,
+            height: 5.0 * scale,
+        };
+        for (a, b) in [
+            (glyph.bounds.xmin, expected.xmin),
+            (glyph.bounds.ymin, expected.ymin),
+            (glyph.bounds.width, expected.width),
+            (glyph.bounds.height, expected.height),
+            (glyph.advance_width, 10.0 * scale),
+        ] {
+            assert!((a - b).abs() < 1e-3, "{a} != {b}");
+        }
+        assert_eq!(font.icons(), [("half".to_string(), '\u{e000}')]);
+        assert!(font.chars().contains(&'\u{e000}'));
+        assert_ne!(*font.digest(), digest);
+
+        let (metrics, sdf) = font
+            .sdf_generate(32.0, 0, 2.0, FillRule::NonZero, '\u{e000}')
+            .unwrap();
+        let center = sdf.buffer[(metrics.width / 2 + metrics.height / 2 * metrics.width) as usize];
+        assert!(center > 0.5);
+
+        assert!(font.add_svg_icon("bad", 'x', "<svg/>").is_err());
+        let strokes =
+            r#"<svg viewBox="0 0 10 10"><path d="M1 1L9 9" fill="none" stroke="red"/></svg>"#;
+        assert!(
+            matches!(font.add_svg_icon("line", 'y', strokes), Err(err) if err.contains("strokes"))
+        );
+
+        // Both squares go the same way, only the evenodd rule of the icon leaves a hole.
+        let frame = r#"<svg viewBox="0 0 10 10"><path fill-rule="evenodd" d="M0 0h10v10H0zM3 3h4v4H3z"/></svg>"#;
+        font.add_svg_icon("frame", '\u{e001}', frame).unwrap();
+        let (metrics, sdf) = font
+            .sdf_generate(32.0, 0, 2.0, FillRule::NonZero, '\u{e001}')
+            .unwrap();
+        let center = sdf.buffer[(metrics.width / 2 + metrics.height / 2 * metrics.width) as usize];
+        assert!(center < 0.5);
+    }
+}
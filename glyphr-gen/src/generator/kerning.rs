@@ -0,0 +1,110 @@
+//! Kerning of pairs of glyphs: the pair adjustments of the GPOS `kern` feature or, for fonts
+//! without it, the `kern` table.
+
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::{Face, GlyphId, Tag, kern};
+
+pub(crate) enum Kerning<'a> {
+    /// Pair adjustment subtables of every lookup of the `kern` feature.
+    Gpos(Vec<Vec<PairAdjustment<'a>>>),
+    /// Horizontal subtables of the `kern` table.
+    Kern(Vec<kern::Subtable<'a>>),
+}
+
+impl<'a> Kerning<'a> {
+    pub fn new(face: &Face<'a>) -> Self {
+        let tag = Tag::from_bytes(b"kern");
+        if let Some(gpos) = face.tables().gpos
+            && let Some(feature) = gpos.features.into_iter().find(|f| f.tag == tag)
+        {
+            let lookups = feature
+                .lookup_indices
+                .into_iter()
+                .filter_map(|index| gpos.lookups.get(index))
+                .map(|lookup| {
+                    lookup
+                        .subtables
+                        .into_iter::<PositioningSubtable>()
+                        .filter_map(|subtable| match subtable {
+                            PositioningSubtable::Pair(pair) => Some(pair),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .collect();
+            return Kerning::Gpos(lookups);
+        }
+
+        let subtables = face
+            .tables()
+            .kern
+            .map(|kern| {
+                kern.subtables
+                    .into_iter()
+                    .filter(|s| s.horizontal && !s.variable && !s.has_cross_stream)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Kerning::Kern(subtables)
+    }
+
+    /// Whether `left` can be kerned with any glyph, to skip the pairs it starts.
+    pub fn covers(&self, left: GlyphId) -> bool {
+        match self {
+            Kerning::Gpos(lookups) => lookups
+                .iter()
+                .flatten()
+                .any(|pair| pair.coverage().contains(left)),
+            Kerning::Kern(subtables) => !subtables.is_empty(),
+        }
+    }
+
+    /// Adjustment of the advance of `left` when followed by `right`, in font units. Each lookup
+    /// applies its first subtable with the pair.
+    pub fn get(&self, left: GlyphId, right: GlyphId) -> i32 {
+        match self {
+            Kerning::Gpos(lookups) => lookups
+                .iter()
+                .filter_map(|subtables| subtables.iter().find_map(|p| pair_value(p, left, right)))
+                .sum(),
+            Kerning::Kern(subtables) => subtables
+                .iter()
+                .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+                .map(i32::from)
+                .sum(),
+        }
+    }
+}
+
+fn pair_value(pair: &PairAdjustment, left: GlyphId, right: GlyphId) -> Option<i32> {
+    let index = pair.coverage().get(left)?;
+    let (first, _) = match pair {
+        PairAdjustment::Format1 { sets, .. } => sets.get(index)?.get(right)?,
+        PairAdjustment::Format2 {
+            classes, matrix, ..
+        } => matrix.get((classes.0.get(left), classes.1.get(right)))?,
+    };
+    Some(first.x_advance as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+    const KERN_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/kern.ttf");
+    const GPOS_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/gpos.ttf");
+
+    #[test]
+    fn test_kerning_tables() {
+        let (a, notdef) = (GlyphId(1), GlyphId(0));
+        for (path, expected) in [(KERN_TTF, -100), (GPOS_TTF, -150), (DEMO_TTF, 0)] {
+            let data = std::fs::read(path).unwrap();
+            let face = Face::parse(&data, 0).unwrap();
+            let kerning = Kerning::new(&face);
+            assert_eq!(kerning.get(a, a), expected, "{path}");
+            assert_eq!(kerning.get(a, notdef), 0, "{path}");
+            assert_eq!(kerning.covers(a), expected != 0, "{path}");
+        }
+    }
+}
@@ -61,7 +61,7 @@ impl Line {
                     let z = (-p).sqrt();
                     let v = (q / (p * z * 2.0)).acos() / 3.0;
                     let m = v.cos();
-                    let n = v.sin() * 1.732050808;
+                    let n = v.sin() * 1.732_050_8;
                     let t = (vec3(m + m, -n - m, n - m) * z - kx).clamp(0.0, 1.0);
                     let qx = pd + (pc + pb * t[0]) * t[0];
                     let dx = qx.dot(qx);
@@ -126,12 +126,21 @@ impl Line {
         }
     }
 
-    pub fn intersections(&self, y: f32, out: &mut [f32; 3]) -> usize {
+    /// Finds where the segment crosses the horizontal line at `y`. Every crossing is stored as
+    /// its x coordinate and its winding: `1` if the segment goes towards positive y there, `-1`
+    /// if it goes towards negative y, `0` if it only touches the line.
+    pub fn intersections(&self, y: f32, out: &mut [(f32, i32); 3]) -> usize {
         match *self {
             Self::Line { start, end } => {
-                if (y >= start[1] && y <= end[1]) || (y >= end[1] && y < start[1]) {
+                if start[1] == end[1] {
+                    // Horizontal segments never cross a horizontal line.
+                    0
+                } else if (y >= start[1] && y <= end[1]) || (y >= end[1] && y < start[1]) {
                     let h = (y - start[1]) / (end[1] - start[1]);
-                    out[0] = super::sdf_generation::mix(start[0], end[0], h);
+                    out[0] = (
+                        super::sdf_generation::mix(start[0], end[0], h),
+                        winding((end[1] - start[1]) as f64),
+                    );
                     1
                 } else {
                     0
@@ -152,11 +161,14 @@ impl Line {
                 let x0 = start[0] as f64;
                 let x1 = control[0] as f64;
                 let x2 = end[0] as f64;
+                let (y0, y1, y2) = (start[1] as f64, control[1] as f64, end[1] as f64);
                 let solve = |t: f64| {
                     let t2 = t * t;
                     let mt = 1.0 - t;
                     let mt2 = mt * mt;
-                    ((x0 * mt2) + (x1 * 2.0 * mt * t) + (x2 * t2)) as f32
+                    let x = (x0 * mt2) + (x1 * 2.0 * mt * t) + (x2 * t2);
+                    let dy = 2.0 * mt * (y1 - y0) + 2.0 * t * (y2 - y1);
+                    (x as f32, winding(dy))
                 };
 
                 align_quadratic(y, &mut start, &mut end, &mut control);
@@ -211,13 +223,22 @@ impl Line {
                 let x1 = first_control[0] as f64;
                 let x2 = second_control[0] as f64;
                 let x3 = end[0] as f64;
+                let (y0, y1, y2, y3) = (
+                    start[1] as f64,
+                    first_control[1] as f64,
+                    second_control[1] as f64,
+                    end[1] as f64,
+                );
                 let solve = |t: f64| {
                     let t2 = t * t;
                     let t3 = t2 * t;
                     let mt = 1.0 - t;
                     let mt2 = mt * mt;
                     let mt3 = mt2 * mt;
-                    ((x0 * mt3) + (3.0 * x1 * mt2 * t) + (3.0 * x2 * mt * t2) + (x3 * t3)) as f32
+                    let x = (x0 * mt3) + (3.0 * x1 * mt2 * t) + (3.0 * x2 * mt * t2) + (x3 * t3);
+                    let dy =
+                        3.0 * mt2 * (y1 - y0) + 6.0 * mt * t * (y2 - y1) + 3.0 * t2 * (y3 - y2);
+                    (x as f32, winding(dy))
                 };
 
                 align_cubic(
@@ -381,6 +402,39 @@ impl Line {
         }
     }
 
+    /// Inverse of `normalize_to_with_offset`: maps `[0, 1]` coordinates to a `width` x `height`
+    /// box starting at (`x`, `y`).
+    pub fn denormalize_to_with_offset(&self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        let o = vec2(x, y);
+        let p = vec2(width, height);
+        match *self {
+            Self::Line { start, end } => Self::Line {
+                start: start * p + o,
+                end: end * p + o,
+            },
+            Self::Quad {
+                start,
+                end,
+                control,
+            } => Self::Quad {
+                start: start * p + o,
+                end: end * p + o,
+                control: control * p + o,
+            },
+            Self::Curve {
+                start,
+                end,
+                first_control,
+                second_control,
+            } => Self::Curve {
+                start: start * p + o,
+                end: end * p + o,
+                first_control: first_control * p + o,
+                second_control: second_control * p + o,
+            },
+        }
+    }
+
     pub fn flip_y(&mut self) {
         let p1 = vec2(1.0, -1.0);
         let p2 = vec2(0.0, -1.0);
@@ -453,6 +507,49 @@ fn align_cubic(
     *control_2 = *control_2 - p;
 }
 
+/// Direction of a crossing from the y derivative of the segment.
+fn winding(dy: f64) -> i32 {
+    if dy > 0.0 {
+        1
+    } else if dy < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
 fn approximately(v1: f64, v2: f64) -> bool {
     (v1 - v2).abs() <= f64::EPSILON
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersection_winding() {
+        let mut out = [(0.0, 0); 3];
+
+        let down = Line::Line {
+            start: vec2(0.0, 0.0),
+            end: vec2(0.0, 10.0),
+        };
+        assert_eq!(down.intersections(5.0, &mut out), 1);
+        assert_eq!(out[0], (0.0, 1));
+
+        let up = Line::Quad {
+            start: vec2(0.0, 10.0),
+            end: vec2(0.0, 0.0),
+            control: vec2(10.0, 5.0),
+        };
+        assert_eq!(up.intersections(5.0, &mut out), 1);
+        assert_eq!(out[0].1, -1);
+        assert!((out[0].0 - 5.0).abs() < 1e-4);
+
+        let flat = Line::Line {
+            start: vec2(0.0, 5.0),
+            end: vec2(10.0, 5.0),
+        };
+        assert_eq!(flat.intersections(5.0, &mut out), 0);
+    }
+}
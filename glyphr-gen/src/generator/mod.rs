@@ -0,0 +1,501 @@
+mod bitmap_font;
+mod color;
+pub mod font;
+pub mod font_geometry;
+mod kerning;
+pub mod line;
+pub(crate) mod raster;
+pub mod sdf_generation;
+mod svg;
+mod variation;
+pub mod vec2;
+pub mod woff;
+
+use crate::Error;
+use crate::config::{BitmapFormat, FontLoaded, MissingGlyphs};
+use crate::generator::color::RasterLayer;
+use crate::generator::font::Metrics;
+use crate::generator::sdf_generation::SdfRaster;
+
+/// Character the `.notdef` glyph is stored as, must match `glyphr::NOTDEF`.
+pub const NOTDEF: char = '\u{10FFFF}';
+
+/// Contains the info of the font to write out (one per glyph)
+pub struct GlyphEntry {
+    pub character: char,
+    pub name: String,
+    pub xmin: i32,
+    pub ymin: i32,
+    pub width: i32,
+    pub height: i32,
+    pub advance_width: i32,
+}
+
+/// A font together with its generated glyphs, ready to be written out
+pub struct GeneratedFont {
+    pub font: FontLoaded,
+    pub glyphs: Vec<(Vec<u8>, GlyphEntry)>,
+    /// Requested characters left out because the font has no glyph for them.
+    pub missing: Vec<char>,
+}
+
+impl GeneratedFont {
+    pub fn new(font: FontLoaded) -> Result<Self, Error> {
+        let (glyphs, missing) = generate_font(&font)?;
+        Ok(Self {
+            font,
+            glyphs,
+            missing,
+        })
+    }
+
+    pub fn ascent(&self) -> i32 {
+        self.font.font.get_ascent(self.font.px as f32)
+    }
+
+    pub fn descent(&self) -> i32 {
+        self.font.font.get_descent(self.font.px as f32)
+    }
+}
+
+/// Based on the input, generates a font and return Vec<(bitmaps, entries)> paired, together with
+/// the characters that were skipped according to `MissingGlyphs`.
+#[allow(clippy::type_complexity)]
+pub fn generate_font(
+    loaded_font: &FontLoaded,
+) -> Result<(Vec<(Vec<u8>, GlyphEntry)>, Vec<char>), Error> {
+    let mut entries: Vec<(Vec<u8>, GlyphEntry)> = vec![];
+    let mut missing = Vec::new();
+
+    if loaded_font.px < 1 {
+        return Err(Error::InvalidSize {
+            font: loaded_font.name.clone(),
+            size: loaded_font.px,
+        });
+    }
+
+    if let Some(pixel_size) = loaded_font.font.pixel_size()
+        && pixel_size != loaded_font.px
+    {
+        return Err(Error::FixedSize {
+            font: loaded_font.name.clone(),
+            size: loaded_font.px,
+            pixel_size,
+        });
+    }
+
+    if loaded_font.char_range.contains(&NOTDEF) {
+        return Err(Error::ReservedCharacter {
+            font: loaded_font.name.clone(),
+        });
+    }
+
+    let (spread, padding) = match loaded_font.format {
+        BitmapFormat::Bitmap { spread, padding } => (spread, padding),
+        BitmapFormat::SDF { spread, padding } => (spread, padding),
+    };
+
+    let options = loaded_font.options;
+    let mut char_range = loaded_font.char_range.clone();
+    if (options.include_notdef || options.missing == MissingGlyphs::Notdef)
+        && !char_range.contains(&NOTDEF)
+    {
+        char_range.push(NOTDEF);
+    }
+
+    // Glyphs are independent, rasterize them in parallel and collect them in order.
+    let font: &font::Font = &loaded_font.font;
+    let (px, format, fill_rule) = (loaded_font.px as f32, loaded_font.format, options.fill_rule);
+    let encode = |metrics: &Metrics, glyph_sdf: &SdfRaster| {
+        let bitmap_sdf = sdf_generation::sdf_to_bitmap(glyph_sdf);
+        match format {
+            BitmapFormat::Bitmap {
+                spread: _,
+                padding: _,
+            } => sdf_generation::sdf_bitmap_to_fixed_bitmap(
+                &bitmap_sdf,
+                metrics.width,
+                metrics.height,
+                |val| val > 128,
+            ),
+            BitmapFormat::SDF {
+                spread: _,
+                padding: _,
+            } => rle_encode(bitmap_sdf),
+        }
+    };
+    let rasterized = parallel_map(&char_range, |c| {
+        // Color glyphs are made of their COLR layers, other glyphs of a single layer in the text
+        // color.
+        let layers = font.color_layers(*c);
+        if options.color && !layers.is_empty() {
+            let layers: Vec<RasterLayer> = layers
+                .iter()
+                .map(|layer| {
+                    let (metrics, sdf) =
+                        font.sdf_generate_layer(px, padding, spread, fill_rule, layer);
+                    RasterLayer {
+                        palette_index: layer.palette_index,
+                        color: layer.color,
+                        metrics,
+                        bitmap: encode(&metrics, &sdf),
+                    }
+                })
+                .collect();
+            return Some(color::pack_layers(font.metrics(*c, px)?, &layers));
+        }
+
+        let (metrics, glyph_sdf) = font.sdf_generate(px, padding, spread, fill_rule, *c)?;
+        let bitmap = encode(&metrics, &glyph_sdf);
+        if !options.color {
+            return Some((metrics, bitmap));
+        }
+        let layer = RasterLayer {
+            palette_index: color::FOREGROUND,
+            color: 0,
+            metrics,
+            bitmap,
+        };
+        Some(color::pack_layers(metrics, &[layer]))
+    });
+
+    for (c, glyph) in char_range.iter().zip(rasterized) {
+        if let Some((metrics, bitmap)) = glyph {
+            entries.push((
+                bitmap,
+                GlyphEntry {
+                    character: *c,
+                    name: format!("GLYPH_{}", *c as u32),
+                    xmin: metrics.xmin,
+                    ymin: metrics.ymin,
+                    width: metrics.width,
+                    height: metrics.height,
+                    advance_width: metrics.advance_width,
+                },
+            ));
+        } else {
+            let metrics = loaded_font.font.metrics(*c, loaded_font.px as f32);
+            if c.is_whitespace() || metrics.is_some_and(|m| m.advance_width > 0) {
+                eprintln!(
+                    "Info: Glyph '{c}' is empty or not renderable, but has metrics. Inserting dummy entry.",
+                );
+                let met = metrics.unwrap_or_default();
+                entries.push((
+                    Vec::new(),
+                    GlyphEntry {
+                        character: *c,
+                        name: format!("GLYPH_{}", *c as u32),
+                        xmin: met.xmin,
+                        ymin: met.ymin,
+                        width: met.width,
+                        height: met.height,
+                        advance_width: met.advance_width,
+                    },
+                ));
+                continue;
+            }
+
+            missing.push(*c);
+        }
+    }
+
+    if !missing.is_empty() && options.missing == MissingGlyphs::Error {
+        return Err(Error::MissingGlyphs {
+            font: loaded_font.name.clone(),
+            characters: missing,
+        });
+    }
+
+    Ok((entries, missing))
+}
+
+/// Maps `items` on all the available threads, keeping their order.
+fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads == 1 || items.len() < 2 {
+        return items.iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(threads);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("glyph generation thread panicked"))
+            .collect()
+    })
+}
+
+/// Encodes a u8 vector with Run-Lenght-Encoding (RLE)
+pub(crate) fn rle_encode(data: Vec<u8>) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut iter = data.iter().peekable();
+
+    while let Some(&value) = iter.next() {
+        let mut count = 1;
+        while let Some(&&next) = iter.peek() {
+            if next == value && count < u8::MAX {
+                iter.next();
+                count += 1;
+            } else {
+                break;
+            }
+        }
+        encoded.push(count);
+        encoded.push(value);
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GenerationOptions, expand_family, load_font};
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+
+    fn demo(missing: MissingGlyphs, include_notdef: bool) -> FontLoaded {
+        let mut fonts = expand_family(
+            "DEMO",
+            load_font(DEMO_TTF).unwrap(),
+            &[16],
+            &[BitmapFormat::Bitmap {
+                spread: 2.0,
+                padding: 0,
+            }],
+            vec!['A', 'B', 'C'],
+            GenerationOptions {
+                missing,
+                include_notdef,
+                ..Default::default()
+            },
+        );
+        fonts.remove(0)
+    }
+
+    fn characters(font: &GeneratedFont) -> Vec<char> {
+        font.glyphs
+            .iter()
+            .map(|(_, entry)| entry.character)
+            .collect()
+    }
+
+    #[test]
+    fn test_missing_glyphs_policies() {
+        assert!(matches!(
+            GeneratedFont::new(demo(MissingGlyphs::Error, false)),
+            Err(Error::MissingGlyphs { characters, .. }) if characters == ['B', 'C']
+        ));
+
+        let skipped = GeneratedFont::new(demo(MissingGlyphs::Skip, false)).unwrap();
+        assert_eq!(characters(&skipped), ['A']);
+        assert_eq!(skipped.missing, ['B', 'C']);
+
+        let notdef = GeneratedFont::new(demo(MissingGlyphs::Notdef, false)).unwrap();
+        assert_eq!(characters(&notdef), ['A', NOTDEF]);
+    }
+
+    #[test]
+    fn test_notdef_is_not_a_requested_character() {
+        let mut loaded = demo(MissingGlyphs::Skip, true);
+        loaded.char_range.push(NOTDEF);
+        assert!(matches!(
+            GeneratedFont::new(loaded),
+            Err(Error::ReservedCharacter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_include_notdef() {
+        let font = GeneratedFont::new(demo(MissingGlyphs::Skip, true)).unwrap();
+        assert_eq!(characters(&font), ['A', NOTDEF]);
+        let (bitmap, notdef) = &font.glyphs[1];
+        assert!(notdef.width > 0 && !bitmap.is_empty());
+    }
+
+    #[test]
+    fn test_web_fonts_generate_same_glyphs() {
+        let generate = |file: &str| {
+            let path = format!("{}/tests/fonts/{file}", env!("CARGO_MANIFEST_DIR"));
+            let font = expand_family(
+                "WEB",
+                load_font(path).unwrap(),
+                &[24],
+                &[BitmapFormat::SDF {
+                    spread: 4.0,
+                    padding: 1,
+                }],
+                vec!['A', '\u{c4}'],
+                GenerationOptions::default(),
+            )
+            .remove(0);
+            let generated = GeneratedFont::new(font).unwrap();
+            generated
+                .glyphs
+                .into_iter()
+                .map(|(bitmap, e)| {
+                    let metrics = (e.xmin, e.ymin, e.width, e.height, e.advance_width);
+                    (e.character, metrics, bitmap)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let ttf = generate("web.ttf");
+        assert_eq!(generate("web.woff"), ttf);
+        assert_eq!(generate("web.woff2"), ttf);
+    }
+
+    #[test]
+    fn test_png_icons_keep_their_pixels() {
+        let png = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/icons/heart.png"
+        ));
+        let png = png.unwrap();
+        let (width, height, mask) = raster::decode_png(&png).unwrap();
+        let generate = |format, px| {
+            let mut font = load_font(DEMO_TTF).unwrap();
+            font.add_png_icon("heart", '\u{e000}', &png, 2, Some(12))
+                .unwrap();
+            let loaded = expand_family(
+                "ICONS",
+                font,
+                &[px],
+                &[format],
+                vec!['\u{e000}'],
+                GenerationOptions::default(),
+            )
+            .remove(0);
+            GeneratedFont::new(loaded).unwrap().glyphs.remove(0)
+        };
+
+        // The same pixels at every size, thresholded back to the image.
+        for (px, spread) in [(16, 1.0), (48, 4.0)] {
+            let (bitmap, entry) = generate(BitmapFormat::Bitmap { spread, padding: 0 }, px);
+            assert_eq!((entry.width, entry.height), (width as i32, height as i32));
+            assert_eq!((entry.xmin, entry.ymin, entry.advance_width), (0, -2, 12));
+            for (i, &inked) in mask.iter().enumerate() {
+                assert_eq!(bitmap[i / 8] & (0x80 >> (i % 8)) != 0, inked);
+            }
+        }
+
+        let (sdf, entry) = generate(
+            BitmapFormat::SDF {
+                spread: 2.0,
+                padding: 2,
+            },
+            16,
+        );
+        assert_eq!(
+            (entry.width, entry.height),
+            (width as i32 + 4, height as i32 + 4)
+        );
+        assert_eq!((entry.xmin, entry.ymin), (-2, -4));
+        assert!(!sdf.is_empty());
+    }
+
+    #[test]
+    fn test_color_glyphs_are_layered() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/color.ttf");
+        let font = load_font(path).unwrap();
+        assert_eq!(font.color_layers('A').len(), 2);
+        let frame = font.metrics(NOTDEF, 32.0).unwrap();
+        let base = font.metrics('A', 32.0).unwrap();
+        let loaded = expand_family(
+            "COLOR",
+            font,
+            &[32],
+            &[BitmapFormat::Bitmap {
+                spread: 2.0,
+                padding: 0,
+            }],
+            vec!['A'],
+            GenerationOptions {
+                include_notdef: true,
+                color: true,
+                ..Default::default()
+            },
+        )
+        .remove(0);
+        let generated = GeneratedFont::new(loaded).unwrap();
+
+        // Palette index, then color, of every layer.
+        let layers = |bitmap: &[u8]| {
+            let mut layers = Vec::new();
+            let mut rest = bitmap;
+            while !rest.is_empty() {
+                let index = u16::from_le_bytes([rest[0], rest[1]]);
+                let color = u32::from_le_bytes(rest[2..6].try_into().unwrap());
+                let len = u32::from_le_bytes(rest[14..18].try_into().unwrap()) as usize;
+                layers.push((index, color));
+                rest = &rest[18 + len..];
+            }
+            layers
+        };
+
+        // The box of the glyph covers both layers, the advance is the one of 'A'.
+        let (bitmap, entry) = &generated.glyphs[0];
+        assert_eq!(layers(bitmap), [(0, 0xFFFF_0000), (color::FOREGROUND, 0)]);
+        let right = |m: &font::Metrics| m.xmin + m.width;
+        let top = |m: &font::Metrics| m.ymin + m.height;
+        assert_eq!(entry.xmin, frame.xmin.min(base.xmin));
+        assert_eq!(entry.ymin, frame.ymin.min(base.ymin));
+        assert_eq!(entry.xmin + entry.width, right(&frame).max(right(&base)));
+        assert_eq!(entry.ymin + entry.height, top(&frame).max(top(&base)));
+        assert_eq!(entry.advance_width, base.advance_width);
+
+        // Glyphs without layers are a single one in the text color.
+        let (bitmap, notdef) = &generated.glyphs[1];
+        assert_eq!(notdef.character, NOTDEF);
+        assert_eq!(layers(bitmap), [(color::FOREGROUND, 0)]);
+    }
+
+    #[test]
+    fn test_bitmap_fonts_keep_their_pixels() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/bitmap.bdf");
+        let generate = |px| {
+            let loaded = expand_family(
+                "PIXELS",
+                load_font(path).unwrap(),
+                &[px],
+                &[BitmapFormat::Bitmap {
+                    spread: 1.0,
+                    padding: 0,
+                }],
+                vec![' ', 'A', 'g'],
+                GenerationOptions {
+                    include_notdef: true,
+                    ..Default::default()
+                },
+            )
+            .remove(0);
+            GeneratedFont::new(loaded)
+        };
+
+        let font = generate(10).unwrap();
+        assert_eq!((font.ascent(), font.descent()), (8, -2));
+        assert_eq!(characters(&font), [' ', 'A', 'g', NOTDEF]);
+        let (bitmap, a) = &font.glyphs[1];
+        assert_eq!((a.width, a.height, a.advance_width), (5, 7, 6));
+        // `.###.` then `#...#`, packed without padding between rows.
+        assert_eq!(bitmap[0], 0b0111_0100);
+        let (_, g) = &font.glyphs[2];
+        assert_eq!((g.xmin, g.ymin, g.width, g.height), (1, -2, 4, 7));
+        let (_, space) = &font.glyphs[0];
+        assert_eq!((space.width, space.advance_width), (0, 6));
+
+        assert!(matches!(
+            generate(12),
+            Err(Error::FixedSize {
+                size: 12,
+                pixel_size: 10,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,244 @@
+//! Glyphs imported from PNG images: decoded to a mask of inked pixels at their own resolution,
+//! and turned into distance fields with a Euclidean distance transform.
+
+use crate::generator::sdf_generation::SdfRaster;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A glyph drawn pixel by pixel, not scaled with the font size.
+pub(crate) struct RasterGlyph {
+    pub width: u32,
+    pub height: u32,
+    /// Columns between the pen position and the left of the image.
+    pub xmin: i32,
+    /// Rows of the image below the baseline.
+    pub baseline: i32,
+    pub advance: i32,
+    /// Whether every pixel is inked, row by row.
+    pub mask: Vec<bool>,
+}
+
+pub(crate) fn is_png(data: &[u8]) -> bool {
+    data.starts_with(PNG_SIGNATURE)
+}
+
+/// Decodes a PNG into a mask: pixels are inked if at least half opaque, or at least half dark
+/// for images without transparency. Returns the width, the height and the mask.
+pub(crate) fn decode_png(data: &[u8]) -> Result<(u32, u32, Vec<bool>), String> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|err| err.to_string())?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let luminance = |r: u8, g: u8, b: u8| (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+    let mask = match info.color_type {
+        png::ColorType::Grayscale => pixels.iter().map(|&v| v < 128).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks_exact(2).map(|p| p[1] >= 128).collect(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .map(|p| luminance(p[0], p[1], p[2]) < 128)
+            .collect(),
+        png::ColorType::Rgba => pixels.chunks_exact(4).map(|p| p[3] >= 128).collect(),
+        png::ColorType::Indexed => return Err("indexed colors were not expanded".into()),
+    };
+    Ok((info.width, info.height, mask))
+}
+
+/// Distance field of `mask` with the encoding of `sdf_generate`: the edges of the inked pixels
+/// map to `0.5`, and the field saturates `spread` pixels away. `padding` pixels are added on
+/// every side.
+pub(crate) fn distance_field(
+    mask: &[bool],
+    width: u32,
+    height: u32,
+    padding: i32,
+    spread: f32,
+) -> SdfRaster {
+    let spread = spread.max(f32::EPSILON);
+    // Pixels out of the image count as not inked, also beyond the padding, so that the field
+    // fades out at the edges of the image as it does everywhere else.
+    let margin = padding.max(0) as usize + spread.ceil() as usize + 1;
+    let (grid_w, grid_h) = (width as usize + 2 * margin, height as usize + 2 * margin);
+    let inked = |x: usize, y: usize| {
+        let (x, y) = (x.wrapping_sub(margin), y.wrapping_sub(margin));
+        x < width as usize && y < height as usize && mask[y * width as usize + x]
+    };
+
+    // Squared distances from every pixel to the nearest pixel of the other kind.
+    let to_blank = squared_distances(grid_w, grid_h, |x, y| !inked(x, y));
+    let to_inked = squared_distances(grid_w, grid_h, inked);
+
+    let (out_w, out_h) = (
+        width as usize + 2 * padding.max(0) as usize,
+        height as usize + 2 * padding.max(0) as usize,
+    );
+    let offset = margin - padding.max(0) as usize;
+    let mut buffer = Vec::with_capacity(out_w * out_h);
+    for y in offset..offset + out_h {
+        for x in offset..offset + out_w {
+            let index = y * grid_w + x;
+            // Pixel centers are half a pixel away from the edge of the pixel next to them.
+            let distance = match inked(x, y) {
+                true => to_blank[index].sqrt() - 0.5,
+                false => -(to_inked[index].sqrt() - 0.5),
+            };
+            buffer.push((0.5 + distance / spread * 0.5).clamp(0.0, 1.0));
+        }
+    }
+
+    SdfRaster {
+        width: out_w as u32,
+        height: out_h as u32,
+        buffer,
+    }
+}
+
+/// Squared Euclidean distance from every pixel to the nearest one where `feature` is true, with
+/// the separable algorithm of Felzenszwalb and Huttenlocher.
+fn squared_distances(
+    width: usize,
+    height: usize,
+    feature: impl Fn(usize, usize) -> bool,
+) -> Vec<f32> {
+    const FAR: f32 = 1e12;
+    let mut grid: Vec<f32> = (0..width * height)
+        .map(|i| {
+            if feature(i % width, i / width) {
+                0.0
+            } else {
+                FAR
+            }
+        })
+        .collect();
+
+    let mut line = Vec::with_capacity(width.max(height));
+    for x in 0..width {
+        line.clear();
+        line.extend((0..height).map(|y| grid[y * width + x]));
+        for (y, distance) in transform_line(&line).into_iter().enumerate() {
+            grid[y * width + x] = distance;
+        }
+    }
+    for y in 0..height {
+        let row = &mut grid[y * width..(y + 1) * width];
+        let distances = transform_line(row);
+        row.copy_from_slice(&distances);
+    }
+    grid
+}
+
+/// One dimensional distance transform: the lower envelope of the parabolas rooted at every
+/// sample.
+fn transform_line(f: &[f32]) -> Vec<f32> {
+    let n = f.len();
+    let mut vertices = vec![0usize; n];
+    let mut bounds = vec![0f32; n + 1];
+    let mut k = 0;
+    bounds[0] = f32::NEG_INFINITY;
+    bounds[1] = f32::INFINITY;
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * q as f32 - 2.0 * p as f32)
+    };
+    for q in 1..n {
+        let mut s = intersection(q, vertices[k]);
+        while s <= bounds[k] {
+            k -= 1;
+            s = intersection(q, vertices[k]);
+        }
+        k += 1;
+        vertices[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = f32::INFINITY;
+    }
+
+    let mut distances = vec![0.0; n];
+    k = 0;
+    for (q, distance) in distances.iter_mut().enumerate() {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - vertices[k] as f32;
+        *distance = offset * offset + f[vertices[k]];
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mask from rows of `#` (inked) and `.`.
+    fn mask(rows: &[&str]) -> (u32, u32, Vec<bool>) {
+        let pixels = rows.iter().flat_map(|row| row.chars().map(|c| c == '#'));
+        (rows[0].len() as u32, rows.len() as u32, pixels.collect())
+    }
+
+    #[test]
+    fn test_squared_distances() {
+        let distances = squared_distances(5, 3, |x, y| (x, y) == (0, 0));
+        assert_eq!(distances[0], 0.0);
+        assert_eq!(distances[4], 16.0);
+        assert_eq!(distances[2 * 5 + 3], 13.0);
+    }
+
+    #[test]
+    fn test_distance_field_thresholds_to_mask() {
+        let (width, height, pixels) = mask(&["..#..", ".###.", "#####", ".#.#."]);
+        for spread in [1.0, 2.0, 8.0] {
+            let sdf = distance_field(&pixels, width, height, 2, spread);
+            assert_eq!((sdf.width, sdf.height), (9, 8));
+            for y in 0..height {
+                for x in 0..width {
+                    let value = sdf.buffer[((y + 2) * sdf.width + x + 2) as usize];
+                    assert_eq!(value > 0.5, pixels[(y * width + x) as usize]);
+                }
+            }
+            // Padding is outside, and fades out with the distance.
+            assert!(sdf.buffer[0] <= sdf.buffer[sdf.width as usize + 1]);
+            assert!(sdf.buffer[0] < 0.5);
+        }
+    }
+
+    #[test]
+    fn test_distance_field_spread() {
+        let (width, height, pixels) = mask(&["#......."]);
+        let sdf = distance_field(&pixels, width, height, 0, 2.0);
+        // 0.5 per `spread` pixels from the edge, saturating beyond it.
+        let expected = [0.625, 0.375, 0.125, 0.0, 0.0];
+        for (value, expected) in sdf.buffer.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6, "{value} != {expected}");
+        }
+    }
+
+    #[test]
+    fn test_decode_png() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 3, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels = [
+            [0, 0, 0, 255],
+            [255, 255, 255, 0],
+            [255, 0, 0, 200],
+            [0, 0, 0, 100],
+            [0, 0, 0, 128],
+            [0, 0, 0, 0],
+        ];
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(pixels.as_flattened())
+            .unwrap();
+
+        assert!(is_png(&data));
+        let (width, height, mask) = decode_png(&data).unwrap();
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(mask, [true, false, true, false, true, false]);
+        assert!(decode_png(&data[..20]).is_err());
+        assert!(!is_png(b"<svg/>"));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::config::FillRule;
+use crate::generator::line;
+
+pub struct SdfRaster {
+    pub width: u32,
+    pub height: u32,
+    pub buffer: Vec<f32>,
+}
+
+/// Rasterizes `lines`, given in pixel coordinates with y pointing down, into a distance field.
+///
+/// Distances are measured in pixels, the same in both axes: the edge of the outline maps to
+/// `0.5`, and the field saturates to `0.0` (outside) or `1.0` (inside) `spread` pixels away.
+/// Which pixels are inside is decided by `fill_rule`.
+pub fn sdf_generate(
+    width: u32,
+    height: u32,
+    spread: f32,
+    fill_rule: FillRule,
+    lines: &[line::Line],
+) -> SdfRaster {
+    let spread = spread.max(f32::EPSILON);
+    let buffer_size = (width * height) as usize;
+    let mut image_buffer: Vec<f32> = vec![0.0; buffer_size];
+
+    let bounds: Vec<SegmentBounds> = lines.iter().map(SegmentBounds::new).collect();
+    let mut candidates: Vec<usize> = Vec::with_capacity(lines.len());
+
+    for y in 0..height {
+        let py = y as f32 + 0.5;
+        let scanline = scanline(py, lines);
+
+        // Pixels further than `spread` from the outline saturate, so only segments that can
+        // be closer than that to this row are worth measuring.
+        candidates.clear();
+        candidates
+            .extend((0..lines.len()).filter(|&i| bounds[i].distance_y(py) < spread + PRUNE_MARGIN));
+
+        for x in 0..width {
+            let px = x as f32 + 0.5;
+            let index = (x + (width * y)) as usize;
+
+            let mut min_distance = spread;
+            for &i in &candidates {
+                if bounds[i].distance(px, py) >= min_distance + PRUNE_MARGIN {
+                    continue;
+                }
+                let d = lines[i].distance(px, py);
+                if d < min_distance {
+                    min_distance = d;
+                }
+            }
+
+            min_distance = 0.5 - (min_distance / spread) * 0.5;
+            image_buffer[index] = min_distance.clamp(0.0, 1.0);
+
+            if scanline_scan(&scanline, px, fill_rule) {
+                image_buffer[index] = 1.0 - image_buffer[index];
+            }
+        }
+    }
+
+    SdfRaster {
+        width,
+        height,
+        buffer: image_buffer,
+    }
+}
+
+/// Slack on the bounding box test, so rounding in `Line::distance` never makes a pruned segment
+/// the closest one.
+const PRUNE_MARGIN: f32 = 1e-3;
+
+/// Bounding box of a segment's points. Curves lie inside the box of their control points, so
+/// the distance to the box is a lower bound of the distance to the segment.
+struct SegmentBounds {
+    xmin: f32,
+    ymin: f32,
+    xmax: f32,
+    ymax: f32,
+}
+
+impl SegmentBounds {
+    fn new(line: &line::Line) -> Self {
+        let points = match *line {
+            line::Line::Line { start, end } => [start, end, start, end],
+            line::Line::Quad {
+                start,
+                end,
+                control,
+            } => [start, end, control, control],
+            line::Line::Curve {
+                start,
+                end,
+                first_control,
+                second_control,
+            } => [start, end, first_control, second_control],
+        };
+
+        let mut bounds = SegmentBounds {
+            xmin: f32::INFINITY,
+            ymin: f32::INFINITY,
+            xmax: f32::NEG_INFINITY,
+            ymax: f32::NEG_INFINITY,
+        };
+        for point in points {
+            bounds.xmin = bounds.xmin.min(point[0]);
+            bounds.ymin = bounds.ymin.min(point[1]);
+            bounds.xmax = bounds.xmax.max(point[0]);
+            bounds.ymax = bounds.ymax.max(point[1]);
+        }
+        bounds
+    }
+
+    fn distance_y(&self, y: f32) -> f32 {
+        (self.ymin - y).max(y - self.ymax).max(0.0)
+    }
+
+    fn distance(&self, x: f32, y: f32) -> f32 {
+        let dx = (self.xmin - x).max(x - self.xmax).max(0.0);
+        let dy = self.distance_y(y);
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+pub fn sdf_to_bitmap(sdf: &SdfRaster) -> Vec<u8> {
+    let width = sdf.width;
+    let height = sdf.height;
+    let mut buffer: Vec<u8> = vec![0u8; (width * height) as usize];
+
+    for x in 0..width {
+        for y in 0..height {
+            let index = (x + (width * y)) as usize;
+            buffer[index] = (sdf.buffer[index] * 255.0) as u8;
+        }
+    }
+
+    buffer
+}
+
+pub fn sdf_bitmap_to_fixed_bitmap<F>(
+    sdf_data: &[u8],
+    width: i32,
+    height: i32,
+    predicate: F,
+) -> Vec<u8>
+where
+    F: Fn(u8) -> bool,
+{
+    let total_pixels = (width * height) as usize;
+    let bitmap_size = total_pixels.div_ceil(8);
+    let mut bitmap = vec![0u8; bitmap_size];
+
+    for (i, sdf_i) in sdf_data.iter().enumerate().take(total_pixels) {
+        if predicate(*sdf_i) {
+            let byte_index = i / 8;
+            let bit_index = i % 8;
+            bitmap[byte_index] |= 1 << (7 - bit_index);
+        }
+    }
+
+    bitmap
+}
+
+struct Scanline {
+    /// x coordinate and winding of every crossing.
+    intersections: Vec<(f32, i32)>,
+}
+
+fn scanline(y: f32, lines: &[line::Line]) -> Scanline {
+    let mut scanline = Scanline {
+        intersections: Vec::with_capacity(16),
+    };
+    let mut x = [(0.0, 0); 3];
+
+    for line in lines {
+        let count = line.intersections(y, &mut x);
+        for i in x.iter().take(count) {
+            scanline.intersections.push(*i);
+        }
+    }
+
+    if !scanline.intersections.is_empty() {
+        scanline
+            .intersections
+            .sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        // Segments sharing an endpoint on the scanline report the same crossing twice.
+        scanline.intersections.dedup();
+    }
+
+    scanline
+}
+
+fn scanline_scan(scanline: &Scanline, x: f32, fill_rule: FillRule) -> bool {
+    let crossings = scanline
+        .intersections
+        .iter()
+        .filter(|(inter, _)| x < *inter);
+
+    match fill_rule {
+        FillRule::NonZero => crossings.map(|(_, winding)| winding).sum::<i32>() != 0,
+        FillRule::EvenOdd => crossings.count() % 2 == 1,
+    }
+}
+
+pub fn mix(v1: f32, v2: f32, weight: f32) -> f32 {
+    v1 + (v2 - v1) * weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::line::Line;
+    use crate::generator::vec2::vec2;
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<Line> {
+        let corners = [vec2(x0, y0), vec2(x1, y0), vec2(x1, y1), vec2(x0, y1)];
+        (0..4)
+            .map(|i| Line::Line {
+                start: corners[i],
+                end: corners[(i + 1) % 4],
+            })
+            .collect()
+    }
+
+    fn value(sdf: &SdfRaster, x: u32, y: u32) -> f32 {
+        sdf.buffer[(x + y * sdf.width) as usize]
+    }
+
+    /// Two squares drawn in the same direction, overlapping on 4..8.
+    fn overlapping_squares() -> Vec<Line> {
+        let mut lines = rect(2.0, 2.0, 8.0, 8.0);
+        lines.extend(rect(4.0, 4.0, 10.0, 10.0));
+        lines
+    }
+
+    #[test]
+    fn test_nonzero_fills_overlapping_contours() {
+        let sdf = sdf_generate(12, 12, 2.0, FillRule::NonZero, &overlapping_squares());
+        assert!(value(&sdf, 6, 6) > 0.5);
+        assert!(value(&sdf, 3, 3) > 0.5);
+        assert!(value(&sdf, 9, 9) > 0.5);
+        assert!(value(&sdf, 9, 2) < 0.5);
+    }
+
+    #[test]
+    fn test_even_odd_leaves_overlap_empty() {
+        let sdf = sdf_generate(12, 12, 2.0, FillRule::EvenOdd, &overlapping_squares());
+        assert!(value(&sdf, 6, 6) < 0.5);
+        assert!(value(&sdf, 3, 3) > 0.5);
+        assert!(value(&sdf, 9, 9) > 0.5);
+    }
+
+    #[test]
+    fn test_nonzero_keeps_counter_holes() {
+        // Like an 'O': the inner contour goes the other way around.
+        let mut lines = rect(2.0, 2.0, 10.0, 10.0);
+        lines.extend(
+            rect(4.0, 4.0, 8.0, 8.0)
+                .into_iter()
+                .rev()
+                .map(|line| match line {
+                    Line::Line { start, end } => Line::Line {
+                        start: end,
+                        end: start,
+                    },
+                    other => other,
+                }),
+        );
+        let sdf = sdf_generate(12, 12, 2.0, FillRule::NonZero, &lines);
+        assert!(value(&sdf, 6, 6) < 0.5);
+        assert!(value(&sdf, 3, 6) > 0.5);
+    }
+
+    /// Every pixel measured against every segment, no pruning.
+    fn naive_sdf(width: u32, height: u32, spread: f32, lines: &[Line]) -> Vec<f32> {
+        let mut buffer = vec![0.0; (width * height) as usize];
+        for y in 0..height {
+            let py = y as f32 + 0.5;
+            let scanline = scanline(py, lines);
+            for x in 0..width {
+                let px = x as f32 + 0.5;
+                let d = lines
+                    .iter()
+                    .map(|line| line.distance(px, py))
+                    .fold(f32::MAX, |min, d| if d < min { d } else { min });
+                let mut value = (0.5 - (d / spread) * 0.5).clamp(0.0, 1.0);
+                if scanline_scan(&scanline, px, FillRule::NonZero) {
+                    value = 1.0 - value;
+                }
+                buffer[(x + y * width) as usize] = value;
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_pruning_matches_naive_output() {
+        // A closed contour mixing every kind of segment, with pseudo-random control points.
+        let mut seed = 0x2545_f491_u32;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            2.0 + (seed % 2800) as f32 / 100.0
+        };
+        let points: Vec<_> = (0..24).map(|_| vec2(next(), next())).collect();
+        let mut lines = Vec::new();
+        for i in (0..points.len()).step_by(3) {
+            let (start, end) = (points[i], points[(i + 3) % points.len()]);
+            lines.push(match i % 9 {
+                0 => Line::Line { start, end },
+                3 => Line::Quad {
+                    start,
+                    end,
+                    control: points[i + 1],
+                },
+                _ => Line::Curve {
+                    start,
+                    end,
+                    first_control: points[i + 1],
+                    second_control: points[i + 2],
+                },
+            });
+        }
+
+        for spread in [0.5, 2.0, 4.0, 40.0] {
+            let sdf = sdf_generate(32, 32, spread, FillRule::NonZero, &lines);
+            let naive = naive_sdf(32, 32, spread, &lines);
+            assert!(
+                sdf.buffer
+                    .iter()
+                    .zip(&naive)
+                    .all(|(a, b)| a.to_bits() == b.to_bits()),
+                "output differs with spread {spread}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spread_is_in_pixels_on_both_axes() {
+        // Tall and narrow, like an 'l'.
+        let sdf = sdf_generate(16, 32, 3.0, FillRule::NonZero, &rect(6.0, 2.0, 10.0, 30.0));
+        let at = |x: u32, y: u32| sdf.buffer[(x + y * sdf.width) as usize];
+
+        // 1.5px left of the left edge and 1.5px above the top edge.
+        assert!((at(4, 16) - 0.25).abs() < 1e-4);
+        assert!((at(8, 0) - 0.25).abs() < 1e-4);
+        // Far outside and deep inside saturate.
+        assert_eq!(at(0, 16), 0.0);
+        assert!((at(7, 16) - 0.75).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,925 @@
+//! Parsing of SVG icons into outlines, so they can be generated as glyphs.
+//!
+//! Only filled geometry is supported: `path`, `rect`, `circle`, `ellipse`, `polygon` and
+//! `polyline`, with `transform`s on them and on their groups, filled with their `fill-rule`.
+//! Strokes, gradients, masks and anything inside `defs` are ignored.
+
+use std::f32::consts::PI;
+
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::config::FillRule;
+
+/// A segment of an outline, in the coordinates of the `viewBox` (y pointing down).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Segment {
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    QuadTo([f32; 2], [f32; 2]),
+    CurveTo([f32; 2], [f32; 2], [f32; 2]),
+    Close,
+}
+
+/// The outlines of an icon and the area of the canvas they are drawn on.
+#[derive(Debug)]
+pub(crate) struct Icon {
+    /// `x`, `y`, `width` and `height` of the `viewBox`.
+    pub view_box: [f32; 4],
+    pub segments: Vec<Segment>,
+    /// Rule every shape is filled with, `nonzero` unless they say otherwise.
+    pub fill_rule: FillRule,
+}
+
+/// What an element passes on to its children.
+#[derive(Clone, Copy)]
+struct Inherited {
+    transform: Transform,
+    /// Not hidden by `display` or by an element that isn't rendered.
+    visible: bool,
+    /// `fill` isn't `none`, which children can override.
+    filled: bool,
+    fill_rule: FillRule,
+}
+
+impl Inherited {
+    const ROOT: Self = Self {
+        transform: Transform::IDENTITY,
+        visible: true,
+        filled: true,
+        fill_rule: FillRule::NonZero,
+    };
+}
+
+/// Elements whose content is never drawn directly.
+const NOT_RENDERED: [&str; 9] = [
+    "defs", "clipPath", "mask", "symbol", "marker", "pattern", "title", "desc", "metadata",
+];
+
+pub(crate) fn parse(data: &str) -> Result<Icon, String> {
+    let mut view_box = None;
+    let mut segments = Vec::new();
+    let mut fill_rule = None;
+    // What every open element passes on to its children.
+    let mut stack: Vec<Inherited> = Vec::new();
+
+    for event in EventReader::from_str(data) {
+        match event.map_err(|err| err.to_string())? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == key)
+                        .map(|attribute| attribute.value.as_str())
+                };
+                let element = name.local_name.as_str();
+
+                if stack.is_empty() {
+                    if element != "svg" {
+                        return Err(format!("expected an <svg> element, found <{element}>"));
+                    }
+                    view_box = Some(root_view_box(
+                        attribute("viewBox"),
+                        attribute("width"),
+                        attribute("height"),
+                    )?);
+                }
+
+                let parent = stack.last().copied().unwrap_or(Inherited::ROOT);
+                let style = attribute("style");
+                let current = Inherited {
+                    transform: match attribute("transform") {
+                        Some(transform) => parent.transform.then(&parse_transform(transform)?),
+                        None => parent.transform,
+                    },
+                    visible: parent.visible
+                        && !NOT_RENDERED.contains(&element)
+                        && attribute("display") != Some("none"),
+                    filled: match property("fill", attribute("fill"), style) {
+                        None | Some("inherit") => parent.filled,
+                        Some(fill) => fill != "none",
+                    },
+                    fill_rule: match property("fill-rule", attribute("fill-rule"), style) {
+                        None | Some("inherit") => parent.fill_rule,
+                        Some("nonzero") => FillRule::NonZero,
+                        Some("evenodd") => FillRule::EvenOdd,
+                        Some(rule) => {
+                            return Err(format!("<{element}>: invalid fill-rule '{rule}'"));
+                        }
+                    },
+                };
+                stack.push(current);
+
+                if current.visible && current.filled {
+                    let start = segments.len();
+                    shape(element, &attribute, &mut segments)
+                        .map_err(|err| format!("<{element}>: {err}"))?;
+                    for segment in &mut segments[start..] {
+                        *segment = current.transform.segment(*segment);
+                    }
+                    // A glyph is filled with a single rule, shapes can't pick their own.
+                    if segments.len() > start
+                        && *fill_rule.get_or_insert(current.fill_rule) != current.fill_rule
+                    {
+                        return Err(format!(
+                            "<{element}>: shapes with both the nonzero and the evenodd \
+                             fill-rule aren't supported"
+                        ));
+                    }
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let view_box = view_box.ok_or("no <svg> element")?;
+    Ok(Icon {
+        view_box,
+        segments: split_at_extremes(segments),
+        fill_rule: fill_rule.unwrap_or_default(),
+    })
+}
+
+/// Splits curves where they are the furthest along either axis. Glyph bounds only look at the
+/// end points of segments, which fonts put at the extremes of their curves but SVG files don't.
+fn split_at_extremes(segments: Vec<Segment>) -> Vec<Segment> {
+    let mut out = Vec::with_capacity(segments.len());
+    let (mut current, mut start) = ([0.0, 0.0], [0.0, 0.0]);
+    for segment in segments {
+        match segment {
+            Segment::MoveTo(p) => {
+                (current, start) = (p, p);
+                out.push(segment);
+            }
+            Segment::LineTo(p) => {
+                current = p;
+                out.push(segment);
+            }
+            Segment::Close => {
+                current = start;
+                out.push(segment);
+            }
+            Segment::QuadTo(c, p) => {
+                let mut ts = Vec::new();
+                for axis in 0..2 {
+                    let denominator = current[axis] - 2.0 * c[axis] + p[axis];
+                    if denominator != 0.0 {
+                        ts.push((current[axis] - c[axis]) / denominator);
+                    }
+                }
+                let mut points = [current, c, p];
+                for t in split_points(ts) {
+                    let [p0, p1, p2] = points;
+                    let (a, b) = (lerp(p0, p1, t), lerp(p1, p2, t));
+                    let middle = lerp(a, b, t);
+                    out.push(Segment::QuadTo(a, middle));
+                    points = [middle, b, p2];
+                }
+                out.push(Segment::QuadTo(points[1], points[2]));
+                current = p;
+            }
+            Segment::CurveTo(c1, c2, p) => {
+                // Roots of the derivative, a t² + b t + c.
+                let mut ts = Vec::new();
+                for axis in 0..2 {
+                    let (p0, p1, p2, p3) = (current[axis], c1[axis], c2[axis], p[axis]);
+                    let a = -p0 + 3.0 * p1 - 3.0 * p2 + p3;
+                    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+                    let c = p1 - p0;
+                    if a.abs() < 1e-6 {
+                        if b != 0.0 {
+                            ts.push(-c / b);
+                        }
+                    } else {
+                        let discriminant = b * b - 4.0 * a * c;
+                        if discriminant >= 0.0 {
+                            let root = discriminant.sqrt();
+                            ts.push((-b + root) / (2.0 * a));
+                            ts.push((-b - root) / (2.0 * a));
+                        }
+                    }
+                }
+                let mut points = [current, c1, c2, p];
+                for t in split_points(ts) {
+                    let [p0, p1, p2, p3] = points;
+                    let (a, b, c) = (lerp(p0, p1, t), lerp(p1, p2, t), lerp(p2, p3, t));
+                    let (d, e) = (lerp(a, b, t), lerp(b, c, t));
+                    let middle = lerp(d, e, t);
+                    out.push(Segment::CurveTo(a, d, middle));
+                    points = [middle, e, c, p3];
+                }
+                out.push(Segment::CurveTo(points[1], points[2], points[3]));
+                current = p;
+            }
+        }
+    }
+    out
+}
+
+/// Sorted parameters strictly inside a curve, each relative to the part left after the
+/// previous split.
+fn split_points(mut ts: Vec<f32>) -> impl Iterator<Item = f32> {
+    ts.retain(|t| *t > 1e-4 && *t < 1.0 - 1e-4);
+    ts.sort_by(f32::total_cmp);
+    ts.dedup_by(|a, b| (*a - *b).abs() < 1e-4);
+    let mut previous = 0.0;
+    ts.into_iter().map(move |t| {
+        let relative = (t - previous) / (1.0 - previous);
+        previous = t;
+        relative
+    })
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+/// `viewBox` of the root, or the canvas given by `width` and `height`.
+fn root_view_box(
+    view_box: Option<&str>,
+    width: Option<&str>,
+    height: Option<&str>,
+) -> Result<[f32; 4], String> {
+    let view_box = match view_box {
+        Some(view_box) => {
+            let values = numbers(view_box)?;
+            <[f32; 4]>::try_from(values).map_err(|_| format!("invalid viewBox '{view_box}'"))?
+        }
+        None => {
+            let length = |value: Option<&str>, name: &str| {
+                let value = value.ok_or_else(|| format!("no viewBox nor {name}"))?;
+                value
+                    .trim()
+                    .trim_end_matches("px")
+                    .parse::<f32>()
+                    .map_err(|_| format!("invalid {name} '{value}'"))
+            };
+            [0.0, 0.0, length(width, "width")?, length(height, "height")?]
+        }
+    };
+    if view_box[2] <= 0.0 || view_box[3] <= 0.0 {
+        return Err("the canvas is empty".into());
+    }
+    Ok(view_box)
+}
+
+fn property<'a>(name: &str, attribute: Option<&'a str>, style: Option<&'a str>) -> Option<&'a str> {
+    let styled = style.and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            (property.trim() == name).then_some(value)
+        })
+    });
+    styled.or(attribute).map(str::trim)
+}
+
+/// Appends the outline of a shape element, nothing for any other element.
+fn shape<'a>(
+    element: &str,
+    attribute: &dyn Fn(&str) -> Option<&'a str>,
+    out: &mut Vec<Segment>,
+) -> Result<(), String> {
+    let number = |name: &str| -> Result<f32, String> {
+        match attribute(name) {
+            None => Ok(0.0),
+            Some(value) => value
+                .trim()
+                .trim_end_matches("px")
+                .parse()
+                .map_err(|_| format!("invalid {name} '{value}'")),
+        }
+    };
+
+    match element {
+        "path" => path_data(attribute("d").unwrap_or(""), out)?,
+        "rect" => {
+            let (x, y, width, height) = (
+                number("x")?,
+                number("y")?,
+                number("width")?,
+                number("height")?,
+            );
+            if width <= 0.0 || height <= 0.0 {
+                return Ok(());
+            }
+            // A missing radius takes the value of the other one.
+            let (rx, ry) = match (attribute("rx"), attribute("ry")) {
+                (None, None) => (0.0, 0.0),
+                (Some(_), None) => (number("rx")?, number("rx")?),
+                (None, Some(_)) => (number("ry")?, number("ry")?),
+                _ => (number("rx")?, number("ry")?),
+            };
+            let (rx, ry) = (rx.clamp(0.0, width / 2.0), ry.clamp(0.0, height / 2.0));
+            if rx == 0.0 || ry == 0.0 {
+                out.extend([
+                    Segment::MoveTo([x, y]),
+                    Segment::LineTo([x + width, y]),
+                    Segment::LineTo([x + width, y + height]),
+                    Segment::LineTo([x, y + height]),
+                    Segment::Close,
+                ]);
+            } else {
+                let (right, bottom) = (x + width, y + height);
+                out.push(Segment::MoveTo([x + rx, y]));
+                out.push(Segment::LineTo([right - rx, y]));
+                arc(
+                    out,
+                    [right - rx, y],
+                    [rx, ry],
+                    0.0,
+                    false,
+                    true,
+                    [right, y + ry],
+                );
+                out.push(Segment::LineTo([right, bottom - ry]));
+                arc(
+                    out,
+                    [right, bottom - ry],
+                    [rx, ry],
+                    0.0,
+                    false,
+                    true,
+                    [right - rx, bottom],
+                );
+                out.push(Segment::LineTo([x + rx, bottom]));
+                arc(
+                    out,
+                    [x + rx, bottom],
+                    [rx, ry],
+                    0.0,
+                    false,
+                    true,
+                    [x, bottom - ry],
+                );
+                out.push(Segment::LineTo([x, y + ry]));
+                arc(out, [x, y + ry], [rx, ry], 0.0, false, true, [x + rx, y]);
+                out.push(Segment::Close);
+            }
+        }
+        "circle" | "ellipse" => {
+            let (cx, cy) = (number("cx")?, number("cy")?);
+            let (rx, ry) = match element {
+                "circle" => (number("r")?, number("r")?),
+                _ => (number("rx")?, number("ry")?),
+            };
+            if rx <= 0.0 || ry <= 0.0 {
+                return Ok(());
+            }
+            out.push(Segment::MoveTo([cx + rx, cy]));
+            arc(
+                out,
+                [cx + rx, cy],
+                [rx, ry],
+                0.0,
+                false,
+                true,
+                [cx - rx, cy],
+            );
+            arc(
+                out,
+                [cx - rx, cy],
+                [rx, ry],
+                0.0,
+                false,
+                true,
+                [cx + rx, cy],
+            );
+            out.push(Segment::Close);
+        }
+        "polygon" | "polyline" => {
+            let values = numbers(attribute("points").unwrap_or(""))?;
+            let mut points = values.chunks_exact(2).map(|pair| [pair[0], pair[1]]);
+            if let Some(first) = points.next() {
+                out.push(Segment::MoveTo(first));
+                out.extend(points.map(Segment::LineTo));
+                // Filling closes polylines too.
+                out.push(Segment::Close);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Appends the segments of the path data `d`, with arcs converted to cubic curves.
+fn path_data(d: &str, out: &mut Vec<Segment>) -> Result<(), String> {
+    let mut tokens = PathTokens {
+        data: d.as_bytes(),
+        pos: 0,
+    };
+    let mut current = [0.0, 0.0];
+    let mut start = [0.0, 0.0];
+    // Reflected control point for `S` and `T`, with the command that set it.
+    let mut last_control: Option<(u8, [f32; 2])> = None;
+    let mut command = None;
+
+    while let Some(next) = tokens.command_or_number()? {
+        let letter = match next {
+            Token::Command(letter) => letter,
+            // More coordinates repeat the previous command, `M` continuing as `L`.
+            Token::Number => match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(letter) => letter,
+                None => return Err(format!("path data '{d}' doesn't start with a command")),
+            },
+        };
+        command = Some(letter);
+        let relative = letter.is_ascii_lowercase();
+        let point = |tokens: &mut PathTokens, current: [f32; 2]| -> Result<[f32; 2], String> {
+            let (x, y) = (tokens.number()?, tokens.number()?);
+            Ok(if relative {
+                [current[0] + x, current[1] + y]
+            } else {
+                [x, y]
+            })
+        };
+
+        let mut control = None;
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                current = point(&mut tokens, current)?;
+                start = current;
+                out.push(Segment::MoveTo(current));
+            }
+            b'L' => {
+                current = point(&mut tokens, current)?;
+                out.push(Segment::LineTo(current));
+            }
+            b'H' => {
+                let x = tokens.number()?;
+                current[0] = if relative { current[0] + x } else { x };
+                out.push(Segment::LineTo(current));
+            }
+            b'V' => {
+                let y = tokens.number()?;
+                current[1] = if relative { current[1] + y } else { y };
+                out.push(Segment::LineTo(current));
+            }
+            b'C' | b'S' => {
+                let first = match letter.to_ascii_uppercase() {
+                    b'C' => point(&mut tokens, current)?,
+                    _ => reflect(last_control, b'C', current),
+                };
+                let second = point(&mut tokens, current)?;
+                current = point(&mut tokens, current)?;
+                out.push(Segment::CurveTo(first, second, current));
+                control = Some((b'C', second));
+            }
+            b'Q' | b'T' => {
+                let quad = match letter.to_ascii_uppercase() {
+                    b'Q' => point(&mut tokens, current)?,
+                    _ => reflect(last_control, b'Q', current),
+                };
+                current = point(&mut tokens, current)?;
+                out.push(Segment::QuadTo(quad, current));
+                control = Some((b'Q', quad));
+            }
+            b'A' => {
+                let radii = [tokens.number()?.abs(), tokens.number()?.abs()];
+                let rotation = tokens.number()?;
+                let (large_arc, sweep) = (tokens.flag()?, tokens.flag()?);
+                let end = point(&mut tokens, current)?;
+                arc(out, current, radii, rotation, large_arc, sweep, end);
+                current = end;
+            }
+            b'Z' => {
+                out.push(Segment::Close);
+                current = start;
+                // A command after `Z` without its own letter is an error.
+                command = None;
+            }
+            _ => return Err(format!("unknown path command '{}'", letter as char)),
+        }
+        last_control = control;
+    }
+    Ok(())
+}
+
+/// First control point of a smooth curve: the previous control point reflected on `current`,
+/// if the previous segment was a curve of the same kind.
+fn reflect(last: Option<(u8, [f32; 2])>, kind: u8, current: [f32; 2]) -> [f32; 2] {
+    match last {
+        Some((last_kind, control)) if last_kind == kind => {
+            [2.0 * current[0] - control[0], 2.0 * current[1] - control[1]]
+        }
+        _ => current,
+    }
+}
+
+/// Appends an elliptical arc from `from` to `to` as cubic curves of at most 90° each, as in the
+/// implementation notes of the SVG specification.
+fn arc(
+    out: &mut Vec<Segment>,
+    from: [f32; 2],
+    radii: [f32; 2],
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: [f32; 2],
+) {
+    let [mut rx, mut ry] = radii;
+    if from == to {
+        return;
+    }
+    if rx == 0.0 || ry == 0.0 {
+        out.push(Segment::LineTo(to));
+        return;
+    }
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from[0] - to[0]) / 2.0, (from[1] - to[1]) / 2.0);
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    // Radii too small to reach `to` are scaled up.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = (rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1).max(0.0);
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut factor = (numerator / denominator).sqrt();
+    if large_arc == sweep {
+        factor = -factor;
+    }
+    let cx1 = factor * rx * y1 / ry;
+    let cy1 = -factor * ry * x1 / rx;
+    let cx = cos * cx1 - sin * cy1 + (from[0] + to[0]) / 2.0;
+    let cy = sin * cx1 + cos * cy1 + (from[1] + to[1]) / 2.0;
+
+    let angle = |ux: f32, uy: f32| uy.atan2(ux);
+    let start = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start;
+    if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    } else if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    }
+
+    // A point of the ellipse at angle `t`, and its derivative.
+    let at = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        [
+            cx + rx * cos * cos_t - ry * sin * sin_t,
+            cy + rx * sin * cos_t + ry * cos * sin_t,
+        ]
+    };
+    let tangent = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        [
+            -rx * cos * sin_t - ry * sin * cos_t,
+            -rx * sin * sin_t + ry * cos * cos_t,
+        ]
+    };
+
+    let count = (delta.abs() / (PI / 2.0) - 1e-4).ceil().max(1.0) as usize;
+    let step = delta / count as f32;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    for i in 0..count {
+        let (t0, t1) = (start + step * i as f32, start + step * (i + 1) as f32);
+        let (p0, d0, d1) = (at(t0), tangent(t0), tangent(t1));
+        let p1 = if i + 1 == count { to } else { at(t1) };
+        out.push(Segment::CurveTo(
+            [p0[0] + k * d0[0], p0[1] + k * d0[1]],
+            [p1[0] - k * d1[0], p1[1] - k * d1[1]],
+            p1,
+        ));
+    }
+}
+
+enum Token {
+    Command(u8),
+    /// A number follows, to be read with `PathTokens::number`.
+    Number,
+}
+
+/// Reader of path data: command letters, numbers and the single digit flags of arcs.
+struct PathTokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl PathTokens<'_> {
+    fn skip_separators(&mut self) {
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn command_or_number(&mut self) -> Result<Option<Token>, String> {
+        self.skip_separators();
+        match self.data.get(self.pos) {
+            None => Ok(None),
+            Some(c) if c.is_ascii_alphabetic() && !matches!(c, b'e' | b'E') => {
+                self.pos += 1;
+                Ok(Some(Token::Command(*c)))
+            }
+            Some(_) => Ok(Some(Token::Number)),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |this: &mut Self| {
+            while this.data.get(this.pos).is_some_and(u8::is_ascii_digit) {
+                this.pos += 1;
+            }
+        };
+        if matches!(self.data.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E'))
+            && self
+                .data
+                .get(self.pos + 1)
+                .is_some_and(|c| c.is_ascii_digit() || *c == b'-' || *c == b'+')
+        {
+            self.pos += 2;
+            digits(self);
+        }
+
+        let text = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or_default();
+        text.parse().map_err(|_| match self.data.get(start) {
+            Some(&c) => format!("expected a number at '{}'", c as char),
+            None => "path data ends in the middle of a command".into(),
+        })
+    }
+
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        let flag = match self.data.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err("expected an arc flag, 0 or 1".into()),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+}
+
+/// Numbers separated by whitespace and/or commas.
+fn numbers(list: &str) -> Result<Vec<f32>, String> {
+    list.split(|c: char| c.is_ascii_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid number '{value}'"))
+        })
+        .collect()
+}
+
+/// Affine transform `[a, b, c, d, e, f]`, mapping (x, y) to (ax + cy + e, bx + dy + f).
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Transform([f32; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    /// `self` applied after `inner`.
+    fn then(&self, inner: &Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = inner.0;
+        Transform([
+            a * a2 + c * b2,
+            b * a2 + d * b2,
+            a * c2 + c * d2,
+            b * c2 + d * d2,
+            a * e2 + c * f2 + e,
+            b * e2 + d * f2 + f,
+        ])
+    }
+
+    fn point(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [a, b, c, d, e, f] = self.0;
+        [a * x + c * y + e, b * x + d * y + f]
+    }
+
+    fn segment(&self, segment: Segment) -> Segment {
+        match segment {
+            Segment::MoveTo(p) => Segment::MoveTo(self.point(p)),
+            Segment::LineTo(p) => Segment::LineTo(self.point(p)),
+            Segment::QuadTo(c, p) => Segment::QuadTo(self.point(c), self.point(p)),
+            Segment::CurveTo(c1, c2, p) => {
+                Segment::CurveTo(self.point(c1), self.point(c2), self.point(p))
+            }
+            Segment::Close => Segment::Close,
+        }
+    }
+}
+
+/// Parses a `transform` attribute, a list of `matrix`, `translate`, `scale`, `rotate`, `skewX`
+/// and `skewY`.
+fn parse_transform(list: &str) -> Result<Transform, String> {
+    let mut transform = Transform::IDENTITY;
+    let mut rest = list.trim();
+    while !rest.is_empty() {
+        let (name, after) = rest
+            .split_once('(')
+            .ok_or_else(|| format!("invalid transform '{list}'"))?;
+        let (arguments, after) = after
+            .split_once(')')
+            .ok_or_else(|| format!("invalid transform '{list}'"))?;
+        let values = numbers(arguments)?;
+        let next = match (name.trim(), values.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Transform([a, b, c, d, e, f]),
+            ("translate", &[x]) => Transform([1.0, 0.0, 0.0, 1.0, x, 0.0]),
+            ("translate", &[x, y]) => Transform([1.0, 0.0, 0.0, 1.0, x, y]),
+            ("scale", &[s]) => Transform([s, 0.0, 0.0, s, 0.0, 0.0]),
+            ("scale", &[sx, sy]) => Transform([sx, 0.0, 0.0, sy, 0.0, 0.0]),
+            ("rotate", &[angle, ref center @ ..]) if center.is_empty() || center.len() == 2 => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let rotation = Transform([cos, sin, -sin, cos, 0.0, 0.0]);
+                match center {
+                    &[x, y] => Transform([1.0, 0.0, 0.0, 1.0, x, y])
+                        .then(&rotation)
+                        .then(&Transform([1.0, 0.0, 0.0, 1.0, -x, -y])),
+                    _ => rotation,
+                }
+            }
+            ("skewX", &[angle]) => Transform([1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", &[angle]) => Transform([1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => return Err(format!("invalid transform '{}({arguments})'", name.trim())),
+        };
+        transform = transform.then(&next);
+        rest = after.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+    }
+    Ok(transform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(body: &str) -> Vec<Segment> {
+        let svg =
+            format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">{body}</svg>"#);
+        parse(&svg).unwrap().segments
+    }
+
+    fn end(segment: &Segment) -> Option<[f32; 2]> {
+        match *segment {
+            Segment::MoveTo(p) | Segment::LineTo(p) | Segment::QuadTo(_, p) => Some(p),
+            Segment::CurveTo(_, _, p) => Some(p),
+            Segment::Close => None,
+        }
+    }
+
+    fn assert_near(a: [f32; 2], b: [f32; 2]) {
+        assert!(
+            (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn test_path_commands() {
+        let mut path = Vec::new();
+        path_data(
+            "M2 2h10v-1.5L4,4l1-1zm1 1 2 2c1 0 1 1 1 1s1 1 2 0q1-1 2 0t2 0Z",
+            &mut path,
+        )
+        .unwrap();
+        assert_eq!(
+            path,
+            [
+                Segment::MoveTo([2.0, 2.0]),
+                Segment::LineTo([12.0, 2.0]),
+                Segment::LineTo([12.0, 0.5]),
+                Segment::LineTo([4.0, 4.0]),
+                Segment::LineTo([5.0, 3.0]),
+                Segment::Close,
+                Segment::MoveTo([3.0, 3.0]),
+                Segment::LineTo([5.0, 5.0]),
+                Segment::CurveTo([6.0, 5.0], [6.0, 6.0], [6.0, 6.0]),
+                Segment::CurveTo([6.0, 6.0], [7.0, 7.0], [8.0, 6.0]),
+                Segment::QuadTo([9.0, 5.0], [10.0, 6.0]),
+                Segment::QuadTo([11.0, 7.0], [12.0, 6.0]),
+                Segment::Close,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compact_numbers_and_arc_flags() {
+        let path = segments(r#"<path d="M-.5.5L1e1-2a8 8 0 1012 0"/>"#);
+        assert_eq!(path[0], Segment::MoveTo([-0.5, 0.5]));
+        assert_eq!(path[1], Segment::LineTo([10.0, -2.0]));
+        // The large arc of a circle of radius 8 through both points spans more than 180°.
+        assert!(path.len() > 4);
+        assert_near(end(path.last().unwrap()).unwrap(), [22.0, -2.0]);
+    }
+
+    #[test]
+    fn test_arc_stays_on_circle() {
+        let path = segments(r#"<circle cx="12" cy="12" r="10"/>"#);
+        for segment in &path {
+            if let Some([x, y]) = end(segment) {
+                let radius = ((x - 12.0).powi(2) + (y - 12.0).powi(2)).sqrt();
+                assert!((radius - 10.0).abs() < 1e-3);
+            }
+        }
+        assert_eq!(
+            path.iter()
+                .filter(|s| matches!(s, Segment::CurveTo(..)))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_curves_are_split_at_extremes() {
+        // The top of the arc is the middle of the curve, not one of its ends.
+        let path = segments(r#"<path d="M2 12C2 4 22 4 22 12"/>"#);
+        assert_eq!(path.len(), 3);
+        assert_near(end(&path[1]).unwrap(), [12.0, 6.0]);
+        assert_near(end(&path[2]).unwrap(), [22.0, 12.0]);
+
+        let quad = segments(r#"<path d="M2 12Q12 2 22 12"/>"#);
+        assert_eq!(quad.len(), 3);
+        assert_near(end(&quad[1]).unwrap(), [12.0, 7.0]);
+
+        let rotated = segments(r#"<circle transform="rotate(30 12 12)" cx="12" cy="12" r="10"/>"#);
+        let ends: Vec<[f32; 2]> = rotated.iter().filter_map(end).collect();
+        for extreme in [[2.0, 12.0], [22.0, 12.0], [12.0, 2.0], [12.0, 22.0]] {
+            // Arcs made of cubic curves are off by less than 0.03% of the radius.
+            assert!(
+                ends.iter()
+                    .any(|&p| (p[0] - extreme[0]).abs() < 1e-2 && (p[1] - extreme[1]).abs() < 1e-2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_transforms_and_hidden_elements() {
+        let path = segments(
+            r#"<g transform="translate(10 0) scale(2)"><rect x="1" y="1" width="2" height="2"/></g>
+            <defs><rect width="5" height="5"/></defs>
+            <rect width="5" height="5" fill="none"/>
+            <rect width="5" height="5" style="stroke:red;fill: none"/>"#,
+        );
+        assert_eq!(path.len(), 5);
+        assert_eq!(path[0], Segment::MoveTo([12.0, 2.0]));
+        assert_eq!(path[2], Segment::LineTo([16.0, 6.0]));
+
+        let rotated = segments(r#"<path transform="rotate(90 12 12)" d="M12 2L12 2"/>"#);
+        assert_near(end(&rotated[0]).unwrap(), [22.0, 12.0]);
+    }
+
+    #[test]
+    fn test_fill_is_inherited() {
+        let path = segments(
+            r#"<g fill="none"><rect width="5" height="5"/><rect width="2" height="2" fill="red"/>
+            <g style="fill:#000"><rect width="3" height="3"/></g></g>"#,
+        );
+        assert_eq!(path.len(), 10);
+        assert_eq!(path[1], Segment::LineTo([2.0, 0.0]));
+        assert_eq!(path[6], Segment::LineTo([3.0, 0.0]));
+    }
+
+    #[test]
+    fn test_fill_rule() {
+        let parse_body = |body: &str| parse(&format!(r#"<svg viewBox="0 0 24 24">{body}</svg>"#));
+        let rect = r#"<rect width="5" height="5"/>"#;
+        assert_eq!(parse_body(rect).unwrap().fill_rule, FillRule::NonZero);
+        let evenodd = format!(r#"<g style="fill-rule: evenodd">{rect}</g>"#);
+        assert_eq!(parse_body(&evenodd).unwrap().fill_rule, FillRule::EvenOdd);
+        let hidden = format!(r#"<path fill-rule="evenodd" fill="none" d="M1 1h2v2z"/>{rect}"#);
+        assert_eq!(parse_body(&hidden).unwrap().fill_rule, FillRule::NonZero);
+
+        assert!(parse_body(&format!(r#"{evenodd}{rect}"#)).is_err());
+        assert!(parse_body(r#"<path fill-rule="odd" d="M1 1h2v2z"/>"#).is_err());
+    }
+
+    #[test]
+    fn test_invalid_svg() {
+        assert!(parse("<svg viewBox=\"0 0 24\"></svg>").is_err());
+        assert!(parse("<svg width=\"24\"></svg>").is_err());
+        assert!(parse("<html></html>").is_err());
+        assert!(parse("<svg viewBox=\"0 0 24 24\"><path d=\"M1 1 L\"/></svg>").is_err());
+        assert!(parse("<svg viewBox=\"0 0 24 24\"><path d=\"1 1\"/></svg>").is_err());
+        assert_eq!(
+            parse(r#"<svg width="32px" height="16"/>"#)
+                .unwrap()
+                .view_box,
+            [0.0, 0.0, 32.0, 16.0]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Selection of an instance of a variable font: a named instance from `fvar` and/or explicit
+//! axis coordinates.
+
+use ttf_parser::{Face, Tag};
+
+/// Sets the coordinates of `face`: first the ones of `instance`, then `axes` on top of them.
+/// Fails with a description of what the font offers when something doesn't exist.
+pub(crate) fn apply(
+    face: &mut Face,
+    instance: Option<&str>,
+    axes: &[(String, f32)],
+) -> Result<(), String> {
+    if instance.is_none() && axes.is_empty() {
+        return Ok(());
+    }
+    if !face.is_variable() {
+        return Err("the font is not a variable font".into());
+    }
+
+    if let Some(name) = instance {
+        let instances = named_instances(face);
+        let (_, coordinates) = instances
+            .iter()
+            .find(|(instance, _)| instance == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = instances.iter().map(|(name, _)| name.as_str()).collect();
+                format!("no instance '{name}', the font has {}", list(&names))
+            })?;
+        for (axis, &value) in face.variation_axes().into_iter().zip(coordinates) {
+            face.set_variation(axis.tag, value);
+        }
+    }
+
+    for (tag, value) in axes {
+        // Tags shorter than 4 characters are padded with spaces.
+        let wanted = (tag.len() <= 4).then(|| Tag::from_bytes_lossy(tag.as_bytes()));
+        let axis = face
+            .variation_axes()
+            .into_iter()
+            .find(|axis| Some(axis.tag) == wanted)
+            .ok_or_else(|| {
+                let names: Vec<String> = face
+                    .variation_axes()
+                    .into_iter()
+                    .map(|axis| format!("{} ({}-{})", axis.tag, axis.min_value, axis.max_value))
+                    .collect();
+                format!("no axis '{tag}', the font has {}", list(&names))
+            })?;
+        if !(axis.min_value..=axis.max_value).contains(value) {
+            return Err(format!(
+                "{tag} = {value} is out of the axis range {}-{}",
+                axis.min_value, axis.max_value
+            ));
+        }
+        face.set_variation(axis.tag, *value);
+    }
+
+    Ok(())
+}
+
+/// Names and axis coordinates of the named instances in the `fvar` table.
+pub(crate) fn named_instances(face: &Face) -> Vec<(String, Vec<f32>)> {
+    read_instances(face).unwrap_or_default()
+}
+
+fn read_instances(face: &Face) -> Option<Vec<(String, Vec<f32>)>> {
+    let fvar = face.raw_face().table(Tag::from_bytes(b"fvar"))?;
+    let u16_at = |at: usize| Some(u16::from_be_bytes(fvar.get(at..at + 2)?.try_into().ok()?));
+    let fixed_at = |at: usize| {
+        let value = i32::from_be_bytes(fvar.get(at..at + 4)?.try_into().ok()?);
+        Some(value as f32 / 65536.0)
+    };
+
+    let axes_offset = u16_at(4)? as usize;
+    let axis_count = u16_at(8)? as usize;
+    let axis_size = u16_at(10)? as usize;
+    let instance_count = u16_at(12)? as usize;
+    let instance_size = u16_at(14)? as usize;
+
+    // Instance records follow the axes: name id, flags, then a coordinate per axis.
+    let first = axes_offset + axis_count * axis_size;
+    let mut instances = Vec::with_capacity(instance_count);
+    for index in 0..instance_count {
+        let record = first + index * instance_size;
+        let coordinates = (0..axis_count)
+            .map(|axis| fixed_at(record + 4 + axis * 4))
+            .collect::<Option<Vec<f32>>>()?;
+        if let Some(name) = name(face, u16_at(record)?) {
+            instances.push((name, coordinates));
+        }
+    }
+    Some(instances)
+}
+
+fn name(face: &Face, id: u16) -> Option<String> {
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == id && name.is_unicode())
+        .find_map(|name| name.to_string())
+}
+
+fn list(names: &[impl AsRef<str>]) -> String {
+    match names {
+        [] => "none".into(),
+        names => names
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
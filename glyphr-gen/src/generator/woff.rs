@@ -0,0 +1,749 @@
+//! Decoding of WOFF and WOFF2 web fonts back to the sfnt (TTF/OTF) data they wrap, so the rest of
+//! the generator only ever sees plain fonts.
+//!
+//! WOFF compresses every table with zlib. WOFF2 compresses all of them together with Brotli and
+//! can also transform `glyf`/`loca` and `hmtx` into a more compressible layout, which is undone
+//! here as described in the [WOFF2 specification](https://www.w3.org/TR/WOFF2/).
+
+use std::borrow::Cow;
+use std::io::Read;
+
+use crate::generator::font::FontError;
+
+const WOFF: &[u8; 4] = b"wOFF";
+const WOFF2: &[u8; 4] = b"wOF2";
+
+/// Tags a WOFF2 table directory can refer to by index.
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Returns the sfnt data of a WOFF or WOFF2 font, any other data is returned as is.
+pub fn decode(data: &[u8]) -> Result<Cow<'_, [u8]>, FontError> {
+    match data.get(..4) {
+        Some(signature) if signature == WOFF => decode_woff(data)
+            .map(Cow::Owned)
+            .map_err(|message| FontError::Woff(format!("invalid WOFF file: {message}"))),
+        Some(signature) if signature == WOFF2 => decode_woff2(data)
+            .map(Cow::Owned)
+            .map_err(|message| FontError::Woff(format!("invalid WOFF2 file: {message}"))),
+        _ => Ok(Cow::Borrowed(data)),
+    }
+}
+
+type Table = ([u8; 4], Vec<u8>);
+
+/// Largest font accepted out of a WOFF or WOFF2 file, far more than any font needs.
+const MAX_SFNT_LEN: usize = 256 << 20;
+/// Most tables an sfnt can have, so its `searchRange` fits in 16 bits.
+const MAX_TABLES: u16 = 4095;
+
+/// Rejects more tables than an sfnt can hold.
+fn check_num_tables(num_tables: u16) -> Result<(), String> {
+    match num_tables > MAX_TABLES {
+        true => Err(format!(
+            "{num_tables} tables, at most {MAX_TABLES} are supported"
+        )),
+        false => Ok(()),
+    }
+}
+
+fn decode_woff(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(30)?;
+    check_num_tables(num_tables)?;
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    let mut total_len = 0usize;
+    for _ in 0..num_tables {
+        let tag = header.tag()?;
+        let offset = header.u32()? as usize;
+        let stored_len = header.u32()? as usize;
+        let len = header.u32()? as usize;
+        header.skip(4)?;
+        total_len = total_len
+            .checked_add(len)
+            .filter(|&total| total <= MAX_SFNT_LEN)
+            .ok_or("tables are too large")?;
+
+        let stored = offset
+            .checked_add(stored_len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| format!("table '{}' is out of bounds", tag_name(&tag)))?;
+        let table = if stored_len < len {
+            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(stored, len)
+                .ok()
+                .filter(|table| table.len() == len)
+                .ok_or_else(|| format!("table '{}' can't be decompressed", tag_name(&tag)))?
+        } else if stored_len == len {
+            stored.to_vec()
+        } else {
+            return Err(format!(
+                "table '{}' is larger compressed than uncompressed",
+                tag_name(&tag)
+            ));
+        };
+        tables.push((tag, table));
+    }
+
+    Ok(write_sfnt(flavor, tables))
+}
+
+/// A table of the WOFF2 directory.
+struct Entry {
+    tag: [u8; 4],
+    /// Whether the table is stored transformed, so it has to be rebuilt.
+    transformed: bool,
+    /// Length in the decompressed stream.
+    stored_len: usize,
+}
+
+fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(6)?;
+    let compressed_len = header.u32()? as usize;
+    header.skip(24)?;
+    check_num_tables(num_tables)?;
+
+    if flavor == u32::from_be_bytes(*b"ttcf") {
+        return Err("font collections are not supported".into());
+    }
+
+    let mut entries = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match flags & 0x3f {
+            63 => header.tag()?,
+            index => *KNOWN_TAGS[index as usize],
+        };
+        let version = flags >> 6;
+        let len = header.base128()? as usize;
+        // `glyf` and `loca` are transformed with version 0, every other table with any other.
+        let transformed = match &tag {
+            b"glyf" | b"loca" => version == 0,
+            _ => version != 0,
+        };
+        let stored_len = match transformed {
+            true => header.base128()? as usize,
+            false => len,
+        };
+        if transformed && !matches!(&tag, b"glyf" | b"loca" | b"hmtx") {
+            return Err(format!("unknown transform of table '{}'", tag_name(&tag)));
+        }
+        entries.push(Entry {
+            tag,
+            transformed,
+            stored_len,
+        });
+    }
+
+    let compressed = header.bytes(compressed_len)?;
+    let total_len = entries
+        .iter()
+        .try_fold(0usize, |total, entry| total.checked_add(entry.stored_len))
+        .filter(|&total| total <= MAX_SFNT_LEN)
+        .ok_or("tables are too large")?;
+    let mut stream = Vec::with_capacity(total_len);
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(total_len as u64)
+        .read_to_end(&mut stream)
+        .map_err(|err| format!("can't decompress the tables: {err}"))?;
+    if stream.len() != total_len {
+        return Err("the compressed tables are truncated".into());
+    }
+
+    let mut tables: Vec<Table> = Vec::with_capacity(entries.len());
+    let mut offset = 0;
+    for entry in &entries {
+        tables.push((
+            entry.tag,
+            stream[offset..offset + entry.stored_len].to_vec(),
+        ));
+        offset += entry.stored_len;
+    }
+
+    let is_transformed = |tag: &[u8; 4]| entries.iter().any(|e| &e.tag == tag && e.transformed);
+    let mut x_mins = None;
+    if is_transformed(b"glyf") {
+        let glyf = table(&tables, b"glyf")?;
+        let glyphs = reconstruct_glyf(glyf)?;
+        x_mins = Some(glyphs.x_mins);
+        set_table(&mut tables, b"glyf", glyphs.glyf);
+        set_table(&mut tables, b"loca", glyphs.loca);
+    } else if is_transformed(b"loca") {
+        return Err("'loca' is transformed but 'glyf' isn't".into());
+    }
+    if is_transformed(b"hmtx") {
+        let x_mins = x_mins.ok_or("'hmtx' is transformed but 'glyf' isn't")?;
+        let hmtx = reconstruct_hmtx(&tables, &x_mins)?;
+        set_table(&mut tables, b"hmtx", hmtx);
+    }
+
+    Ok(write_sfnt(flavor, tables))
+}
+
+/// `glyf` and `loca` rebuilt from the transformed `glyf`.
+struct Glyphs {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    /// xMin of every glyph, for `hmtx`.
+    x_mins: Vec<i16>,
+}
+
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyphs, String> {
+    let mut header = Reader::new(data);
+    header.skip(2)?;
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+
+    let mut streams = Vec::with_capacity(7);
+    let mut offset = 36;
+    for _ in 0..7 {
+        let len = header.u32()? as usize;
+        let end = offset + len;
+        streams.push(Reader::new(
+            data.get(offset..end).ok_or("'glyf' is truncated")?,
+        ));
+        offset = end;
+    }
+    let [
+        mut contours,
+        mut points,
+        mut flags,
+        mut glyphs,
+        mut composites,
+        bbox,
+        mut instructions,
+    ] = <[Reader; 7]>::try_from(streams).map_err(|_| "'glyf' is truncated")?;
+
+    let bitmap_len = num_glyphs.div_ceil(32) * 4;
+    let mut bbox = bbox;
+    let bbox_bitmap = bbox.bytes(bitmap_len)?;
+    let overlap_bitmap = match option_flags & 1 {
+        0 => None,
+        _ => Some(
+            data.get(offset..offset + num_glyphs.div_ceil(8))
+                .ok_or("'glyf' is truncated")?,
+        ),
+    };
+    let bit = |bitmap: &[u8], index: usize| bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let num_contours = contours.i16()?;
+        let explicit_bbox = match bit(bbox_bitmap, index) {
+            true => Some([bbox.i16()?, bbox.i16()?, bbox.i16()?, bbox.i16()?]),
+            false => None,
+        };
+
+        if num_contours == 0 {
+            x_mins.push(0);
+            continue;
+        }
+
+        let mut glyph = Vec::new();
+        let glyph_bbox;
+        if num_contours > 0 {
+            let mut end_points = Vec::with_capacity(num_contours as usize);
+            let mut total = 0u32;
+            for _ in 0..num_contours {
+                total += points.u255()? as u32;
+                let end = total.checked_sub(1).ok_or("contour without points")?;
+                end_points.push(u16::try_from(end).map_err(|_| "too many points")?);
+            }
+
+            let mut outline = Vec::with_capacity(total as usize);
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..total {
+                let (on_curve, dx, dy) = read_triplet(flags.u8()?, &mut glyphs)?;
+                x += dx;
+                y += dy;
+                outline.push((on_curve, dx, dy, x, y));
+            }
+
+            glyph_bbox = match explicit_bbox {
+                Some(bbox) => bbox,
+                None => {
+                    let (xs, ys) = (outline.iter().map(|p| p.3), outline.iter().map(|p| p.4));
+                    [
+                        xs.clone().min().unwrap_or(0) as i16,
+                        ys.clone().min().unwrap_or(0) as i16,
+                        xs.max().unwrap_or(0) as i16,
+                        ys.max().unwrap_or(0) as i16,
+                    ]
+                }
+            };
+
+            push_i16(&mut glyph, num_contours);
+            glyph_bbox.iter().for_each(|&v| push_i16(&mut glyph, v));
+            end_points.iter().for_each(|&end| push_u16(&mut glyph, end));
+            let instructions_len = glyphs.u255()?;
+            push_u16(&mut glyph, instructions_len);
+            glyph.extend_from_slice(instructions.bytes(instructions_len as usize)?);
+
+            let overlap = overlap_bitmap.is_some_and(|bitmap| bit(bitmap, index));
+            write_points(&mut glyph, &outline, overlap);
+        } else {
+            glyph_bbox = explicit_bbox.ok_or("composite glyph without a bounding box")?;
+            push_i16(&mut glyph, num_contours);
+            glyph_bbox.iter().for_each(|&v| push_i16(&mut glyph, v));
+
+            let (components, have_instructions) = read_components(&mut composites)?;
+            glyph.extend_from_slice(components);
+            if have_instructions {
+                let instructions_len = glyphs.u255()?;
+                push_u16(&mut glyph, instructions_len);
+                glyph.extend_from_slice(instructions.bytes(instructions_len as usize)?);
+            }
+        }
+
+        x_mins.push(glyph_bbox[0]);
+        glyph.resize(glyph.len().next_multiple_of(4), 0);
+        glyf.extend_from_slice(&glyph);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::new();
+    for offset in offsets {
+        match index_format {
+            0 => push_u16(
+                &mut loca,
+                u16::try_from(offset / 2).map_err(|_| "'glyf' too large")?,
+            ),
+            _ => loca.extend_from_slice(&(offset as u32).to_be_bytes()),
+        }
+    }
+
+    Ok(Glyphs { glyf, loca, x_mins })
+}
+
+/// Decodes a point of a simple glyph: whether it's on the curve, and its delta from the previous.
+fn read_triplet(flag: u8, glyphs: &mut Reader) -> Result<(bool, i32, i32), String> {
+    let on_curve = flag & 0x80 == 0;
+    let flag = (flag & 0x7f) as i32;
+    let with_sign = |flag: i32, value: i32| if flag & 1 != 0 { value } else { -value };
+
+    let (dx, dy) = match flag {
+        0..10 => (0, with_sign(flag, ((flag & 14) << 7) + glyphs.u8()? as i32)),
+        10..20 => (
+            with_sign(flag, (((flag - 10) & 14) << 7) + glyphs.u8()? as i32),
+            0,
+        ),
+        20..84 => {
+            let b0 = flag - 20;
+            let b1 = glyphs.u8()? as i32;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0c) << 2) + (b1 & 0x0f)),
+            )
+        }
+        84..120 => {
+            let b0 = flag - 84;
+            let (b1, b2) = (glyphs.u8()? as i32, glyphs.u8()? as i32);
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        }
+        120..124 => {
+            let (b1, b2, b3) = (
+                glyphs.u8()? as i32,
+                glyphs.u8()? as i32,
+                glyphs.u8()? as i32,
+            );
+            (
+                with_sign(flag, (b1 << 4) + (b2 >> 4)),
+                with_sign(flag >> 1, ((b2 & 0x0f) << 8) + b3),
+            )
+        }
+        _ => (
+            with_sign(flag, glyphs.u16()? as i32),
+            with_sign(flag >> 1, glyphs.u16()? as i32),
+        ),
+    };
+    Ok((on_curve, dx, dy))
+}
+
+/// Writes flags and coordinates of a simple glyph, in the usual `glyf` encoding.
+fn write_points(glyph: &mut Vec<u8>, outline: &[(bool, i32, i32, i32, i32)], overlap: bool) {
+    const ON_CURVE: u8 = 0x01;
+    const X_SHORT: u8 = 0x02;
+    const Y_SHORT: u8 = 0x04;
+    const X_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_SAME_OR_POSITIVE: u8 = 0x20;
+    const OVERLAP_SIMPLE: u8 = 0x40;
+
+    let encoding = |delta: i32, short: u8, same_or_positive: u8| match delta {
+        0 => same_or_positive,
+        1..256 => short | same_or_positive,
+        -255..0 => short,
+        _ => 0,
+    };
+
+    for (index, &(on_curve, dx, dy, _, _)) in outline.iter().enumerate() {
+        let mut flag =
+            encoding(dx, X_SHORT, X_SAME_OR_POSITIVE) | encoding(dy, Y_SHORT, Y_SAME_OR_POSITIVE);
+        if on_curve {
+            flag |= ON_CURVE;
+        }
+        if overlap && index == 0 {
+            flag |= OVERLAP_SIMPLE;
+        }
+        glyph.push(flag);
+    }
+    for axis in 0..2 {
+        for &(_, dx, dy, _, _) in outline {
+            let delta = if axis == 0 { dx } else { dy };
+            match delta {
+                0 => {}
+                -255..256 => glyph.push(delta.unsigned_abs() as u8),
+                _ => push_i16(glyph, delta as i16),
+            }
+        }
+    }
+}
+
+/// Reads the components of a composite glyph, returning their data and whether the glyph has
+/// instructions.
+fn read_components<'a>(composites: &mut Reader<'a>) -> Result<(&'a [u8], bool), String> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+    const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+    let start = composites.pos;
+    let mut have_instructions = false;
+    loop {
+        let flags = composites.u16()?;
+        have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+        let args = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        let transform = if flags & WE_HAVE_A_SCALE != 0 {
+            2
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            4
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            8
+        } else {
+            0
+        };
+        // Glyph index, arguments and transform.
+        composites.skip(2 + args + transform)?;
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    Ok((&composites.data[start..composites.pos], have_instructions))
+}
+
+/// Rebuilds `hmtx`, taking the left side bearings left out of the transformed table from the
+/// glyphs' xMin.
+fn reconstruct_hmtx(tables: &[Table], x_mins: &[i16]) -> Result<Vec<u8>, String> {
+    let mut hmtx = Reader::new(table(tables, b"hmtx")?);
+    let mut hhea = Reader::new(table(tables, b"hhea")?);
+    hhea.skip(34)?;
+    let num_metrics = hhea.u16()? as usize;
+    let mut maxp = Reader::new(table(tables, b"maxp")?);
+    maxp.skip(4)?;
+    let num_glyphs = maxp.u16()? as usize;
+    if num_metrics > num_glyphs || x_mins.len() != num_glyphs {
+        return Err("'hmtx' doesn't match the number of glyphs".into());
+    }
+
+    let flags = hmtx.u8()?;
+    let advances = (0..num_metrics)
+        .map(|_| hmtx.u16())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut bearings = Vec::with_capacity(num_glyphs);
+    for (glyphs, omitted) in [
+        (0..num_metrics, flags & 1),
+        (num_metrics..num_glyphs, flags & 2),
+    ] {
+        for index in glyphs {
+            bearings.push(match omitted {
+                0 => hmtx.i16()?,
+                _ => x_mins[index],
+            });
+        }
+    }
+
+    let mut out = Vec::with_capacity(num_metrics * 4 + (num_glyphs - num_metrics) * 2);
+    for (index, &bearing) in bearings.iter().enumerate() {
+        if let Some(&advance) = advances.get(index) {
+            push_u16(&mut out, advance);
+        }
+        push_i16(&mut out, bearing);
+    }
+    Ok(out)
+}
+
+/// Writes the tables out as an sfnt file, sorted by tag as the format requires.
+fn write_sfnt(flavor: u32, mut tables: Vec<Table>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    // At most `MAX_TABLES`, checked by the decoders, so none of this overflows.
+    let num_tables = tables.len() as u16;
+    let entry_selector = num_tables.checked_ilog2().unwrap_or(0) as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&flavor.to_be_bytes());
+    for value in [
+        num_tables,
+        search_range,
+        entry_selector,
+        num_tables * 16 - search_range,
+    ] {
+        push_u16(&mut out, value);
+    }
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, table) in &tables {
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&checksum(table).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in &tables {
+        out.extend_from_slice(table);
+        out.resize(out.len().next_multiple_of(4), 0);
+    }
+    out
+}
+
+fn checksum(table: &[u8]) -> u32 {
+    table.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn table<'a>(tables: &'a [Table], tag: &[u8; 4]) -> Result<&'a [u8], String> {
+    tables
+        .iter()
+        .find(|(t, _)| t == tag)
+        .map(|(_, table)| table.as_slice())
+        .ok_or_else(|| format!("table '{}' is missing", tag_name(tag)))
+}
+
+fn set_table(tables: &mut Vec<Table>, tag: &[u8; 4], data: Vec<u8>) {
+    match tables.iter_mut().find(|(t, _)| t == tag) {
+        Some((_, table)) => *table = data,
+        None => tables.push((*tag, data)),
+    }
+}
+
+fn tag_name(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).into_owned()
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_i16(out: &mut Vec<u8>, value: i16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Big-endian reader, failing on truncated data.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or("unexpected end of data")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn tag(&mut self) -> Result<[u8; 4], String> {
+        Ok(self.u32()?.to_be_bytes())
+    }
+
+    /// `UIntBase128`: up to 5 bytes of 7 bits, most significant first.
+    fn base128(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            if i == 0 && byte == 0x80 {
+                return Err("UIntBase128 with leading zeros".into());
+            }
+            if value & 0xfe00_0000 != 0 {
+                return Err("UIntBase128 overflows".into());
+            }
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("UIntBase128 longer than 5 bytes".into())
+    }
+
+    /// `255UInt16`: a byte, or a marker byte followed by the rest of the value.
+    fn u255(&mut self) -> Result<u16, String> {
+        Ok(match self.u8()? {
+            253 => self.u16()?,
+            254 => self.u8()? as u16 + 506,
+            255 => self.u8()? as u16 + 253,
+            value => value as u16,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEB_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/web.ttf");
+    const WEB_WOFF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/web.woff");
+    const WEB_WOFF2: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/web.woff2");
+
+    fn outlines(data: &[u8]) -> Vec<String> {
+        struct Path(String);
+        impl ttf_parser::OutlineBuilder for Path {
+            fn move_to(&mut self, x: f32, y: f32) {
+                self.0 += &format!("M{x},{y}");
+            }
+            fn line_to(&mut self, x: f32, y: f32) {
+                self.0 += &format!("L{x},{y}");
+            }
+            fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+                self.0 += &format!("Q{x1},{y1},{x},{y}");
+            }
+            fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+                self.0 += &format!("C{x1},{y1},{x2},{y2},{x},{y}");
+            }
+            fn close(&mut self) {
+                self.0 += "Z";
+            }
+        }
+
+        let face = ttf_parser::Face::parse(data, 0).unwrap();
+        (0..face.number_of_glyphs())
+            .map(ttf_parser::GlyphId)
+            .map(|id| {
+                let mut path = Path(String::new());
+                let bbox = face.outline_glyph(id, &mut path);
+                let metrics = (face.glyph_hor_advance(id), face.glyph_hor_side_bearing(id));
+                format!("{} {bbox:?} {metrics:?}", path.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_matches_ttf() {
+        let ttf = std::fs::read(WEB_TTF).unwrap();
+        let expected = outlines(&ttf);
+        assert_eq!(expected.len(), 3);
+        assert!(matches!(decode(&ttf).unwrap(), Cow::Borrowed(_)));
+
+        for path in [WEB_WOFF, WEB_WOFF2] {
+            let data = std::fs::read(path).unwrap();
+            let decoded = decode(&data).unwrap();
+            assert_eq!(outlines(&decoded), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_files() {
+        for path in [WEB_WOFF, WEB_WOFF2] {
+            let data = std::fs::read(path).unwrap();
+            assert!(matches!(
+                decode(&data[..data.len() / 2]),
+                Err(FontError::Woff(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_headers() {
+        let error = |data: &[u8]| match decode(data) {
+            Err(FontError::Woff(message)) => message,
+            _ => panic!("decoded"),
+        };
+
+        let mut woff = std::fs::read(WEB_WOFF).unwrap();
+        woff[12..14].copy_from_slice(&4096u16.to_be_bytes());
+        assert!(error(&woff).contains("4096 tables"));
+
+        // Replaces the 1 byte length of the first WOFF2 table, `cmap`, with 2^31 - 1.
+        let woff2 = std::fs::read(WEB_WOFF2).unwrap();
+        assert_eq!(woff2[48..50], [0x00, 0x34]);
+        let huge = [&woff2[..49], &[0x87, 0xff, 0xff, 0xff, 0x7f], &woff2[50..]].concat();
+        assert!(error(&huge).contains("too large"));
+    }
+
+    #[test]
+    fn test_variable_length_integers() {
+        assert_eq!(Reader::new(&[0x3f]).base128(), Ok(63));
+        assert_eq!(Reader::new(&[0x81, 0x00]).base128(), Ok(128));
+        assert!(Reader::new(&[0x80, 0x01]).base128().is_err());
+        assert!(
+            Reader::new(&[0x9f, 0xff, 0xff, 0xff, 0x7f])
+                .base128()
+                .is_err()
+        );
+
+        assert_eq!(Reader::new(&[252]).u255(), Ok(252));
+        assert_eq!(Reader::new(&[255, 0]).u255(), Ok(253));
+        assert_eq!(Reader::new(&[254, 0]).u255(), Ok(506));
+        assert_eq!(Reader::new(&[253, 0x12, 0x34]).u255(), Ok(0x1234));
+    }
+}
@@ -0,0 +1,24 @@
+//! # glyphr-gen
+//!
+//! Font generation used by `glyphr-macros` and the `glyphr` CLI: TTF loading, SDF/Bitmap
+//! generation and the output writers (Rust source and texture atlases). Binary blobs are
+//! written by `glyphr::runtime::encode`, with the encoder of the runtime itself.
+//!
+//! Build scripts should start from `Builder`, see `README.md`.
+
+pub mod atlas;
+mod builder;
+pub mod cache;
+pub mod charset;
+pub mod config;
+mod error;
+pub mod generator;
+pub mod renderer;
+pub mod text_image;
+pub mod toml_parser;
+
+pub use builder::Builder;
+pub use cache::Cache;
+pub use config::{BitmapFormat, FillRule};
+pub use error::Error;
+pub use toml_parser::{OneOrMany, TomlConfig, TomlFont};
@@ -0,0 +1,280 @@
+use minijinja::{Environment, Value, context};
+use std::collections::BTreeMap;
+
+use crate::Error;
+use crate::cache::Cache;
+use crate::charset::format_char_set;
+use crate::config::{FontLoaded, MissingGlyphs, ToFontLoaded};
+use crate::generator::{GeneratedFont, NOTDEF};
+use crate::text_image::TextImage;
+
+/// Filter used by minijinja to escape characters that generates error if direcly placed inside
+/// apostrophes (e.g. `'`, `\`, `\n`...).
+fn rust_char_escape(value: Value) -> Result<String, minijinja::Error> {
+    let s = value.as_str().ok_or_else(|| {
+        minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, "expected string")
+    })?;
+
+    if s.chars().count() != 1 {
+        return Err(minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            "expected single character",
+        ));
+    }
+
+    let ch = s.chars().next().unwrap();
+    let escaped = match ch {
+        '\'' => "\\'".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        '\0' => "\\0".to_string(),
+        c if c.is_control() || c == NOTDEF => format!("\\u{{{:04x}}}", c as u32),
+        c => c.to_string(),
+    };
+
+    Ok(escaped)
+}
+
+/// Generates a String containing all the code to write out the macro, reusing the output of a
+/// previous build if nothing changed (see `Cache::from_env`).
+pub fn render<T: ToFontLoaded>(font_config: T) -> Result<String, Error> {
+    let fonts = font_config.to_font_loaded()?;
+    let icons = render_icon_constants(fonts.iter().flat_map(|font| font.font.icons()))?;
+    let mut code = render_fonts(fonts, Cache::from_env().as_ref())?;
+    code.push_str(&icons);
+    Ok(code)
+}
+
+/// Writes out a `ICON_NAME` constant with the codepoint of every icon, once per name even if
+/// the icon is in more fonts.
+pub fn render_icon_constants<'a>(
+    icons: impl IntoIterator<Item = &'a (String, char)>,
+) -> Result<String, Error> {
+    let icons: BTreeMap<(String, char), &str> = icons
+        .into_iter()
+        .map(|(name, codepoint)| ((icon_constant(name), *codepoint), name.as_str()))
+        .collect();
+    if icons.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut env = Environment::new();
+    env.add_template("icons", include_str!("../templates/icons.rs.j2"))?;
+    let icons: Vec<_> = icons
+        .into_iter()
+        .map(|((constant, codepoint), name)| {
+            context! {
+                name => name,
+                constant => constant,
+                codepoint => format!("\\u{{{:04x}}}", codepoint as u32),
+            }
+        })
+        .collect();
+    Ok(env
+        .get_template("icons")?
+        .render(context! { icons => icons })?)
+}
+
+/// Name of the constant of an icon: upper case, with anything that can't be in an identifier
+/// replaced by `_`.
+fn icon_constant(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// Generates the code of `fonts`, looking it up in `cache` first and storing it there after.
+pub fn render_fonts(fonts: Vec<FontLoaded>, cache: Option<&Cache>) -> Result<String, Error> {
+    let cached = cache.map(|cache| (cache, Cache::key(&fonts)));
+    if let Some(code) = cached.as_ref().and_then(|(cache, key)| cache.get(key)) {
+        return Ok(code);
+    }
+
+    let generated = fonts
+        .into_iter()
+        .map(GeneratedFont::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let code = render_generated(&generated)?;
+
+    if let Some((cache, key)) = &cached {
+        cache.put(key, &code);
+    }
+    Ok(code)
+}
+
+/// Writes out the Rust code of fonts that are already generated.
+pub fn render_generated(generated: &[GeneratedFont]) -> Result<String, Error> {
+    let mut env = Environment::new();
+    env.add_filter("rust_char_escape", rust_char_escape);
+    env.add_template("fonts", include_str!("../templates/fonts.rs.j2"))?;
+    env.add_template("family", include_str!("../templates/family.rs.j2"))?;
+
+    let mut output = String::new();
+    let mut families: Vec<(&str, Vec<&str>)> = Vec::new();
+    for generated_font in generated {
+        let loaded_font = &generated_font.font;
+        if let Some(family) = &loaded_font.family {
+            match families.iter_mut().find(|(name, _)| name == family) {
+                Some((_, members)) => members.push(&loaded_font.name),
+                None => families.push((family, vec![&loaded_font.name])),
+            }
+        }
+
+        let mut glyphs = vec![];
+        for (bitmap, entry) in &generated_font.glyphs {
+            glyphs.push(context! {
+                character => entry.character,
+                codepoint => entry.name.clone(),
+                bitmap_len => bitmap.len(),
+                bitmap => bitmap.clone(),
+                xmin => entry.xmin,
+                ymin => entry.ymin,
+                width => entry.width,
+                height => entry.height,
+                advance_width => entry.advance_width,
+            });
+        }
+
+        // Reported in the docs, mostly for sets coming from `characters_from`.
+        let mut characters: Vec<char> = generated_font
+            .glyphs
+            .iter()
+            .map(|(_, entry)| entry.character)
+            .filter(|&c| c != NOTDEF)
+            .collect();
+        characters.sort_unstable();
+
+        // Skipped characters become a compiler warning wherever the code ends up.
+        let missing_note = match loaded_font.options.missing {
+            MissingGlyphs::Skip if !generated_font.missing.is_empty() => {
+                let err = Error::MissingGlyphs {
+                    font: loaded_font.name.clone(),
+                    characters: generated_font.missing.clone(),
+                };
+                Some(format!("{:?}", format!("{err}, they were skipped")))
+            }
+            _ => None,
+        };
+
+        // Now render fonts.rs with everything
+        output.push_str(&env.get_template("fonts")?.render(context! {
+            font => context! {
+                name => loaded_font.name,
+                size => loaded_font.px,
+                ascent => generated_font.ascent(),
+                descent => generated_font.descent(),
+                format => loaded_font.format.runtime_format(loaded_font.options.color),
+                glyphs => glyphs,
+                characters => format_char_set(&characters),
+                missing_note => missing_note,
+            },
+        })?);
+    }
+
+    for (name, members) in families {
+        output.push_str(&env.get_template("family")?.render(context! {
+            family => context! {
+                name => name,
+                members => members,
+            },
+        })?);
+    }
+
+    Ok(output)
+}
+
+/// Writes out the Rust code of a pre-rendered text.
+pub fn render_text_image(image: &TextImage) -> Result<String, Error> {
+    let mut env = Environment::new();
+    env.add_template("text_image", include_str!("../templates/text_image.rs.j2"))?;
+
+    Ok(env.get_template("text_image")?.render(context! {
+        image => context! {
+            name => image.name,
+            text => format!("{:?}", image.text),
+            width => image.width,
+            height => image.height,
+            x_offset => image.x_offset,
+            y_offset => image.y_offset,
+            format => format!("{:?}", image.format),
+            data => image.data,
+        },
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minijinja::value::Value;
+
+    fn val(s: &str) -> Value {
+        Value::from(s.to_string())
+    }
+
+    #[test]
+    fn test_icon_constants() {
+        let icons = [
+            ("wifi-off".to_string(), '\u{e001}'),
+            ("wifi".to_string(), '\u{e000}'),
+            ("wifi".to_string(), '\u{e000}'),
+        ];
+        let code = render_icon_constants(&icons).unwrap();
+        assert_eq!(code.matches("pub const").count(), 2);
+        assert!(code.contains("pub const ICON_WIFI: char = '\\u{e000}';"));
+        assert!(code.contains("/// Codepoint of the `wifi-off` icon."));
+        assert!(code.contains("pub const ICON_WIFI_OFF: char = '\\u{e001}';"));
+        assert_eq!(render_icon_constants(&[]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_normal_char() {
+        assert_eq!(rust_char_escape(val("a")).unwrap(), "a");
+        assert_eq!(rust_char_escape(val("Z")).unwrap(), "Z");
+        assert_eq!(rust_char_escape(val("7")).unwrap(), "7");
+    }
+
+    #[test]
+    fn test_escape_chars() {
+        assert_eq!(rust_char_escape(val("'")).unwrap(), "\\'");
+        assert_eq!(rust_char_escape(val("\\")).unwrap(), "\\\\");
+        assert_eq!(rust_char_escape(val("\n")).unwrap(), "\\n");
+        assert_eq!(rust_char_escape(val("\r")).unwrap(), "\\r");
+        assert_eq!(rust_char_escape(val("\t")).unwrap(), "\\t");
+        assert_eq!(rust_char_escape(val("\0")).unwrap(), "\\0");
+    }
+
+    #[test]
+    fn test_control_char() {
+        // ASCII 0x01 (Start of Header)
+        assert_eq!(
+            rust_char_escape(Value::from("\u{01}")).unwrap(),
+            "\\u{0001}"
+        );
+    }
+
+    #[test]
+    fn test_unicode_non_escape() {
+        assert_eq!(rust_char_escape(Value::from("λ")).unwrap(), "λ");
+        assert_eq!(rust_char_escape(Value::from("🦀")).unwrap(), "🦀");
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let err = rust_char_escape(val("")).unwrap_err();
+        assert!(format!("{}", err).contains("expected single character"));
+
+        let err = rust_char_escape(val("ab")).unwrap_err();
+        assert!(format!("{}", err).contains("expected single character"));
+    }
+
+    #[test]
+    fn test_non_string_input() {
+        let err = rust_char_escape(Value::from(42)).unwrap_err();
+        assert!(format!("{}", err).contains("expected string"));
+    }
+}
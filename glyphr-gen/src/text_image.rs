@@ -0,0 +1,287 @@
+//! # text_image.rs
+//!
+//! Static texts laid out and rasterized at generation time into a single image, so labels that
+//! never change don't need their glyphs (nor the renderer) at runtime.
+
+use std::rc::Rc;
+
+use crate::Error;
+use crate::config::{BitmapFormat, FillRule, FontLoaded, GenerationOptions};
+use crate::generator::{GeneratedFont, font::Font, rle_encode};
+
+/// Glyphs are generated with a distance field of 1px, which is enough for the coverage of the
+/// pixels on the outline, and a pixel of padding to hold it.
+const SPREAD: f32 = 1.0;
+const PADDING: i32 = 1;
+
+/// How the pixels of a `TextImage` are stored, must match `glyphr::ImageFormat`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ImageFormat {
+    /// Anti-aliased 8 bit coverage, RLE encoded in `[count, value]` pairs like SDF glyphs.
+    Alpha,
+    /// 1 bit per pixel, packed like Bitmap glyphs.
+    Mono,
+}
+
+/// A text rasterized in a single image, cropped to the pixels it covers.
+pub struct TextImage {
+    pub name: String,
+    pub text: String,
+    pub width: i32,
+    pub height: i32,
+    /// Position of the image relative to the point `Glyphr::render` draws the text at, with the
+    /// default alignment.
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+}
+
+impl TextImage {
+    /// Lays out `text` as `Glyphr::render` does with the default alignment and scale 1, and
+    /// rasterizes it at `px`. Characters the font has no glyph for are an error.
+    pub fn new(
+        name: &str,
+        font: Rc<Font>,
+        px: i32,
+        text: &str,
+        format: ImageFormat,
+        fill_rule: FillRule,
+    ) -> Result<Self, Error> {
+        let mut char_range: Vec<char> = text.chars().collect();
+        char_range.sort_unstable();
+        char_range.dedup();
+        let generated = GeneratedFont::new(FontLoaded {
+            name: name.into(),
+            font,
+            px,
+            char_range,
+            format: match format {
+                ImageFormat::Alpha => BitmapFormat::SDF {
+                    spread: SPREAD,
+                    padding: PADDING,
+                },
+                ImageFormat::Mono => BitmapFormat::Bitmap {
+                    spread: SPREAD,
+                    padding: PADDING,
+                },
+            },
+            options: GenerationOptions {
+                fill_rule,
+                ..Default::default()
+            },
+            family: None,
+        })?;
+
+        // Same placement as the runtime: glyphs at the pen position, top aligned.
+        let (ascent, descent) = (generated.ascent(), generated.descent());
+        let mut pen = 0;
+        let mut placed = Vec::new();
+        for ch in text.chars() {
+            let (bitmap, entry) = generated
+                .glyphs
+                .iter()
+                .find(|(_, entry)| entry.character == ch)
+                .expect("missing glyphs are an error");
+            // Whitespace has no bitmap, only an advance.
+            if bitmap.is_empty() {
+                pen += entry.advance_width;
+                continue;
+            }
+            let x = pen + entry.xmin;
+            let y = descent + ascent - entry.ymin - entry.height;
+            let coverage: Vec<u8> = match format {
+                ImageFormat::Alpha => rle_decode(bitmap).map(sdf_coverage).collect(),
+                ImageFormat::Mono => (0..(entry.width * entry.height) as usize)
+                    .map(|i| match bitmap.get(i / 8) {
+                        Some(byte) if byte & (0x80 >> (i % 8)) != 0 => 255,
+                        _ => 0,
+                    })
+                    .collect(),
+            };
+            placed.push((x, y, entry.width, entry.height, coverage));
+            pen += entry.advance_width;
+        }
+
+        let mut image = Canvas::around(&placed);
+        for (x, y, width, height, coverage) in &placed {
+            image.draw(*x, *y, *width, *height, coverage);
+        }
+        let image = image.cropped();
+
+        let data = match format {
+            ImageFormat::Alpha => rle_encode(image.pixels),
+            ImageFormat::Mono => pack_bits(&image.pixels),
+        };
+        Ok(Self {
+            name: name.into(),
+            text: text.into(),
+            width: image.width,
+            height: image.height,
+            x_offset: image.x,
+            y_offset: image.y,
+            format,
+            data,
+        })
+    }
+}
+
+/// Coverage of a pixel from its distance field value, with a 1px spread: the field is 0.5 on
+/// the outline and changes by 0.5 per pixel.
+fn sdf_coverage(value: u8) -> u8 {
+    let coverage = (2.0 * value as f32 / 255.0 - 0.5).clamp(0.0, 1.0);
+    (coverage * 255.0).round() as u8
+}
+
+pub(crate) fn rle_decode(data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    data.chunks_exact(2)
+        .flat_map(|run| std::iter::repeat_n(run[1], run[0] as usize))
+}
+
+/// Packs pixels in bits, most significant first, without padding between rows.
+fn pack_bits(pixels: &[u8]) -> Vec<u8> {
+    pixels
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &p)| byte | (((p > 0) as u8) << (7 - i)))
+        })
+        .collect()
+}
+
+/// Coverage image placed at (`x`, `y`).
+struct Canvas {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Empty canvas covering every glyph.
+    fn around(placed: &[(i32, i32, i32, i32, Vec<u8>)]) -> Self {
+        let boxes = placed.iter().filter(|(_, _, w, h, _)| *w > 0 && *h > 0);
+        let x0 = boxes.clone().map(|(x, ..)| *x).min().unwrap_or(0);
+        let y0 = boxes.clone().map(|(_, y, ..)| *y).min().unwrap_or(0);
+        let x1 = boxes.clone().map(|(x, _, w, ..)| x + w).max().unwrap_or(0);
+        let y1 = boxes.map(|(_, y, _, h, _)| y + h).max().unwrap_or(0);
+        Self {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            pixels: vec![0; ((x1 - x0) * (y1 - y0)) as usize],
+        }
+    }
+
+    /// Draws a glyph, keeping the highest coverage where glyphs overlap.
+    fn draw(&mut self, x: i32, y: i32, width: i32, height: i32, coverage: &[u8]) {
+        for row in 0..height {
+            for column in 0..width {
+                let value = coverage[(row * width + column) as usize];
+                let index = ((y - self.y + row) * self.width + x - self.x + column) as usize;
+                self.pixels[index] = self.pixels[index].max(value);
+            }
+        }
+    }
+
+    /// The smallest canvas with all the covered pixels.
+    fn cropped(self) -> Self {
+        let covered = |x: i32, y: i32| self.pixels[(y * self.width + x) as usize] > 0;
+        let rows: Vec<i32> = (0..self.height)
+            .filter(|&y| (0..self.width).any(|x| covered(x, y)))
+            .collect();
+        let columns: Vec<i32> = (0..self.width)
+            .filter(|&x| (0..self.height).any(|y| covered(x, y)))
+            .collect();
+        let (Some(&top), Some(&bottom), Some(&left), Some(&right)) =
+            (rows.first(), rows.last(), columns.first(), columns.last())
+        else {
+            return Self {
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+                ..self
+            };
+        };
+
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in top..=bottom {
+            let start = (y * self.width + left) as usize;
+            pixels.extend_from_slice(&self.pixels[start..start + width as usize]);
+        }
+        Self {
+            x: self.x + left,
+            y: self.y + top,
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::load_font;
+
+    const DEMO_TTF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/demo.ttf");
+
+    fn image(text: &str, format: ImageFormat) -> Result<TextImage, Error> {
+        let font = Rc::new(load_font(DEMO_TTF).unwrap());
+        TextImage::new("LABEL", font, 32, text, format, FillRule::default())
+    }
+
+    fn pixels(image: &TextImage) -> Vec<u8> {
+        match image.format {
+            ImageFormat::Alpha => rle_decode(&image.data).collect(),
+            ImageFormat::Mono => (0..(image.width * image.height) as usize)
+                .map(|i| (image.data[i / 8] >> (7 - i % 8)) & 1)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_text_image_layout() {
+        let single = image("A", ImageFormat::Mono).unwrap();
+        let double = image("AA", ImageFormat::Mono).unwrap();
+        assert_eq!(
+            pixels(&single).len(),
+            (single.width * single.height) as usize
+        );
+        assert_eq!(
+            (double.x_offset, double.y_offset),
+            (single.x_offset, single.y_offset)
+        );
+        assert_eq!(double.height, single.height);
+
+        // The second 'A' is one advance to the right of the first one.
+        let font = load_font(DEMO_TTF).unwrap();
+        let advance = font.metrics('A', 32.0).unwrap().advance_width;
+        assert_eq!(double.width, advance + single.width);
+    }
+
+    #[test]
+    fn test_alpha_image_is_antialiased() {
+        let alpha = image("A", ImageFormat::Alpha).unwrap();
+        let mono = image("A", ImageFormat::Mono).unwrap();
+        let values = pixels(&alpha);
+        assert_eq!(values.len(), (alpha.width * alpha.height) as usize);
+        assert!(values.contains(&255) && values.iter().any(|&v| v > 0 && v < 255));
+        assert!(alpha.width >= mono.width && alpha.height >= mono.height);
+    }
+
+    #[test]
+    fn test_text_image_missing_glyphs() {
+        assert!(matches!(
+            image("AB", ImageFormat::Alpha),
+            Err(Error::MissingGlyphs { characters, .. }) if characters == ['B']
+        ));
+        let empty = image("", ImageFormat::Alpha).unwrap();
+        assert_eq!((empty.width, empty.height, empty.data.len()), (0, 0, 0));
+    }
+}
@@ -0,0 +1,338 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::config::{
+    BitmapFormat, FillRule, FontLoaded, GenerationOptions, IconSource, MissingGlyphs, ToFontLoaded,
+    add_icons, check_family, collect_char_set, expand_family, load_font_with,
+};
+use crate::generator::font::{FaceSelector, FontSettings};
+
+/// Contains all the fonts specified in the `toml`
+#[derive(Deserialize)]
+pub struct TomlConfig {
+    pub font: Vec<TomlFont>,
+}
+
+/// Describes one font in the `toml`
+#[derive(Deserialize, Clone)]
+pub struct TomlFont {
+    pub name: String,
+    pub path: String,
+    /// Face of a collection, by index (`face = 1`) or name (`face = "NotoSansCJKjp-Bold"`).
+    #[serde(default)]
+    pub face: FaceSelector,
+    pub size: OneOrMany<i32>,
+    #[serde(default)]
+    pub characters: String,
+    /// Text files whose characters are added to `characters`, relative to the `toml`.
+    #[serde(default, deserialize_with = "one_or_many_strings")]
+    pub characters_from: Vec<String>,
+    pub format: OneOrMany<BitmapFormat>,
+    /// `"NonZero"` (default) or `"EvenOdd"`.
+    #[serde(default)]
+    pub fill_rule: FillRule,
+    /// `"Error"` (default), `"Skip"` or `"Notdef"`.
+    #[serde(default)]
+    pub missing: MissingGlyphs,
+    #[serde(default)]
+    pub include_notdef: bool,
+    /// Generates the layers of COLR glyphs, drawn with their palette colors.
+    #[serde(default)]
+    pub color: bool,
+    /// Named instance of a variable font.
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// Variation axis coordinates, e.g. `{ wght = 600 }`, applied after `instance`.
+    #[serde(default)]
+    pub axes: BTreeMap<String, f32>,
+    /// SVG and PNG files imported as glyphs, by name, e.g.
+    /// `{ wifi = { path = "wifi.svg", codepoint = 0xE000 } }`. Every name gets a `ICON_NAME`
+    /// constant with its codepoint.
+    #[serde(default)]
+    pub icons: BTreeMap<String, IconSource>,
+}
+
+/// Lets a key hold either a single value or an array of values
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+/// `characters_from` is optional, so it's kept as a plain list.
+fn one_or_many_strings<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(match OneOrMany::<String>::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+impl<T> OneOrMany<T> {
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            OneOrMany::One(value) => std::slice::from_ref(value),
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+impl ToFontLoaded for TomlConfig {
+    fn to_font_loaded(&self) -> Result<Vec<FontLoaded>, Error> {
+        let mut fonts = Vec::new();
+
+        for toml_font in &self.font {
+            check_family(
+                &toml_font.name,
+                toml_font.size.as_slice(),
+                toml_font.format.as_slice(),
+            )?;
+            let mut font = load_font_with(
+                &toml_font.path,
+                &toml_font.face,
+                FontSettings {
+                    instance: toml_font.instance.clone(),
+                    axes: toml_font.axes.clone().into_iter().collect(),
+                    ..Default::default()
+                },
+            )?;
+            let mut char_range =
+                collect_char_set(&toml_font.characters, &toml_font.characters_from)?;
+            add_icons(&mut font, &toml_font.icons, &mut char_range)?;
+            fonts.extend(expand_family(
+                &toml_font.name,
+                font,
+                toml_font.size.as_slice(),
+                toml_font.format.as_slice(),
+                char_range,
+                GenerationOptions {
+                    fill_rule: toml_font.fill_rule,
+                    missing: toml_font.missing,
+                    include_notdef: toml_font.include_notdef,
+                    color: toml_font.color,
+                },
+            ));
+        }
+
+        Ok(fonts)
+    }
+}
+
+impl FromStr for TomlConfig {
+    type Err = toml::de::Error;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        toml::from_str(content)
+    }
+}
+
+impl TomlConfig {
+    /// Reads a `toml` file and relativizes the font paths to it.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut config: TomlConfig = content.parse().map_err(|source| Error::Toml {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        config.relativize_paths(&path.to_string_lossy());
+        Ok(config)
+    }
+
+    /// Used to relativize the paths of ttfs to the relative path of the `toml` file
+    pub fn relativize_paths(&mut self, toml_path: &str) {
+        let toml_path = Path::new(toml_path);
+
+        let base_dir = toml_path.parent().unwrap_or(Path::new(""));
+
+        let relativize = |path: &mut String| {
+            if Path::new(path.as_str()).is_relative() {
+                *path = base_dir.join(path.as_str()).to_string_lossy().to_string();
+            }
+        };
+
+        for font in &mut self.font {
+            relativize(&mut font.path);
+            font.characters_from.iter_mut().for_each(relativize);
+            for icon in font.icons.values_mut() {
+                relativize(&mut icon.path);
+            }
+        }
+    }
+}
+
+/// Same layout as `TomlConfig`, keeping where every value is in the file.
+#[derive(Deserialize)]
+struct SpannedConfig {
+    font: Vec<BTreeMap<String, toml::Spanned<toml::Value>>>,
+}
+
+/// Line and column (both starting at 1) of the value of `key` in the `font`-th `[[font]]` table.
+pub fn key_location(content: &str, font: usize, key: &str) -> Option<(usize, usize)> {
+    let config: SpannedConfig = toml::from_str(content).ok()?;
+    let span = config.font.get(font)?.get(key)?.span();
+    Some(line_column(content, span.start))
+}
+
+/// Line and column (both starting at 1) of a byte offset in `content`.
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |newline| &before[newline + 1..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy_cfg() -> TomlConfig {
+        TomlConfig {
+            font: vec![TomlFont {
+                name: "lol".into(),
+                path: "a.ttf".into(),
+                face: FaceSelector::default(),
+                size: OneOrMany::One(23),
+                characters: "A-Z".into(),
+                characters_from: vec!["strings/de.po".into()],
+                format: OneOrMany::One(BitmapFormat::SDF {
+                    spread: 20.0,
+                    padding: 0,
+                }),
+                fill_rule: Default::default(),
+                missing: Default::default(),
+                include_notdef: false,
+                color: false,
+                instance: None,
+                axes: BTreeMap::new(),
+                icons: BTreeMap::from([(
+                    "wifi".into(),
+                    IconSource {
+                        path: "icons/wifi.svg".into(),
+                        codepoint: 0xE000,
+                        baseline: 0,
+                        advance: None,
+                    },
+                )]),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_relativize_paths() {
+        let mut cfg = dummy_cfg();
+        cfg.relativize_paths("fonts/fonts.toml");
+        assert_eq!("fonts/a.ttf", &cfg.font[0].path);
+        assert_eq!("fonts/strings/de.po", &cfg.font[0].characters_from[0]);
+        assert_eq!("fonts/icons/wifi.svg", &cfg.font[0].icons["wifi"].path);
+    }
+
+    #[test]
+    fn test_relativize_paths_absolute() {
+        let mut cfg = dummy_cfg();
+        cfg.font[0].path = "/Users/fonts/a.ttf".into();
+        cfg.relativize_paths("fonts/fonts.toml");
+        assert_eq!("/Users/fonts/a.ttf", &cfg.font[0].path);
+    }
+
+    #[test]
+    fn test_size_and_format_lists() {
+        let cfg: TomlConfig = toml::from_str(
+            r#"
+            [[font]]
+            name = "FAMILY"
+            path = "a.ttf"
+            face = 0
+            size = [16, 24]
+            characters = "A-Z"
+            format = [{ SDF = { spread = 20.0, padding = 0 } }, { Bitmap = { spread = 2.0, padding = 0 } }]
+
+            [[font]]
+            name = "SINGLE"
+            path = "a.ttf"
+            size = 16
+            characters = "A-Z"
+            format = { SDF = { spread = 20.0, padding = 0 } }
+            fill_rule = "EvenOdd"
+            characters_from = "ui.txt"
+            instance = "Bold"
+            axes = { wght = 650, slnt = -5.5 }
+            face = "Demo-Wide"
+            icons = { wifi = { path = "wifi.svg", codepoint = 0xE000 }, heart = { path = "heart.png", codepoint = 0xE001, baseline = 1, advance = 9 } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.font[0].size.as_slice(), &[16, 24]);
+        assert_eq!(cfg.font[0].format.as_slice().len(), 2);
+        assert_eq!(cfg.font[1].size.as_slice(), &[16]);
+        assert_eq!(cfg.font[1].format.as_slice().len(), 1);
+        assert_eq!(cfg.font[0].fill_rule, FillRule::NonZero);
+        assert_eq!(cfg.font[1].fill_rule, FillRule::EvenOdd);
+        assert!(cfg.font[0].characters_from.is_empty());
+        assert_eq!(cfg.font[1].characters_from, ["ui.txt"]);
+        assert_eq!(cfg.font[1].instance.as_deref(), Some("Bold"));
+        assert_eq!(cfg.font[1].axes["wght"], 650.0);
+        assert_eq!(cfg.font[1].axes["slnt"], -5.5);
+        assert_eq!(cfg.font[0].face, FaceSelector::Index(0));
+        assert_eq!(cfg.font[1].face, FaceSelector::Name("Demo-Wide".into()));
+        assert!(cfg.font[0].icons.is_empty());
+        assert_eq!(cfg.font[1].icons["wifi"].codepoint, 0xE000);
+        assert_eq!(cfg.font[1].icons["wifi"].advance, None);
+        assert_eq!(cfg.font[1].icons["heart"].baseline, 1);
+        assert_eq!(cfg.font[1].icons["heart"].advance, Some(9));
+    }
+
+    #[test]
+    fn test_duplicate_family_members() {
+        let mut cfg = dummy_cfg();
+        cfg.font[0].icons.clear();
+        cfg.font[0].format = OneOrMany::Many(vec![
+            BitmapFormat::SDF {
+                spread: 2.0,
+                padding: 0,
+            },
+            BitmapFormat::SDF {
+                spread: 4.0,
+                padding: 0,
+            },
+        ]);
+        assert!(matches!(
+            cfg.to_font_loaded(),
+            Err(Error::DuplicateFormat { format: "SDF", .. })
+        ));
+
+        cfg.font[0].format = OneOrMany::One(BitmapFormat::Bitmap {
+            spread: 2.0,
+            padding: 0,
+        });
+        cfg.font[0].size = OneOrMany::Many(vec![16, 24, 16]);
+        assert!(matches!(
+            cfg.to_font_loaded(),
+            Err(Error::DuplicateSize { size: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn test_key_location() {
+        let content = "[[font]]\nname = \"A\"\n\n[[font]]\nname = \"B\"\ncharacters = \"xyz\"\n";
+        assert_eq!(key_location(content, 1, "characters"), Some((6, 14)));
+        assert_eq!(key_location(content, 0, "name"), Some((2, 8)));
+        assert_eq!(key_location(content, 0, "characters"), None);
+        assert_eq!(key_location(content, 2, "name"), None);
+    }
+}
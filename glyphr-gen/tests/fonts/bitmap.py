@@ -0,0 +1,104 @@
+# Builds bitmap.bdf, and the same font as bitmap.pcf and bitmap.pcf.gz: a 10px pixel font with
+# a box for DEFAULT_CHAR (U+0000), 'A', 'g' (with a descender and a left bearing), an empty
+# space and a glyph outside of the encoding. The PCF file is little-endian with the bits most
+# significant first, glyph rows padded to 4 bytes and a scan unit of 4 bytes, so the bytes of
+# every unit are swapped.
+#
+#   python3 bitmap.py
+import gzip, struct
+
+GLYPHS = [
+    # name, encoding, advance, (width, height, xoff, yoff), rows
+    ('box', 0, 6, (5, 8, 0, 0), ['#####', '#...#', '#...#', '#...#', '#...#', '#...#', '#...#', '#####']),
+    ('space', 32, 6, (0, 0, 0, 0), []),
+    ('A', 65, 6, (5, 7, 0, 0), ['.###.', '#...#', '#...#', '#####', '#...#', '#...#', '#...#']),
+    ('g', 103, 6, (4, 7, 1, -2), ['.###', '#..#', '#..#', '.###', '...#', '...#', '###.']),
+    ('unencoded', -1, 6, (2, 2, 0, 0), ['##', '##']),
+]
+ASCENT, DESCENT, PIXEL_SIZE = 8, 2, 10
+
+
+def row_bytes(row, length):
+    bits = ''.join('1' if c == '#' else '0' for c in row).ljust(length * 8, '0')
+    return bytes(int(bits[i:i + 8], 2) for i in range(0, len(bits), 8))
+
+
+def bdf():
+    lines = ['STARTFONT 2.1', 'FONT -test-bitmap-medium-r-normal--10-100-75-75-c-60-iso10646-1',
+             'SIZE 10 75 75', 'FONTBOUNDINGBOX 6 10 0 -2', 'STARTPROPERTIES 6',
+             'FONT_ASCENT %d' % ASCENT, 'FONT_DESCENT %d' % DESCENT, 'PIXEL_SIZE %d' % PIXEL_SIZE,
+             'DEFAULT_CHAR 0', 'CHARSET_REGISTRY "ISO10646"', 'CHARSET_ENCODING "1"',
+             'ENDPROPERTIES', 'CHARS %d' % len(GLYPHS)]
+    for name, encoding, advance, (w, h, x, y), rows in GLYPHS:
+        lines += ['STARTCHAR ' + name, 'ENCODING %d' % encoding, 'SWIDTH 600 0',
+                  'DWIDTH %d 0' % advance, 'BBX %d %d %d %d' % (w, h, x, y), 'BITMAP']
+        lines += [row_bytes(row, (w + 7) // 8).hex().upper() for row in rows]
+        lines.append('ENDCHAR')
+    lines.append('ENDFONT')
+    return '\n'.join(lines) + '\n'
+
+
+PROPERTIES, METRICS, BITMAPS, BDF_ENCODINGS = 1 << 0, 1 << 2, 1 << 3, 1 << 5
+BYTE_MSB, BIT_MSB, COMPRESSED_METRICS = 1 << 2, 1 << 3, 1 << 8
+GLYPH_PAD, SCAN_UNIT = 2, 2 << 4
+
+
+def pcf():
+    encoded = [g for g in GLYPHS if g[1] >= 0]
+    tables = {}
+
+    props = [('FONT_ASCENT', ASCENT), ('FONT_DESCENT', DESCENT), ('PIXEL_SIZE', PIXEL_SIZE),
+             ('DEFAULT_CHAR', 0), ('CHARSET_REGISTRY', 'ISO10646'), ('CHARSET_ENCODING', '1')]
+    strings = b''
+    entries = b''
+    for name, value in props:
+        name_offset = len(strings)
+        strings += name.encode() + b'\x00'
+        if isinstance(value, str):
+            entries += struct.pack('<iBi', name_offset, 1, len(strings))
+            strings += value.encode() + b'\x00'
+        else:
+            entries += struct.pack('<iBi', name_offset, 0, value)
+    padding = b'\x00' * ((4 - len(props) % 4) % 4)
+    tables[PROPERTIES] = (0, struct.pack('<i', len(props)) + entries + padding
+                          + struct.pack('<i', len(strings)) + strings)
+
+    metrics = struct.pack('<h', len(encoded))
+    for _, _, advance, (w, h, x, y), _ in encoded:
+        metrics += bytes(v + 0x80 for v in (x, x + w, advance, h + y, -y))
+    tables[METRICS] = (COMPRESSED_METRICS, metrics)
+
+    offsets, data = [], b''
+    for _, _, _, (w, h, x, y), rows in encoded:
+        offsets.append(len(data))
+        length = ((w + 7) // 8 + 3) // 4 * 4
+        glyph = b''.join(row_bytes(row, length) for row in rows)
+        # Little-endian scan units of 4 bytes.
+        data += b''.join(glyph[i:i + 4][::-1] for i in range(0, len(glyph), 4))
+    bitmaps = struct.pack('<i', len(encoded)) + b''.join(struct.pack('<i', o) for o in offsets)
+    bitmaps += struct.pack('<4i', 0, 0, len(data), 0) + data
+    tables[BITMAPS] = (BIT_MSB | SCAN_UNIT | GLYPH_PAD, bitmaps)
+
+    indices = [0xFFFF] * 128
+    for index, g in enumerate(encoded):
+        indices[g[1]] = index
+    encodings = struct.pack('<5h', 0, 127, 0, 0, 0) + struct.pack('<128H', *indices)
+    tables[BDF_ENCODINGS] = (0, encodings)
+
+    out = b'\x01fcp' + struct.pack('<I', len(tables))
+    offset = 8 + 16 * len(tables)
+    body = b''
+    for table_type in sorted(tables):
+        fmt, content = tables[table_type]
+        content = struct.pack('<I', fmt) + content
+        content += b'\x00' * (-len(content) % 4)
+        out += struct.pack('<4I', table_type, fmt, len(content), offset + len(body))
+        body += content
+    return out + body
+
+
+open('bitmap.bdf', 'w').write(bdf())
+data = pcf()
+open('bitmap.pcf', 'wb').write(data)
+with gzip.GzipFile('bitmap.pcf.gz', 'wb', mtime=0) as f:
+    f.write(data)
@@ -0,0 +1,60 @@
+# Builds collection.ttc out of two faces: demo.ttf as "Demo Regular" and variable.ttf as
+# "Demo Wide", with the names needed to select them.
+#
+#   python3 collection.py demo.ttf variable.ttf collection.ttc
+import struct, sys
+
+def read_tables(path):
+    data = open(path, 'rb').read()
+    num = struct.unpack('>H', data[4:6])[0]
+    tables = {}
+    for i in range(num):
+        tag, _, off, length = struct.unpack('>4sIII', data[12 + 16 * i:28 + 16 * i])
+        tables[tag] = data[off:off + length]
+    return tables
+
+def name_table(names, existing=None):
+    records = []
+    if existing:
+        count, string_offset = struct.unpack('>HH', existing[2:6])
+        for i in range(count):
+            record = struct.unpack('>HHHHHH', existing[6 + 12 * i:18 + 12 * i])
+            start = string_offset + record[5]
+            records.append((record[3], existing[start:start + record[4]]))
+    records += [(name_id, text.encode('utf-16-be')) for name_id, text in names]
+    records.sort()
+    strings = b''
+    out = b''
+    for name_id, encoded in records:
+        out += struct.pack('>HHHHHH', 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return struct.pack('>HHH', 0, len(records), 6 + 12 * len(records)) + out + strings
+
+def checksum(data):
+    padded = data + b'\x00' * (-len(data) % 4)
+    return sum(struct.unpack('>%dI' % (len(padded) // 4), padded)) & 0xffffffff
+
+faces = []
+for path, family, subfamily in [(sys.argv[1], 'Demo', 'Regular'), (sys.argv[2], 'Demo', 'Wide')]:
+    tables = read_tables(path)
+    names = [(1, family), (2, subfamily), (4, f'{family} {subfamily}'), (6, f'{family}-{subfamily}')]
+    tables[b'name'] = name_table(names, tables.get(b'name'))
+    faces.append(tables)
+
+header_len = 12 + 4 * len(faces)
+directories_len = sum(12 + 16 * len(tables) for tables in faces)
+offset = header_len + directories_len
+directories = b''
+body = b''
+offsets = []
+for tables in faces:
+    offsets.append(header_len + len(directories))
+    directory = struct.pack('>IHHHH', 0x00010000, len(tables), 0, 0, 0)
+    for tag in sorted(tables):
+        table = tables[tag]
+        directory += struct.pack('>4sIII', tag, checksum(table), offset + len(body), len(table))
+        body += table + b'\x00' * (-len(table) % 4)
+    directories += directory
+
+header = b'ttcf' + struct.pack('>HHI', 1, 0, len(faces)) + struct.pack('>%dI' % len(faces), *offsets)
+open(sys.argv[3], 'wb').write(header + directories + body)
@@ -0,0 +1,42 @@
+# Builds color.ttf: demo.ttf plus COLRv0 layers for 'A', the frame of .notdef in palette entry 0
+# under 'A' in the text color, and a CPAL table with two palettes of two colors.
+#
+#   python3 color.py demo.ttf color.ttf
+import struct, sys
+
+src, dst = sys.argv[1], sys.argv[2]
+data = open(src, 'rb').read()
+num = struct.unpack('>H', data[4:6])[0]
+tables = {}
+for i in range(num):
+    tag, _, off, length = struct.unpack('>4sIII', data[12 + 16 * i:28 + 16 * i])
+    tables[tag] = data[off:off + length]
+
+base_glyphs = [(1, 0, 2)]
+layers = [(0, 0), (1, 0xffff)]
+colr = struct.pack('>HHIIH', 0, len(base_glyphs), 14, 14 + 6 * len(base_glyphs), len(layers))
+colr += b''.join(struct.pack('>HHH', *record) for record in base_glyphs)
+colr += b''.join(struct.pack('>HH', *record) for record in layers)
+
+# Colors are stored as BGRA.
+palettes = [[(0x00, 0x00, 0xff, 0xff), (0x00, 0x80, 0x00, 0x80)],
+            [(0xff, 0x00, 0x00, 0xff), (0x00, 0xff, 0xff, 0xff)]]
+records = [color for palette in palettes for color in palette]
+cpal = struct.pack('>HHHHI', 0, 2, len(palettes), len(records), 12 + 2 * len(palettes))
+cpal += b''.join(struct.pack('>H', 2 * i) for i in range(len(palettes)))
+cpal += b''.join(struct.pack('>BBBB', *color) for color in records)
+
+tables[b'COLR'] = colr
+tables[b'CPAL'] = cpal
+
+tags = sorted(tables)
+out = struct.pack('>IHHHH', 0x00010000, len(tags), 0, 0, 0)
+offset = 12 + 16 * len(tags)
+body = b''
+for tag in tags:
+    table = tables[tag]
+    padded = table + b'\x00' * (-len(table) % 4)
+    checksum = sum(struct.unpack('>%dI' % (len(padded) // 4), padded)) & 0xffffffff
+    out += struct.pack('>4sIII', tag, checksum, offset + len(body), len(table))
+    body += padded
+open(dst, 'wb').write(out + body)
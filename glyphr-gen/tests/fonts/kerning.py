@@ -0,0 +1,53 @@
+# Builds two copies of demo.ttf kerning the pair 'A' 'A': kern.ttf with a `kern` table (-100
+# units) and gpos.ttf with a `kern` feature in GPOS (-150 units, pair positioning format 1).
+#
+#   python3 kerning.py demo.ttf kern.ttf gpos.ttf
+import struct, sys
+
+src, kern_dst, gpos_dst = sys.argv[1], sys.argv[2], sys.argv[3]
+data = open(src, 'rb').read()
+num = struct.unpack('>H', data[4:6])[0]
+tables = {}
+for i in range(num):
+    tag, _, off, length = struct.unpack('>4sIII', data[12 + 16 * i:28 + 16 * i])
+    tables[tag] = data[off:off + length]
+
+pairs = [(1, 1, -100)]
+subtable = struct.pack('>HHHH', len(pairs), 6, 0, 0)
+subtable += b''.join(struct.pack('>HHh', *pair) for pair in pairs)
+kern = struct.pack('>HH', 0, 1) + struct.pack('>HHH', 0, 6 + len(subtable), 0x0001) + subtable
+
+# Every offset is relative to the start of the table it is stored in.
+lang_sys = struct.pack('>HHHH', 0, 0xffff, 1, 0)
+script = struct.pack('>HH', 4, 0) + lang_sys
+script_list = struct.pack('>H4sH', 1, b'DFLT', 8) + script
+feature = struct.pack('>HHH', 0, 1, 0)
+feature_list = struct.pack('>H4sH', 1, b'kern', 8) + feature
+coverage = struct.pack('>HHH', 1, 1, 1)
+pair_set = struct.pack('>HHh', 1, 1, -150)
+# Format 1, x advance of the first glyph only.
+pair_pos = struct.pack('>HHHHHH', 1, 12, 0x0004, 0, 1, 12 + len(coverage)) + coverage + pair_set
+lookup = struct.pack('>HHHH', 2, 0, 1, 8) + pair_pos
+lookup_list = struct.pack('>HH', 1, 4) + lookup
+gpos = struct.pack('>HHHHH', 1, 0, 10, 10 + len(script_list),
+                   10 + len(script_list) + len(feature_list))
+gpos += script_list + feature_list + lookup_list
+
+
+def write(dst, extra):
+    font = {**tables, **extra}
+    tags = sorted(font)
+    out = struct.pack('>IHHHH', 0x00010000, len(tags), 0, 0, 0)
+    offset = 12 + 16 * len(tags)
+    body = b''
+    for tag in tags:
+        table = font[tag]
+        padded = table + b'\x00' * (-len(table) % 4)
+        checksum = sum(struct.unpack('>%dI' % (len(padded) // 4), padded)) & 0xffffffff
+        out += struct.pack('>4sIII', tag, checksum, offset + len(body), len(table))
+        body += padded
+    open(dst, 'wb').write(out + body)
+
+
+write(kern_dst, {b'kern': kern})
+write(gpos_dst, {b'GPOS': gpos})
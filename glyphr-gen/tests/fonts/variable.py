@@ -0,0 +1,58 @@
+# Builds variable.ttf: demo.ttf plus a `wght` axis (100-400-900) that widens 'A', with the named
+# instances "Thin" (100) and "Black" (900).
+#
+#   python3 variable.py demo.ttf variable.ttf
+import struct, sys
+
+src, dst = sys.argv[1], sys.argv[2]
+data = open(src, 'rb').read()
+num = struct.unpack('>H', data[4:6])[0]
+tables = {}
+for i in range(num):
+    tag, _, off, length = struct.unpack('>4sIII', data[12 + 16 * i:28 + 16 * i])
+    tables[tag] = data[off:off + length]
+
+def fixed(v): return struct.pack('>i', int(v * 65536))
+
+fvar = struct.pack('>HHHHHHHH', 1, 0, 16, 2, 1, 20, 2, 8)
+fvar += b'wght' + fixed(100) + fixed(400) + fixed(900) + struct.pack('>HH', 0, 256)
+fvar += struct.pack('>HH', 257, 0) + fixed(100)
+fvar += struct.pack('>HH', 258, 0) + fixed(900)
+
+names = [(256, 'Weight'), (257, 'Thin'), (258, 'Black')]
+strings = b''
+records = b''
+for name_id, text in names:
+    encoded = text.encode('utf-16-be')
+    records += struct.pack('>HHHHHH', 3, 1, 0x409, name_id, len(encoded), len(strings))
+    strings += encoded
+name = struct.pack('>HHH', 0, len(names), 6 + 12 * len(names)) + records + strings
+
+xs = [173, 369, 270, 6, 224, 320, 541, 452, 390, 151, 85]
+x_deltas = [round((x - 273) * 0.3) for x in xs] + [0, 0, 0, 0]
+deltas = bytes([0x40 | (len(x_deltas) - 1)]) + struct.pack('>%dh' % len(x_deltas), *x_deltas)
+deltas += bytes([0x80 | (len(x_deltas) - 1)])
+serialized = b'\x00' + deltas
+glyph = struct.pack('>HH', 0x8000 | 1, 4 + 6) + struct.pack('>HHh', len(deltas), 0x8000, 0x4000)
+glyph += serialized
+if len(glyph) % 2:
+    glyph += b'\x00'
+header_len = 20 + 3 * 2
+gvar = struct.pack('>HHHHIHHI', 1, 0, 1, 0, header_len, 2, 0, header_len)
+gvar += struct.pack('>HHH', 0, 0, len(glyph) // 2) + glyph
+
+tables[b'fvar'] = fvar
+tables[b'gvar'] = gvar
+tables[b'name'] = name
+
+tags = sorted(tables)
+out = struct.pack('>IHHHH', 0x00010000, len(tags), 0, 0, 0)
+offset = 12 + 16 * len(tags)
+body = b''
+for tag in tags:
+    table = tables[tag]
+    padded = table + b'\x00' * (-len(table) % 4)
+    checksum = sum(struct.unpack('>%dI' % (len(padded) // 4), padded)) & 0xffffffff
+    out += struct.pack('>4sIII', tag, checksum, offset + len(body), len(table))
+    body += padded
+open(dst, 'wb').write(out + body)
@@ -0,0 +1,215 @@
+# Builds web.ttf and the same font as web.woff and web.woff2. It's demo.ttf plus a composite
+# glyph for U+00C4, with the last glyph's left side bearing in the monospaced part of `hmtx`.
+# The WOFF2 file uses the `glyf`/`loca` and `hmtx` transforms, compressed with the system's
+# libbrotlienc.
+#
+#   python3 web.py demo.ttf
+import ctypes, struct, sys, zlib
+
+
+def read_tables(path):
+    data = open(path, 'rb').read()
+    num = struct.unpack('>H', data[4:6])[0]
+    tables = {}
+    for i in range(num):
+        tag, _, off, length = struct.unpack('>4sIII', data[12 + 16 * i:28 + 16 * i])
+        tables[tag] = data[off:off + length]
+    return tables
+
+
+def checksum(data):
+    padded = data + b'\x00' * (-len(data) % 4)
+    return sum(struct.unpack('>%dI' % (len(padded) // 4), padded)) & 0xffffffff
+
+
+def pad4(data):
+    return data + b'\x00' * (-len(data) % 4)
+
+
+def sfnt(tables):
+    tags = sorted(tables)
+    out = struct.pack('>IHHHH', 0x00010000, len(tags), 32, 2, len(tags) * 16 - 32)
+    offset = 12 + 16 * len(tags)
+    body = b''
+    for tag in tags:
+        out += struct.pack('>4sIII', tag, checksum(tables[tag]), offset + len(body), len(tables[tag]))
+        body += pad4(tables[tag])
+    return out + body
+
+
+demo = read_tables(sys.argv[1])
+loca = struct.unpack('>3H', demo[b'loca'])
+glyphs = [demo[b'glyf'][loca[i] * 2:loca[i + 1] * 2] for i in range(2)]
+
+# 'A' as is, plus `.notdef` at half size over it.
+ARG_WORDS, XY_VALUES, SCALE, MORE = 0x0001, 0x0002, 0x0008, 0x0020
+composite = struct.pack('>hhhhh', -1, 6, 0, 600, 1050)
+composite += struct.pack('>HHhh', ARG_WORDS | XY_VALUES | MORE, 1, 0, 0)
+composite += struct.pack('>HHhhh', ARG_WORDS | XY_VALUES | SCALE, 0, 300, 700, 0x2000)
+glyphs.append(composite)
+
+glyf = b''
+offsets = []
+for glyph in glyphs:
+    offsets.append(len(glyf) // 2)
+    glyf += pad4(glyph)
+offsets.append(len(glyf) // 2)
+
+tables = dict(demo)
+tables[b'glyf'] = glyf
+tables[b'loca'] = struct.pack('>%dH' % len(offsets), *offsets)
+tables[b'hmtx'] = struct.pack('>HhHhh', 600, 100, 540, 6, 6)
+maxp = bytearray(demo[b'maxp'])
+maxp[4:6] = struct.pack('>H', len(glyphs))
+tables[b'maxp'] = bytes(maxp)
+cmap_groups = [(0x41, 0x41, 1), (0xC4, 0xC4, 2)]
+subtable = struct.pack('>HHIII', 12, 0, 16 + 12 * len(cmap_groups), 0, len(cmap_groups))
+for group in cmap_groups:
+    subtable += struct.pack('>III', *group)
+tables[b'cmap'] = struct.pack('>HHHHI', 0, 1, 3, 10, 12) + subtable
+
+ttf = sfnt(tables)
+open('web.ttf', 'wb').write(ttf)
+
+# WOFF: every table compressed with zlib, when it's smaller.
+woff_header_len = 44 + 20 * len(tables)
+directory = b''
+body = b''
+for tag in sorted(tables):
+    table = tables[tag]
+    compressed = zlib.compress(table, 9)
+    stored = compressed if len(compressed) < len(table) else table
+    directory += struct.pack('>4sIIII', tag, woff_header_len + len(body), len(stored), len(table),
+                             checksum(table))
+    body += pad4(stored)
+woff = b'wOFF' + struct.pack('>IIHHIHHIIIII', 0x00010000, woff_header_len + len(body),
+                             len(tables), 0, len(ttf), 1, 0, 0, 0, 0, 0, 0)
+open('web.woff', 'wb').write(woff + directory + body)
+
+
+# WOFF2
+def uint_base128(value):
+    out = [value & 0x7f]
+    value >>= 7
+    while value:
+        out.insert(0, 0x80 | (value & 0x7f))
+        value >>= 7
+    return bytes(out)
+
+
+def uint_255(value):
+    if value < 253:
+        return bytes([value])
+    if value < 506:
+        return bytes([255, value - 253])
+    if value < 762:
+        return bytes([254, value - 506])
+    return bytes([253]) + struct.pack('>H', value)
+
+
+def triplet(on_curve, x, y):
+    ax, ay = abs(x), abs(y)
+    on = 0 if on_curve else 128
+    xs, ys = int(x >= 0), int(y >= 0)
+    signs = xs + 2 * ys
+    if x == 0 and ay < 1280:
+        return on + ((ay & 0xf00) >> 7) + ys, bytes([ay & 0xff])
+    if y == 0 and ax < 1280:
+        return on + 10 + ((ax & 0xf00) >> 7) + xs, bytes([ax & 0xff])
+    if ax < 65 and ay < 65:
+        return (on + 20 + ((ax - 1) & 0x30) + (((ay - 1) & 0x30) >> 2) + signs,
+                bytes([(((ax - 1) & 0xf) << 4) | ((ay - 1) & 0xf)]))
+    if ax < 769 and ay < 769:
+        return (on + 84 + 12 * (((ax - 1) & 0x300) >> 8) + (((ay - 1) & 0x300) >> 6) + signs,
+                bytes([(ax - 1) & 0xff, (ay - 1) & 0xff]))
+    if ax < 4096 and ay < 4096:
+        return on + 120 + signs, bytes([ax >> 4, ((ax & 0xf) << 4) | (ay >> 8), ay & 0xff])
+    return on + 124 + signs, struct.pack('>HH', ax, ay)
+
+
+def simple_points(glyph):
+    contours = struct.unpack('>h', glyph[:2])[0]
+    ends = struct.unpack('>%dH' % contours, glyph[10:10 + 2 * contours])
+    at = 10 + 2 * contours
+    instructions_len = struct.unpack('>H', glyph[at:at + 2])[0]
+    at += 2 + instructions_len
+    count = ends[-1] + 1
+    flags = []
+    while len(flags) < count:
+        flag = glyph[at]
+        at += 1
+        flags.append(flag)
+        if flag & 8:
+            flags += [flag] * glyph[at]
+            at += 1
+    coordinates = []
+    for short, same in ((2, 16), (4, 32)):
+        values = []
+        for flag in flags:
+            if flag & short:
+                values.append(glyph[at] if flag & same else -glyph[at])
+                at += 1
+            elif flag & same:
+                values.append(0)
+            else:
+                values.append(struct.unpack('>h', glyph[at:at + 2])[0])
+                at += 2
+        coordinates.append(values)
+    return ends, [(flag & 1, dx, dy) for flag, dx, dy in zip(flags, *coordinates)]
+
+
+streams = {name: b'' for name in ('contours', 'points', 'flags', 'glyphs', 'composite',
+                                  'bbox', 'instructions')}
+bbox_bitmap = bytearray(4 * ((len(glyphs) + 31) // 32))
+for index, glyph in enumerate(glyphs):
+    contours = struct.unpack('>h', glyph[:2])[0]
+    streams['contours'] += struct.pack('>h', contours)
+    if contours > 0:
+        ends, points = simple_points(glyph)
+        previous = -1
+        for end in ends:
+            streams['points'] += uint_255(end - previous)
+            previous = end
+        for on_curve, dx, dy in points:
+            flag, data = triplet(on_curve, dx, dy)
+            streams['flags'] += bytes([flag])
+            streams['glyphs'] += data
+        streams['glyphs'] += uint_255(0)
+    else:
+        streams['composite'] += glyph[10:]
+        bbox_bitmap[index >> 3] |= 0x80 >> (index & 7)
+        streams['bbox'] += glyph[2:10]
+streams['bbox'] = bytes(bbox_bitmap) + streams['bbox']
+
+glyf_transformed = struct.pack('>HHHH', 0, 0, len(glyphs), 0)
+glyf_transformed += b''.join(struct.pack('>I', len(stream)) for stream in streams.values())
+glyf_transformed += b''.join(streams.values())
+# Both side bearing arrays are left out, they match the xMin of the glyphs.
+hmtx_transformed = struct.pack('>BHH', 3, 600, 540)
+
+KNOWN_TAGS = [b'cmap', b'head', b'hhea', b'hmtx', b'maxp', b'name', b'OS/2', b'post', b'cvt ',
+              b'fpgm', b'glyf', b'loca']
+order = [b'cmap', b'glyf', b'loca', b'head', b'hhea', b'hmtx', b'maxp']
+directory = b''
+stream = b''
+for tag in order:
+    transformed = {b'glyf': glyf_transformed, b'loca': b'', b'hmtx': hmtx_transformed}.get(tag)
+    version = 1 if tag == b'hmtx' else 0
+    directory += bytes([KNOWN_TAGS.index(tag) | (version << 6)]) + uint_base128(len(tables[tag]))
+    if transformed is not None:
+        directory += uint_base128(len(transformed))
+        stream += transformed
+    else:
+        stream += tables[tag]
+
+brotli = ctypes.CDLL('libbrotlienc.so.1')
+out_size = ctypes.c_size_t(len(stream) + 1024)
+out = ctypes.create_string_buffer(out_size.value)
+assert brotli.BrotliEncoderCompress(11, 22, 2, ctypes.c_size_t(len(stream)), stream,
+                                    ctypes.byref(out_size), out)
+compressed = out.raw[:out_size.value]
+
+length = 48 + len(directory) + len(pad4(compressed))
+woff2 = b'wOF2' + struct.pack('>IIHHIIHHIIIII', 0x00010000, length, len(order), 0, len(ttf),
+                              len(compressed), 1, 0, 0, 0, 0, 0, 0)
+open('web.woff2', 'wb').write(woff2 + directory + pad4(compressed))
@@ -1,28 +1,26 @@
-mod config;
-mod generator;
 mod macro_parser;
-mod renderer;
-
-#[cfg(feature = "toml")]
-mod toml_parser;
 
+use glyphr_gen::renderer;
 use proc_macro::TokenStream;
+use quote::{ToTokens, quote, quote_spanned};
+use std::env;
+use std::path::{Path, PathBuf};
 use syn::parse_macro_input;
 
-use macro_parser::FontConfig;
+use macro_parser::{FontConfig, TextImageConfig, TextInput};
 
 /// Macro used to generate a font with data direcly in the code
 #[proc_macro]
 pub fn generate_font(input: TokenStream) -> TokenStream {
-    let font_input: FontConfig = parse_macro_input!(input as FontConfig);
-
-    let rendered = renderer::render(font_input);
+    let mut font_input: FontConfig = parse_macro_input!(input as FontConfig);
+    let files = resolve_font_paths(&mut font_input);
 
-    match rendered.parse() {
-        Ok(parsed) => parsed,
-        Err(e) => {
-            panic!("Failed to generate font: {e}")
-        }
+    let spans = font_input.spans;
+    match renderer::render(font_input) {
+        Ok(rendered) => with_tracking(rendered, &files),
+        Err(err) => syn::Error::new(spans.of(&err, &files[0]), err)
+            .to_compile_error()
+            .into(),
     }
 }
 
@@ -31,33 +29,255 @@ pub fn generate_font(input: TokenStream) -> TokenStream {
 #[cfg(feature = "toml")]
 #[proc_macro]
 pub fn generate_fonts_from_toml(input: TokenStream) -> TokenStream {
+    use glyphr_gen::config::ToFontLoaded;
+    use glyphr_gen::generator::font::FontError;
+    use glyphr_gen::toml_parser::{TomlConfig, key_location};
+    use glyphr_gen::{Cache, Error as GenError};
     use std::fs;
     use syn::LitStr;
-    use toml;
 
     let file_path = parse_macro_input!(input as LitStr);
-    let path_str = file_path.value();
-    let content = match fs::read_to_string(&path_str) {
+    let path = resolve_path(None, &file_path.value());
+    let content = match fs::read_to_string(&path) {
         Ok(content) => content,
         Err(err) => {
             return syn::Error::new_spanned(
                 file_path,
-                format!("Failed to read file '{path_str}': {err}"),
+                format!("Failed to read file '{}': {err}", path.display()),
             )
             .to_compile_error()
             .into();
         }
     };
-    let mut toml_input: toml_parser::TomlConfig =
-        toml::from_str(&content).expect("Could not parse toml file");
-    toml_input.relativize_paths(&path_str);
+    let mut toml_input: TomlConfig = match content.parse() {
+        Ok(toml_input) => toml_input,
+        Err(err) => {
+            return syn::Error::new_spanned(file_path, format!("{}: {err}", path.display()))
+                .to_compile_error()
+                .into();
+        }
+    };
+    toml_input.relativize_paths(&path.to_string_lossy());
+
+    // Fonts are generated one at a time, to tell which `[[font]]` an error comes from, and the
+    // constants of their icons once at the end.
+    let mut rendered = String::new();
+    let mut icons = Vec::new();
+    let cache = Cache::from_env();
+    for (index, font) in toml_input.font.iter().enumerate() {
+        let single = TomlConfig {
+            font: vec![font.clone()],
+        };
+        let code = single.to_font_loaded().and_then(|fonts| {
+            for loaded in &fonts {
+                icons.extend_from_slice(loaded.font.icons());
+            }
+            renderer::render_fonts(fonts, cache.as_ref())
+        });
+        match code {
+            Ok(code) => rendered.push_str(&code),
+            Err(err) => {
+                let key = match &err {
+                    GenError::Io { path, .. } if *path != Path::new(&font.path) => {
+                        "characters_from"
+                    }
+                    GenError::FontParse {
+                        source: FontError::Face(_),
+                        ..
+                    } => "face",
+                    GenError::FontParse {
+                        source: FontError::Variation(_),
+                        ..
+                    } if font.instance.is_none() || !font.axes.is_empty() => "axes",
+                    GenError::FontParse {
+                        source: FontError::Variation(_),
+                        ..
+                    } => "instance",
+                    GenError::Io { .. } | GenError::FontParse { .. } => "path",
+                    GenError::MissingGlyphs { .. }
+                    | GenError::Charset { .. }
+                    | GenError::ReservedCharacter { .. } => "characters",
+                    GenError::InvalidSize { .. }
+                    | GenError::FixedSize { .. }
+                    | GenError::DuplicateSize { .. } => "size",
+                    GenError::DuplicateFormat { .. } => "format",
+                    GenError::Icon { .. } => "icons",
+                    _ => "name",
+                };
+                let location = key_location(&content, index, key)
+                    .map(|(line, column)| format!(":{line}:{column}"))
+                    .unwrap_or_default();
+                return syn::Error::new_spanned(
+                    file_path,
+                    format!("{}{location}: {err}", path.display()),
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    }
+
+    match renderer::render_icon_constants(&icons) {
+        Ok(code) => rendered.push_str(&code),
+        Err(err) => {
+            return syn::Error::new_spanned(file_path, err)
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let mut files = vec![path];
+    for font in &toml_input.font {
+        files.push(PathBuf::from(&font.path));
+        files.extend(font.characters_from.iter().map(PathBuf::from));
+        files.extend(font.icons.values().map(|icon| PathBuf::from(&icon.path)));
+    }
+
+    with_tracking(rendered, &files)
+}
 
-    let rendered = renderer::render(toml_input);
+/// Lays out and rasterizes a static text at compile time into a `glyphr::TextImage`, drawn with
+/// `TextImage::blit` without any font at runtime.
+#[proc_macro]
+pub fn generate_text_image(input: TokenStream) -> TokenStream {
+    let mut image_input = parse_macro_input!(input as TextImageConfig);
+    let source = &mut image_input.source;
+    let path = resolve_path(source.base_dir.as_deref(), &source.path);
+    source.path = path.to_string_lossy().into_owned();
 
-    match rendered.parse() {
+    let spans = image_input.spans;
+    match image_input
+        .to_text_image()
+        .and_then(|image| renderer::render_text_image(&image))
+    {
+        Ok(rendered) => with_tracking(rendered, &[path]),
+        Err(err) => syn::Error::new(spans.of(&err, &path), err)
+            .to_compile_error()
+            .into(),
+    }
+}
+
+/// Checks at compile time that a generated font has every character of a string literal, and
+/// resolves them to glyph indices: `text!(FONT, "Hello")` makes a `glyphr::IndexedText` that is
+/// rendered without looking glyphs up.
+#[proc_macro]
+pub fn text(input: TokenStream) -> TokenStream {
+    let TextInput { font, text } = parse_macro_input!(input as TextInput);
+    let value = text.value();
+    let font_name = font.to_token_stream().to_string();
+    let indices = value.chars().map(|ch| {
+        let message = format!("{ch:?} is not in the font `{font_name}`");
+        quote_spanned! {text.span()=>
+            match ::glyphr::IndexedText::index(&#font, #ch) {
+                Some(index) => index,
+                None => panic!(#message),
+            }
+        }
+    });
+    let len = value.chars().count();
+
+    quote! {{
+        static INDICES: [u16; #len] = [#(#indices),*];
+        ::glyphr::IndexedText::new(#text, &#font, &INDICES)
+    }}
+    .into()
+}
+
+/// Resolves `path` against `base`, both relative to the manifest directory of the crate being
+/// compiled (absolute paths are left untouched).
+fn resolve_path(base: Option<&str>, path: &str) -> PathBuf {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    Path::new(&manifest_dir).join(base.unwrap_or("")).join(path)
+}
+
+/// Resolves every file `font_input` reads against its `base_dir`, returning them with the font
+/// first.
+fn resolve_font_paths(font_input: &mut FontConfig) -> Vec<PathBuf> {
+    let base = font_input.source.base_dir.clone();
+    let mut resolve = |path: &mut String| {
+        let resolved = resolve_path(base.as_deref(), path);
+        *path = resolved.to_string_lossy().into_owned();
+        resolved
+    };
+    let mut files = vec![resolve(&mut font_input.source.path)];
+    files.extend(font_input.characters_from.iter_mut().map(&mut resolve));
+    files.extend(
+        font_input
+            .icons
+            .iter_mut()
+            .map(|(_, icon)| resolve(&mut icon.path)),
+    );
+    files
+}
+
+/// Parses the generated code and appends a hidden `include_bytes!` of every file that was read,
+/// so the compiler rebuilds the crate when one of them changes.
+fn with_tracking(rendered: String, files: &[PathBuf]) -> TokenStream {
+    let mut output: proc_macro2::TokenStream = match rendered.parse() {
         Ok(parsed) => parsed,
         Err(e) => {
-            panic!("Failed to generate font: {e}")
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("Failed to generate font: {e}"),
+            )
+            .to_compile_error()
+            .into();
         }
+    };
+
+    let mut tracked: Vec<String> = files
+        .iter()
+        .map(|file| file.to_string_lossy().into_owned())
+        .collect();
+    tracked.sort();
+    tracked.dedup();
+    output.extend(quote! {
+        #(const _: &[u8] = include_bytes!(#tracked);)*
+    });
+
+    output.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(resolve_path(None, "a.ttf"), manifest_dir.join("a.ttf"));
+        assert_eq!(
+            resolve_path(Some("fonts"), "a.ttf"),
+            manifest_dir.join("fonts/a.ttf")
+        );
+        assert_eq!(
+            resolve_path(Some("fonts"), "/abs/a.ttf"),
+            Path::new("/abs/a.ttf")
+        );
+    }
+
+    #[test]
+    fn test_base_dir_applies_to_every_file() {
+        let mut font_input: FontConfig = syn::parse_str(
+            r#"name: DEMO, path: "a.ttf", base_dir: "assets", size: 16,
+               format: Bitmap { spread: 2.0, padding: 0 },
+               characters_from: ["text.txt", "/abs/more.txt"],
+               icons: { wifi: { path: "icons/wifi.svg", codepoint: 0xE000 } }"#,
+        )
+        .unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let files = resolve_font_paths(&mut font_input);
+        assert_eq!(
+            files,
+            [
+                dir.join("a.ttf"),
+                dir.join("text.txt"),
+                PathBuf::from("/abs/more.txt"),
+                dir.join("icons/wifi.svg"),
+            ]
+        );
+        assert_eq!(font_input.source.path, files[0].to_string_lossy());
+        assert_eq!(font_input.characters_from[0], files[1].to_string_lossy());
+        assert_eq!(font_input.icons[0].1.path, files[3].to_string_lossy());
     }
 }
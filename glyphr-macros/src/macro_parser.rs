@@ -1,38 +1,196 @@
-use std::fs;
-use std::path::Path;
+use proc_macro2::Span;
 use syn::{Error, Ident, LitFloat, LitInt, LitStr, Token, parse::Parse};
 
-use crate::config::{BitmapFormat, FontLoaded, ToFontLoaded, parse_char_set};
-use crate::generator::font::Font;
+use glyphr_gen::Error as GenError;
+use glyphr_gen::config::{
+    BitmapFormat, FillRule, FontLoaded, GenerationOptions, IconSource, MissingGlyphs, ToFontLoaded,
+    add_icons, collect_char_set, expand_family, load_font_with,
+};
+use glyphr_gen::generator::font::{FaceSelector, Font, FontError, FontSettings};
+use glyphr_gen::text_image::{ImageFormat, TextImage};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The font file the macros read and how its outlines are loaded and filled.
+#[derive(Default)]
+pub struct FontSource {
+    pub path: String,
+    /// Directory the paths of the macro are relative to, itself relative to the crate's manifest
+    /// directory.
+    pub base_dir: Option<String>,
+    /// Face of a font collection.
+    pub face: FaceSelector,
+    pub fill_rule: FillRule,
+    /// Named instance of a variable font.
+    pub instance: Option<String>,
+    /// Variation axis coordinates, applied after `instance`.
+    pub axes: Vec<(String, f32)>,
+}
+
+impl FontSource {
+    pub fn load(&self) -> Result<Font, GenError> {
+        load_font_with(
+            &self.path,
+            &self.face,
+            FontSettings {
+                instance: self.instance.clone(),
+                axes: self.axes.clone(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Parses the value of `field` if it's one of the source fields, returning whether it was.
+    fn parse_field(
+        &mut self,
+        field: &str,
+        input: syn::parse::ParseStream,
+        spans: &mut FieldSpans,
+    ) -> syn::Result<bool> {
+        let value_span = input.span();
+        match field {
+            "path" => {
+                self.path = input.parse::<LitStr>()?.value();
+                spans.path = value_span;
+            }
+            "base_dir" => {
+                self.base_dir = Some(input.parse::<LitStr>()?.value());
+            }
+            "face" => {
+                self.face = if input.peek(LitStr) {
+                    FaceSelector::Name(input.parse::<LitStr>()?.value())
+                } else {
+                    FaceSelector::Index(input.parse::<LitInt>()?.base10_parse()?)
+                };
+                spans.face = value_span;
+            }
+            "fill_rule" => {
+                self.fill_rule = parse_fill_rule(input)?;
+            }
+            "instance" => {
+                self.instance = Some(input.parse::<LitStr>()?.value());
+                spans.variation = value_span;
+            }
+            "axes" => {
+                self.axes = parse_axes(input)?;
+                spans.variation = value_span;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Checks that the required fields were given, once every field is parsed.
+    fn check(&self) -> syn::Result<()> {
+        if self.path.is_empty() {
+            return Err(Error::new(Span::call_site(), "Missing 'path' field"));
+        }
+        Ok(())
+    }
+}
 
 /// Describes the content of the macro
 pub struct FontConfig {
     pub name: Ident,
-    pub path: String,
-    pub size: i32,
+    /// `base_dir` applies to `characters_from` and icon paths too.
+    pub source: FontSource,
+    pub size: Vec<i32>,
     pub characters: String,
-    pub format: BitmapFormat,
+    /// Text files whose characters are added to `characters`.
+    pub characters_from: Vec<String>,
+    pub format: Vec<BitmapFormat>,
+    pub missing: MissingGlyphs,
+    pub include_notdef: bool,
+    /// Generate the layers of COLR glyphs.
+    pub color: bool,
+    /// SVG and PNG files imported as glyphs, by name.
+    pub icons: Vec<(String, IconSource)>,
+    pub spans: FieldSpans,
 }
 
-impl ToFontLoaded for FontConfig {
-    fn to_font_loaded(&self) -> Vec<FontLoaded> {
-        let mut fonts = Vec::new();
+/// Where the values of the macro fields are, to point errors at them. Fields that are absent
+/// point at the macro call.
+#[derive(Clone, Copy)]
+pub struct FieldSpans {
+    pub name: Span,
+    pub path: Span,
+    pub face: Span,
+    pub size: Span,
+    pub characters: Span,
+    pub characters_from: Span,
+    pub format: Span,
+    pub variation: Span,
+    pub icons: Span,
+}
 
-        let ttf_file = fs::read(Path::new(&self.path))
-            .unwrap_or_else(|_| panic!("can't read ttf file at path: {}", self.path));
-        let font = Font::from_bytes(ttf_file.as_slice(), Default::default())
-            .expect("failed to parse ttf file");
+impl Default for FieldSpans {
+    fn default() -> Self {
+        let call_site = Span::call_site();
+        Self {
+            name: call_site,
+            path: call_site,
+            face: call_site,
+            size: call_site,
+            characters: call_site,
+            characters_from: call_site,
+            format: call_site,
+            variation: call_site,
+            icons: call_site,
+        }
+    }
+}
 
-        let font = FontLoaded {
-            name: self.name.to_string(),
-            font,
-            px: self.size,
-            char_range: parse_char_set(&self.characters),
-            format: self.format,
-        };
+impl FieldSpans {
+    /// The field that caused a generation error, `font_path` telling apart the font file from
+    /// the `characters_from` ones.
+    pub fn of(&self, err: &GenError, font_path: &Path) -> Span {
+        match err {
+            GenError::Io { path, .. } if path != font_path => self.characters_from,
+            GenError::FontParse {
+                source: FontError::Face(_),
+                ..
+            } => self.face,
+            GenError::FontParse {
+                source: FontError::Variation(_),
+                ..
+            } => self.variation,
+            GenError::Io { .. } | GenError::FontParse { .. } => self.path,
+            GenError::MissingGlyphs { .. }
+            | GenError::Charset { .. }
+            | GenError::ReservedCharacter { .. } => self.characters,
+            GenError::InvalidSize { .. }
+            | GenError::FixedSize { .. }
+            | GenError::DuplicateSize { .. } => self.size,
+            GenError::DuplicateFormat { .. } => self.format,
+            GenError::Icon { .. } => self.icons,
+            _ => self.name,
+        }
+    }
+}
 
-        fonts.push(font);
-        fonts
+impl ToFontLoaded for FontConfig {
+    fn to_font_loaded(&self) -> Result<Vec<FontLoaded>, GenError> {
+        let mut font = self.source.load()?;
+        let mut char_range = collect_char_set(&self.characters, &self.characters_from)?;
+        add_icons(
+            &mut font,
+            self.icons.iter().map(|(name, icon)| (name, icon)),
+            &mut char_range,
+        )?;
+
+        Ok(expand_family(
+            &self.name.to_string(),
+            font,
+            &self.size,
+            &self.format,
+            char_range,
+            GenerationOptions {
+                fill_rule: self.source.fill_rule,
+                missing: self.missing,
+                include_notdef: self.include_notdef,
+                color: self.color,
+            },
+        ))
     }
 }
 
@@ -40,31 +198,76 @@ impl ToFontLoaded for FontConfig {
 impl Parse for FontConfig {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut name = None;
-        let mut path = None;
+        let mut source = FontSource::default();
         let mut size = None;
         let mut characters = None;
+        let mut characters_from = None;
         let mut format = None;
+        let mut missing = None;
+        let mut include_notdef = None;
+        let mut color = None;
+        let mut icons = Vec::new();
+        let mut spans = FieldSpans::default();
 
         while !input.is_empty() {
             let field_name: Ident = input.parse()?;
             input.parse::<Token![:]>()?;
+            let value_span = input.span();
 
             match field_name.to_string().as_str() {
                 "name" => {
                     name = Some(input.parse::<Ident>()?);
-                }
-                "path" => {
-                    path = Some(input.parse::<LitStr>()?.value());
+                    spans.name = value_span;
                 }
                 "size" => {
-                    size = Some(input.parse::<LitInt>()?.base10_parse::<i32>()?);
+                    let sizes = parse_one_or_many(input, |input| {
+                        let size = input.parse::<LitInt>()?;
+                        Ok((size.span(), size.base10_parse::<i32>()?))
+                    })?;
+                    if let Some(span) = duplicate(&sizes, |size| *size) {
+                        return Err(Error::new(span, "This size is already listed"));
+                    }
+                    size = Some(sizes.into_iter().map(|(_, size)| size).collect());
+                    spans.size = value_span;
                 }
                 "characters" => {
                     characters = Some(input.parse::<LitStr>()?.value());
+                    spans.characters = value_span;
+                }
+                "characters_from" => {
+                    characters_from = Some(parse_one_or_many(input, |input| {
+                        Ok(input.parse::<LitStr>()?.value())
+                    })?);
+                    spans.characters_from = value_span;
                 }
                 "format" => {
-                    format = Some(parse_format(input)?);
+                    let formats =
+                        parse_one_or_many(input, |input| Ok((input.span(), parse_format(input)?)))?;
+                    // Family members are named by format kind, whatever its parameters.
+                    if let Some(span) = duplicate(&formats, std::mem::discriminant) {
+                        return Err(Error::new(
+                            span,
+                            "This format kind is already listed, family members are named by \
+                             size and format kind only",
+                        ));
+                    }
+                    format = Some(formats.into_iter().map(|(_, format)| format).collect());
+                    spans.format = value_span;
+                }
+                "missing" => {
+                    missing = Some(parse_missing(input)?);
+                }
+                "include_notdef" => {
+                    include_notdef = Some(input.parse::<syn::LitBool>()?.value);
                 }
+                "color" => {
+                    color = Some(input.parse::<syn::LitBool>()?.value);
+                }
+                "icons" => {
+                    icons = parse_icons(input)?;
+                    spans.icons = value_span;
+                }
+                field if source.parse_field(field, input, &mut spans)? => {}
                 _ => {
                     return Err(Error::new(field_name.span(), "Unknown field"));
                 }
@@ -76,17 +279,172 @@ impl Parse for FontConfig {
             }
         }
 
+        // Without `characters` the requested set is only the `characters_from` files.
+        if characters.is_none() {
+            spans.characters = spans.characters_from;
+        }
+
+        let name = name.ok_or_else(|| Error::new(Span::call_site(), "Missing 'name' field"))?;
+        source.check()?;
         Ok(FontConfig {
-            name: name.ok_or_else(|| Error::new(input.span(), "Missing 'name' field"))?,
-            path: path.ok_or_else(|| Error::new(input.span(), "Missing 'path' field"))?,
-            size: size.ok_or_else(|| Error::new(input.span(), "Missing 'size' field"))?,
-            characters: characters
-                .ok_or_else(|| Error::new(input.span(), "Missing 'characters' field"))?,
-            format: format.ok_or_else(|| Error::new(input.span(), "Missing 'format' field"))?,
+            name,
+            source,
+            size: size.ok_or_else(|| Error::new(Span::call_site(), "Missing 'size' field"))?,
+            characters: match (characters, &characters_from) {
+                (Some(characters), _) => characters,
+                (None, Some(_)) => String::new(),
+                (None, None) => {
+                    return Err(Error::new(
+                        Span::call_site(),
+                        "Missing 'characters' or 'characters_from' field",
+                    ));
+                }
+            },
+            characters_from: characters_from.unwrap_or_default(),
+            format: format
+                .ok_or_else(|| Error::new(Span::call_site(), "Missing 'format' field"))?,
+            missing: missing.unwrap_or_default(),
+            include_notdef: include_notdef.unwrap_or_default(),
+            color: color.unwrap_or_default(),
+            icons,
+            spans,
         })
     }
 }
 
+/// Describes the content of `generate_text_image!`
+pub struct TextImageConfig {
+    pub name: Ident,
+    pub source: FontSource,
+    pub size: i32,
+    pub text: String,
+    pub format: ImageFormat,
+    /// `characters` is where `text` is.
+    pub spans: FieldSpans,
+}
+
+impl TextImageConfig {
+    pub fn to_text_image(&self) -> Result<TextImage, GenError> {
+        let font = self.source.load()?;
+
+        TextImage::new(
+            &self.name.to_string(),
+            Rc::new(font),
+            self.size,
+            &self.text,
+            self.format,
+            self.source.fill_rule,
+        )
+    }
+}
+
+impl Parse for TextImageConfig {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        let mut source = FontSource::default();
+        let mut size = None;
+        let mut text = None;
+        let mut format = None;
+        let mut spans = FieldSpans::default();
+
+        while !input.is_empty() {
+            let field_name: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+            let value_span = input.span();
+
+            match field_name.to_string().as_str() {
+                "name" => {
+                    name = Some(input.parse::<Ident>()?);
+                    spans.name = value_span;
+                }
+                "size" => {
+                    size = Some(input.parse::<LitInt>()?.base10_parse::<i32>()?);
+                    spans.size = value_span;
+                }
+                "text" => {
+                    text = Some(input.parse::<LitStr>()?.value());
+                    spans.characters = value_span;
+                }
+                "format" => {
+                    format = Some(parse_image_format(input)?);
+                    spans.format = value_span;
+                }
+                field if source.parse_field(field, input, &mut spans)? => {}
+                _ => {
+                    return Err(Error::new(field_name.span(), "Unknown field"));
+                }
+            }
+
+            // Optional comma
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let name = name.ok_or_else(|| Error::new(Span::call_site(), "Missing 'name' field"))?;
+        source.check()?;
+        Ok(TextImageConfig {
+            name,
+            source,
+            size: size.ok_or_else(|| Error::new(Span::call_site(), "Missing 'size' field"))?,
+            text: text.ok_or_else(|| Error::new(Span::call_site(), "Missing 'text' field"))?,
+            format: format.unwrap_or(ImageFormat::Alpha),
+            spans,
+        })
+    }
+}
+
+/// Parses `Alpha` or `Mono`
+fn parse_image_format(input: syn::parse::ParseStream) -> syn::Result<ImageFormat> {
+    let format: Ident = input.parse()?;
+    match format.to_string().as_str() {
+        "Alpha" => Ok(ImageFormat::Alpha),
+        "Mono" => Ok(ImageFormat::Mono),
+        _ => Err(Error::new(
+            format.span(),
+            "Unknown image format, expected 'Alpha' or 'Mono'",
+        )),
+    }
+}
+
+/// Parses either a single value or a non-empty `[a, b, ...]` list of values
+fn parse_one_or_many<T>(
+    input: syn::parse::ParseStream,
+    parse: fn(syn::parse::ParseStream) -> syn::Result<T>,
+) -> syn::Result<Vec<T>> {
+    if !input.peek(syn::token::Bracket) {
+        return Ok(vec![parse(input)?]);
+    }
+
+    let content;
+    let brackets = syn::bracketed!(content in input);
+    let mut values = Vec::new();
+    while !content.is_empty() {
+        values.push(parse(&content)?);
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+
+    if values.is_empty() {
+        return Err(Error::new(
+            brackets.span.join(),
+            "Expected at least one value",
+        ));
+    }
+    Ok(values)
+}
+
+/// Span of the first value whose `key` was already seen
+fn duplicate<T, K: PartialEq>(values: &[(Span, T)], key: impl Fn(&T) -> K) -> Option<Span> {
+    values.iter().enumerate().find_map(|(i, (span, value))| {
+        values[..i]
+            .iter()
+            .any(|(_, seen)| key(seen) == key(value))
+            .then_some(*span)
+    })
+}
+
 /// Parses the Bitmap/SDF format with parameters
 fn parse_format(input: syn::parse::ParseStream) -> syn::Result<BitmapFormat> {
     if input.peek(Ident) {
@@ -134,3 +492,120 @@ fn parse_format(input: syn::parse::ParseStream) -> syn::Result<BitmapFormat> {
         Err(Error::new(input.span(), "No format name provided"))
     }
 }
+
+/// Parses variation axis coordinates, like `{ wght: 600, slnt: -10.5 }`
+fn parse_axes(input: syn::parse::ParseStream) -> syn::Result<Vec<(String, f32)>> {
+    let content;
+    syn::braced!(content in input);
+    let mut axes = Vec::new();
+    while !content.is_empty() {
+        let tag: Ident = content.parse()?;
+        content.parse::<Token![:]>()?;
+        let negative = content.parse::<Option<Token![-]>>()?.is_some();
+        let value = match content.parse::<syn::Lit>()? {
+            syn::Lit::Int(value) => value.base10_parse::<f32>()?,
+            syn::Lit::Float(value) => value.base10_parse::<f32>()?,
+            other => return Err(Error::new(other.span(), "Expected a number")),
+        };
+        axes.push((tag.to_string(), if negative { -value } else { value }));
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    Ok(axes)
+}
+
+/// Parses SVG and PNG icons, like `{ wifi: { path: "icons/wifi.svg", codepoint: 0xE000 } }`,
+/// PNG ones also taking `baseline` and `advance`
+fn parse_icons(input: syn::parse::ParseStream) -> syn::Result<Vec<(String, IconSource)>> {
+    let content;
+    syn::braced!(content in input);
+    let mut icons = Vec::new();
+    while !content.is_empty() {
+        let name: Ident = content.parse()?;
+        content.parse::<Token![:]>()?;
+        let fields;
+        syn::braced!(fields in content);
+        let mut path = None;
+        let mut codepoint = None;
+        let mut baseline = 0;
+        let mut advance = None;
+        while !fields.is_empty() {
+            let field_name: Ident = fields.parse()?;
+            fields.parse::<Token![:]>()?;
+            match field_name.to_string().as_str() {
+                "path" => path = Some(fields.parse::<LitStr>()?.value()),
+                "codepoint" => codepoint = Some(fields.parse::<LitInt>()?.base10_parse::<u32>()?),
+                "baseline" => {
+                    let negative = fields.parse::<Option<Token![-]>>()?.is_some();
+                    let value = fields.parse::<LitInt>()?.base10_parse::<i32>()?;
+                    baseline = if negative { -value } else { value };
+                }
+                "advance" => advance = Some(fields.parse::<LitInt>()?.base10_parse::<i32>()?),
+                _ => return Err(Error::new(field_name.span(), "Unknown field")),
+            }
+            if fields.peek(Token![,]) {
+                fields.parse::<Token![,]>()?;
+            }
+        }
+        icons.push((
+            name.to_string(),
+            IconSource {
+                path: path.ok_or_else(|| Error::new(name.span(), "Missing 'path' field"))?,
+                codepoint: codepoint
+                    .ok_or_else(|| Error::new(name.span(), "Missing 'codepoint' field"))?,
+                baseline,
+                advance,
+            },
+        ));
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    Ok(icons)
+}
+
+/// Parses `NonZero` or `EvenOdd`
+fn parse_fill_rule(input: syn::parse::ParseStream) -> syn::Result<FillRule> {
+    let rule: Ident = input.parse()?;
+    match rule.to_string().as_str() {
+        "NonZero" => Ok(FillRule::NonZero),
+        "EvenOdd" => Ok(FillRule::EvenOdd),
+        _ => Err(Error::new(
+            rule.span(),
+            "Unknown fill rule, expected 'NonZero' or 'EvenOdd'",
+        )),
+    }
+}
+
+/// Parses `Error`, `Skip` or `Notdef`
+fn parse_missing(input: syn::parse::ParseStream) -> syn::Result<MissingGlyphs> {
+    let policy: Ident = input.parse()?;
+    match policy.to_string().as_str() {
+        "Error" => Ok(MissingGlyphs::Error),
+        "Skip" => Ok(MissingGlyphs::Skip),
+        "Notdef" => Ok(MissingGlyphs::Notdef),
+        _ => Err(Error::new(
+            policy.span(),
+            "Unknown missing glyphs policy, expected 'Error', 'Skip' or 'Notdef'",
+        )),
+    }
+}
+
+/// Describes the content of `text!`: a font and a string literal.
+pub struct TextInput {
+    pub font: syn::Expr,
+    pub text: LitStr,
+}
+
+impl Parse for TextInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let font = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let text = input.parse()?;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(TextInput { font, text })
+    }
+}
@@ -1,4 +1,6 @@
-use glyphr::{AlignH, AlignV, BufferTarget, Glyphr, RenderConfig, SdfConfig, TextAlign};
+use glyphr::{
+    AlignH, AlignV, BitmapConfig, BufferTarget, Glyphr, RenderConfig, SdfConfig, TextAlign,
+};
 #[cfg(feature = "window")]
 use minifb::{Window, WindowOptions};
 
@@ -33,6 +35,8 @@ fn main() {
             mid_value: 0.5,
             smoothing: 0.5,
         },
+        bitmap: BitmapConfig { scale: 1 },
+        palette: &[],
     };
     let renderer = Glyphr::with_config(conf);
 
@@ -53,7 +57,7 @@ fn main() {
         size: 64,
         characters: "A-Za-z! ",
         format: SDF {
-            spread: 20.0,
+            spread: 8.0,
             padding: 0,
         },
     }
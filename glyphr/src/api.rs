@@ -3,8 +3,9 @@
 //! This module describes the public API to this library.
 //! Everything is done via the `Glyphr` struct.
 
-use crate::font::{AlignH, AlignV, BitmapFormat, Font};
+use crate::font::{AlignH, AlignV, BitmapFormat, Font, FontFamily};
 use crate::renderer;
+use crate::text::Text;
 
 /// Trait used to make a target writable by Glyphr.
 pub trait RenderTarget {
@@ -91,6 +92,11 @@ pub struct RenderConfig {
     pub color: u32,
     /// SDF-specific configuration (ignored for bitmap fonts).
     pub sdf: SdfConfig,
+    /// Bitmap-specific configuration (ignored for SDF fonts).
+    pub bitmap: BitmapConfig,
+    /// Colors replacing the palette of color fonts, `0xAARRGGBB` by palette index. Layers
+    /// whose index is past the end keep the color of the font.
+    pub palette: &'static [u32],
 }
 
 impl Default for RenderConfig {
@@ -98,6 +104,8 @@ impl Default for RenderConfig {
         Self {
             color: 0xffffff,
             sdf: SdfConfig::default(),
+            bitmap: BitmapConfig::default(),
+            palette: &[],
         }
     }
 }
@@ -123,6 +131,20 @@ impl Default for SdfConfig {
     }
 }
 
+/// Configuration for Bitmap rendering (only used with Bitmap fonts).
+#[derive(Clone, Copy)]
+pub struct BitmapConfig {
+    /// Integer scale factor, every source pixel is drawn as a `scale`x`scale` block.
+    /// A value of 0 is treated as 1.
+    pub scale: u32,
+}
+
+impl Default for BitmapConfig {
+    fn default() -> Self {
+        Self { scale: 1 }
+    }
+}
+
 /// Text alignment options.
 #[derive(Clone, Copy)]
 pub struct TextAlign {
@@ -174,20 +196,19 @@ impl Glyphr {
         &self.render_config
     }
 
-    /// Render text to any target that implements RenderTarget.
-    pub fn render<T: RenderTarget>(
+    /// Render text (a `&str` or a text made by `text!`) to any target that implements
+    /// RenderTarget.
+    pub fn render<'a, T: RenderTarget>(
         &self,
         target: &mut T,
-        text: &str,
-        font: Font,
+        text: impl Into<Text<'a>>,
+        font: Font<'a>,
         mut x: i32,
         y: i32,
         align: TextAlign,
     ) -> Result<(), GlyphrError> {
-        let scale = match font.format {
-            BitmapFormat::SDF => self.render_config.sdf.size as f32 / font.size as f32,
-            BitmapFormat::Bitmap => 1.0,
-        };
+        let text = text.into();
+        let scale = self.scale(font);
         let ascent = font.ascent;
         let descent = font.descent;
 
@@ -206,29 +227,68 @@ impl Glyphr {
             AlignV::Baseline => -(ascent as f32 * scale) as i32,
         };
 
-        for c in text.chars() {
-            let glyph = font.find_glyph(c)?;
+        for glyph in text.glyphs(font) {
+            let glyph = glyph?;
+            let glyph_x = x - x_offset + (glyph.xmin as f32 * scale) as i32;
             let glyph_y =
                 y + y_offset + ((ascent - glyph.ymin - glyph.height) as f32 * scale) as i32;
-            renderer::render_glyph(x - x_offset, glyph_y, c, font, self, scale, target)?;
-            x += (renderer::advance(c, font).unwrap_or(0) as f32 * scale) as i32;
+            renderer::render_glyph(glyph_x, glyph_y, &glyph, font, self, scale, target)?;
+            x += (glyph.advance_width as f32 * scale) as i32;
         }
 
         Ok(())
     }
 
+    /// Picks the font of the family that best fits `px` pixels, see `FontFamily::select`.
+    pub fn select_font<'a>(
+        &self,
+        family: FontFamily<'a>,
+        px: u32,
+    ) -> Result<Font<'a>, GlyphrError> {
+        family.select(px).ok_or(GlyphrError::SizeNotAvailable(px))
+    }
+
+    /// Render text at `px` pixels with the font of the family that best fits it. SDF members are
+    /// scaled to `px` whatever `sdf.size` is, Bitmap members are drawn at their size times
+    /// `bitmap.scale`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_family<'a, T: RenderTarget>(
+        &self,
+        target: &mut T,
+        text: impl Into<Text<'a>>,
+        family: FontFamily<'a>,
+        px: u32,
+        x: i32,
+        y: i32,
+        align: TextAlign,
+    ) -> Result<(), GlyphrError> {
+        let font = self.select_font(family, px)?;
+        let mut config = self.render_config;
+        config.sdf.size = px;
+        Glyphr::with_config(config).render(target, text, font, x, y, align)
+    }
+
     /// Returns the lenght of the string that will be rendered.
-    pub fn phrase_length(&self, phrase: &str, font: Font) -> i32 {
-        let scale = match font.format {
-            BitmapFormat::SDF => self.render_config.sdf.size as f32 / font.size as f32,
-            BitmapFormat::Bitmap => 1.0,
-        };
+    pub fn phrase_length<'a>(&self, phrase: impl Into<Text<'a>>, font: Font<'a>) -> i32 {
+        let scale = self.scale(font);
         let mut tot = 0;
-        for c in phrase.chars() {
-            tot += (renderer::advance(c, font).unwrap_or(0) as f32 * scale) as i32;
+        for glyph in phrase.into().glyphs(font) {
+            tot += (glyph.map_or(0, |glyph| glyph.advance_width) as f32 * scale) as i32;
         }
         tot
     }
+
+    /// Returns the factor the font metrics are multiplied by when rendering.
+    fn scale(&self, font: Font) -> f32 {
+        match font.format {
+            BitmapFormat::SDF | BitmapFormat::ColorSDF => {
+                self.render_config.sdf.size as f32 / font.size as f32
+            }
+            BitmapFormat::Bitmap | BitmapFormat::ColorBitmap => {
+                self.render_config.bitmap.scale.max(1) as f32
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -236,6 +296,8 @@ pub enum GlyphrError {
     OutOfBounds,
     InvalidGlyph(char),
     InvalidTarget,
+    SizeNotAvailable(u32),
+    InvalidFontData(&'static str),
 }
 
 impl core::fmt::Display for GlyphrError {
@@ -244,6 +306,8 @@ impl core::fmt::Display for GlyphrError {
             GlyphrError::OutOfBounds => write!(f, "Rendering position is out of bounds"),
             GlyphrError::InvalidGlyph(c) => write!(f, "Glyph not found: '{c}'"),
             GlyphrError::InvalidTarget => write!(f, "Invalid render target"),
+            GlyphrError::SizeNotAvailable(px) => write!(f, "No font in the family fits {px}px"),
+            GlyphrError::InvalidFontData(reason) => write!(f, "Invalid font data: {reason}"),
         }
     }
 }
@@ -253,6 +317,7 @@ impl core::error::Error for GlyphrError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::font::{Glyph, Glyphs};
 
     #[test]
     fn test_sdf_config_default_values() {
@@ -269,6 +334,7 @@ mod tests {
         assert_eq!(cfg.sdf.size, 16);
         assert_eq!(cfg.sdf.mid_value, 0.5);
         assert_eq!(cfg.sdf.smoothing, 0.1);
+        assert_eq!(cfg.bitmap.scale, 1);
     }
 
     #[test]
@@ -285,9 +351,10 @@ mod tests {
     fn test_pixel_callback_writes_color() {
         let mut buffer = [0u32; 16];
         let mut target = BufferTarget::new(&mut buffer, 4, 4);
-        target.write_pixel(2, 1, 0xff123456);
+        let (x, y) = (2, 1);
+        target.write_pixel(x, y, 0xff123456);
 
-        let idx = 1 * 4 + 2;
+        let idx = y as usize * 4 + x as usize;
         assert_eq!(buffer[idx], 0xff123456);
     }
 
@@ -298,4 +365,88 @@ mod tests {
 
         assert_eq!(target.dimensions(), (4, 4));
     }
+
+    // 2x2 checkerboard: top-left and bottom-right pixels set.
+    static CHECKER_BITMAP: [u8; 1] = [0b1001_0000];
+    static CHECKER_GLYPHS: [Glyph; 1] = [Glyph {
+        character: 'x',
+        bitmap: &CHECKER_BITMAP,
+        width: 2,
+        height: 2,
+        xmin: 0,
+        ymin: 0,
+        advance_width: 3,
+    }];
+    static CHECKER_FONT: Font = Font {
+        glyphs: Glyphs::Static(&CHECKER_GLYPHS),
+        size: 2,
+        ascent: 2,
+        descent: 0,
+        format: BitmapFormat::Bitmap,
+    };
+
+    #[test]
+    fn test_bitmap_scale_doubles_pixels() {
+        let mut buffer = [0u32; 16];
+        let mut target = BufferTarget::new(&mut buffer, 4, 4);
+        let mut glyphr = Glyphr::new();
+        glyphr.render_config.bitmap.scale = 2;
+
+        glyphr
+            .render(&mut target, "x", CHECKER_FONT, 0, 0, TextAlign::default())
+            .unwrap();
+
+        let lit: [bool; 16] = core::array::from_fn(|i| buffer[i] != 0);
+        #[rustfmt::skip]
+        let expected = [
+            true, true, false, false,
+            true, true, false, false,
+            false, false, true, true,
+            false, false, true, true,
+        ];
+        assert_eq!(lit, expected);
+    }
+
+    static CHECKER_4_GLYPHS: [Glyph; 1] = [Glyph {
+        advance_width: 5,
+        ..CHECKER_GLYPHS[0]
+    }];
+    static BITMAP_FAMILY: [Font; 2] = [
+        CHECKER_FONT,
+        Font {
+            glyphs: Glyphs::Static(&CHECKER_4_GLYPHS),
+            size: 4,
+            ..CHECKER_FONT
+        },
+    ];
+
+    #[test]
+    fn test_bitmap_family_uses_requested_size() {
+        let family = FontFamily {
+            fonts: &BITMAP_FAMILY,
+        };
+        // `sdf.size` stays at its default of 16, only `px` picks the member.
+        let glyphr = Glyphr::new();
+        assert_eq!(glyphr.select_font(family, 4).unwrap().size, 4);
+        assert_eq!(glyphr.select_font(family, 2).unwrap().size, 2);
+        assert!(matches!(
+            glyphr.select_font(family, 16),
+            Err(GlyphrError::SizeNotAvailable(16))
+        ));
+
+        let mut buffer = [0u32; 16];
+        let mut target = BufferTarget::new(&mut buffer, 4, 4);
+        glyphr
+            .render_family(&mut target, "x", family, 2, 0, 0, TextAlign::default())
+            .unwrap();
+        assert!(buffer[0] != 0 && buffer[5] != 0 && buffer[1] == 0);
+    }
+
+    #[test]
+    fn test_bitmap_scale_phrase_length() {
+        let mut glyphr = Glyphr::new();
+        assert_eq!(glyphr.phrase_length("xx", CHECKER_FONT), 6);
+        glyphr.render_config.bitmap.scale = 3;
+        assert_eq!(glyphr.phrase_length("xx", CHECKER_FONT), 18);
+    }
 }
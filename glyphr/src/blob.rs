@@ -0,0 +1,385 @@
+//! # blob.rs
+//!
+//! Binary serialization of a `Font`, so fonts can be loaded at runtime from flash, external
+//! memory or an SD card instead of being compiled in.
+//!
+//! Every integer is little-endian. A blob is made of a header, a glyph table sorted by
+//! codepoint and the bitmap section:
+//!
+//! | offset          | size   | content                                           |
+//! |-----------------|--------|---------------------------------------------------|
+//! | 0               | 4      | magic `b"GLYR"`                                   |
+//! | 4               | 2      | version (`2`)                                     |
+//! | 6               | 1      | format (`0` = SDF, `1` = Bitmap, `2` = ColorSDF,  |
+//! |                 |        | `3` = ColorBitmap)                                |
+//! | 7               | 1      | reserved, `0`                                     |
+//! | 8               | 4      | size (`i32`)                                      |
+//! | 12              | 4      | ascent (`i32`)                                    |
+//! | 16              | 4      | descent (`i32`)                                   |
+//! | 20              | 4      | glyph count `n` (`u32`)                           |
+//! | 24              | 32 * n | glyph records                                     |
+//! | 24 + 32 * n     | ..     | bitmaps                                           |
+//!
+//! A glyph record holds, in order: codepoint (`u32`), width, height, xmin, ymin, advance width
+//! (all `i32`), then offset and length of the bitmap (`u32`, relative to the bitmap section).
+//!
+//! Version `2` added the color formats `2` and `3`, the layout is otherwise the same as version
+//! `1`. Version `1` blobs are still read, with format `0` or `1` only.
+
+use crate::GlyphrError;
+use crate::font::{BitmapFormat, Font, Glyph, Glyphs};
+
+pub const MAGIC: [u8; 4] = *b"GLYR";
+pub const VERSION: u16 = 2;
+pub const HEADER_LEN: usize = 24;
+pub const RECORD_LEN: usize = 32;
+/// Largest glyph width and height accepted by `parse`.
+pub const MAX_GLYPH_SIZE: i32 = u16::MAX as i32;
+
+/// Glyph table of a font blob, already validated by `parse`.
+#[derive(Clone, Copy)]
+pub struct GlyphTable<'a> {
+    records: &'a [u8],
+    bitmaps: &'a [u8],
+}
+
+impl<'a> GlyphTable<'a> {
+    pub fn len(&self) -> usize {
+        self.records.len() / RECORD_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the codepoint of the glyph at `index`, without decoding the rest of the record.
+    pub fn codepoint(&self, index: usize) -> u32 {
+        read_u32(self.records, index * RECORD_LEN)
+    }
+
+    /// Decodes the glyph at `index`. `index` must be smaller than `len()`.
+    pub fn glyph(&self, index: usize) -> Glyph<'a> {
+        let record = &self.records[index * RECORD_LEN..(index + 1) * RECORD_LEN];
+        let offset = read_u32(record, 24) as usize;
+        let len = read_u32(record, 28) as usize;
+        Glyph {
+            // Codepoints and bitmap ranges are checked by `parse`.
+            character: char::from_u32(read_u32(record, 0)).unwrap_or_default(),
+            bitmap: &self.bitmaps[offset..offset + len],
+            width: read_i32(record, 4),
+            height: read_i32(record, 8),
+            xmin: read_i32(record, 12),
+            ymin: read_i32(record, 16),
+            advance_width: read_i32(record, 20),
+        }
+    }
+
+    /// Binary search by codepoint.
+    pub fn position(&self, ch: char) -> Option<usize> {
+        let target = ch as u32;
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.codepoint(mid).cmp(&target) {
+                core::cmp::Ordering::Less => lo = mid + 1,
+                core::cmp::Ordering::Greater => hi = mid,
+                core::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+/// Validates a blob and builds a `Font` borrowing from it.
+pub fn parse(data: &[u8]) -> Result<Font<'_>, GlyphrError> {
+    let font = parse_header(data)?;
+    if let Glyphs::Blob(table) = font.glyphs {
+        validate_table(&table, font.format)?;
+    }
+    Ok(font)
+}
+
+/// Checks every glyph record: codepoints, ordering, sizes and bitmap ranges, and that Bitmap
+/// glyphs have a bit for every pixel of their box.
+fn validate_table(table: &GlyphTable, format: BitmapFormat) -> Result<(), GlyphrError> {
+    let mut previous = None;
+    for index in 0..table.len() {
+        let record = &table.records[index * RECORD_LEN..(index + 1) * RECORD_LEN];
+        let codepoint = read_u32(record, 0);
+        if char::from_u32(codepoint).is_none() {
+            return Err(GlyphrError::InvalidFontData("invalid codepoint"));
+        }
+        if previous.is_some_and(|p| p >= codepoint) {
+            return Err(GlyphrError::InvalidFontData("glyphs are not sorted"));
+        }
+        previous = Some(codepoint);
+
+        let (width, height) = (read_i32(record, 4), read_i32(record, 8));
+        if width < 0 || height < 0 {
+            return Err(GlyphrError::InvalidFontData("negative glyph size"));
+        }
+        if width > MAX_GLYPH_SIZE || height > MAX_GLYPH_SIZE {
+            return Err(GlyphrError::InvalidFontData("glyph too large"));
+        }
+
+        let offset = read_u32(record, 24) as usize;
+        let len = read_u32(record, 28) as usize;
+        if offset
+            .checked_add(len)
+            .is_none_or(|end| end > table.bitmaps.len())
+        {
+            return Err(GlyphrError::InvalidFontData("bitmap out of bounds"));
+        }
+        if matches!(format, BitmapFormat::Bitmap)
+            && (width as usize * height as usize).div_ceil(8) > len
+        {
+            return Err(GlyphrError::InvalidFontData(
+                "bitmap smaller than the glyph",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a `Font` checking only the header, for blobs that were already validated by `parse`.
+pub fn parse_header(data: &[u8]) -> Result<Font<'_>, GlyphrError> {
+    if data.len() < HEADER_LEN {
+        return Err(GlyphrError::InvalidFontData("truncated header"));
+    }
+    if data[0..4] != MAGIC {
+        return Err(GlyphrError::InvalidFontData("bad magic"));
+    }
+    let version = read_u16(data, 4);
+    if version == 0 || version > VERSION {
+        return Err(GlyphrError::InvalidFontData("unsupported version"));
+    }
+    let format = match (data[6], version) {
+        (0, _) => BitmapFormat::SDF,
+        (1, _) => BitmapFormat::Bitmap,
+        (2, 2..) => BitmapFormat::ColorSDF,
+        (3, 2..) => BitmapFormat::ColorBitmap,
+        _ => return Err(GlyphrError::InvalidFontData("unknown bitmap format")),
+    };
+
+    let count = read_u32(data, 20) as usize;
+    let table_end = count
+        .checked_mul(RECORD_LEN)
+        .and_then(|len| len.checked_add(HEADER_LEN))
+        .filter(|&end| end <= data.len())
+        .ok_or(GlyphrError::InvalidFontData("truncated glyph table"))?;
+
+    let table = GlyphTable {
+        records: &data[HEADER_LEN..table_end],
+        bitmaps: &data[table_end..],
+    };
+
+    Ok(Font {
+        glyphs: Glyphs::Blob(table),
+        size: read_i32(data, 8),
+        ascent: read_i32(data, 12),
+        descent: read_i32(data, 16),
+        format,
+    })
+}
+
+/// Number of bytes `encode` writes for `font`.
+pub fn encoded_len(font: &Font) -> usize {
+    let mut len = HEADER_LEN + font.glyph_count() * RECORD_LEN;
+    for index in 0..font.glyph_count() {
+        len += font.glyph(index).map_or(0, |g| g.bitmap.len());
+    }
+    len
+}
+
+/// Serializes `font` into `out`, returning the number of bytes written.
+pub fn encode(font: &Font, out: &mut [u8]) -> Result<usize, GlyphrError> {
+    let len = encoded_len(font);
+    if out.len() < len {
+        return Err(GlyphrError::OutOfBounds);
+    }
+
+    let count = font.glyph_count();
+    out[0..4].copy_from_slice(&MAGIC);
+    out[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    out[6] = match font.format {
+        BitmapFormat::SDF => 0,
+        BitmapFormat::Bitmap => 1,
+        BitmapFormat::ColorSDF => 2,
+        BitmapFormat::ColorBitmap => 3,
+    };
+    out[7] = 0;
+    out[8..12].copy_from_slice(&font.size.to_le_bytes());
+    out[12..16].copy_from_slice(&font.ascent.to_le_bytes());
+    out[16..20].copy_from_slice(&font.descent.to_le_bytes());
+    out[20..24].copy_from_slice(&(count as u32).to_le_bytes());
+
+    let bitmaps_start = HEADER_LEN + count * RECORD_LEN;
+    let mut offset = 0;
+    for (index, glyph) in (0..count).filter_map(|i| font.glyph(i)).enumerate() {
+        let fields = [
+            glyph.character as u32,
+            glyph.width as u32,
+            glyph.height as u32,
+            glyph.xmin as u32,
+            glyph.ymin as u32,
+            glyph.advance_width as u32,
+            offset as u32,
+            glyph.bitmap.len() as u32,
+        ];
+        let record = HEADER_LEN + index * RECORD_LEN;
+        for (i, field) in fields.iter().enumerate() {
+            out[record + i * 4..record + i * 4 + 4].copy_from_slice(&field.to_le_bytes());
+        }
+
+        let start = bitmaps_start + offset;
+        out[start..start + glyph.bitmap.len()].copy_from_slice(glyph.bitmap);
+        offset += glyph.bitmap.len();
+    }
+
+    Ok(len)
+}
+
+fn read_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+fn read_i32(data: &[u8], at: usize) -> i32 {
+    read_u32(data, at) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static BITMAP_A: [u8; 2] = [0b1010_0000, 0b1000_0000];
+    static GLYPHS: [Glyph; 2] = [
+        Glyph {
+            character: ' ',
+            bitmap: &[],
+            width: 0,
+            height: 0,
+            xmin: 0,
+            ymin: 0,
+            advance_width: 4,
+        },
+        Glyph {
+            character: 'A',
+            bitmap: &BITMAP_A,
+            width: 3,
+            height: 3,
+            xmin: -1,
+            ymin: 2,
+            advance_width: 5,
+        },
+    ];
+    static FONT: Font = Font {
+        glyphs: Glyphs::Static(&GLYPHS),
+        size: 8,
+        ascent: 7,
+        descent: -2,
+        format: BitmapFormat::Bitmap,
+    };
+
+    fn encoded() -> ([u8; 128], usize) {
+        let mut buf = [0u8; 128];
+        let len = encode(&FONT, &mut buf).unwrap();
+        (buf, len)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (buf, len) = encoded();
+        assert_eq!(len, HEADER_LEN + 2 * RECORD_LEN + 2);
+
+        let font = Font::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(font.glyph_count(), 2);
+        assert_eq!((font.size, font.ascent, font.descent), (8, 7, -2));
+        assert!(matches!(font.format, BitmapFormat::Bitmap));
+
+        let a = font.find_glyph('A').unwrap();
+        assert_eq!(a.bitmap, &BITMAP_A);
+        assert_eq!((a.width, a.height, a.xmin, a.ymin), (3, 3, -1, 2));
+        assert_eq!(a.advance_width, 5);
+        assert_eq!(font.find_glyph(' ').unwrap().advance_width, 4);
+        assert!(font.find_glyph('B').is_err());
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buf = [0u8; 16];
+        assert!(matches!(
+            encode(&FONT, &mut buf),
+            Err(GlyphrError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_header() {
+        let (mut buf, len) = encoded();
+        assert!(Font::from_bytes(&buf[..HEADER_LEN - 1]).is_err());
+        buf[4] = 3;
+        assert!(Font::from_bytes(&buf[..len]).is_err());
+        buf[4] = 0;
+        assert!(Font::from_bytes(&buf[..len]).is_err());
+        buf[4] = 2;
+        buf[0] = b'X';
+        assert!(Font::from_bytes(&buf[..len]).is_err());
+    }
+
+    #[test]
+    fn test_version_1_has_no_color_formats() {
+        let (mut buf, len) = encoded();
+        assert_eq!(read_u16(&buf, 4), VERSION);
+        buf[4] = 1;
+        assert!(matches!(
+            Font::from_bytes(&buf[..len]).unwrap().format,
+            BitmapFormat::Bitmap
+        ));
+        buf[6] = 3;
+        assert!(Font::from_bytes(&buf[..len]).is_err());
+        buf[4] = 2;
+        assert!(matches!(
+            Font::from_bytes(&buf[..len]).unwrap().format,
+            BitmapFormat::ColorBitmap
+        ));
+    }
+
+    #[test]
+    fn test_rejects_truncated_bitmaps() {
+        let (buf, len) = encoded();
+        assert!(Font::from_bytes(&buf[..len - 1]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_glyphs() {
+        let (mut buf, len) = encoded();
+        let width = HEADER_LEN + RECORD_LEN + 4;
+        buf[width..width + 4].copy_from_slice(&0x7fff_ffffu32.to_le_bytes());
+        assert!(matches!(
+            Font::from_bytes(&buf[..len]),
+            Err(GlyphrError::InvalidFontData("glyph too large"))
+        ));
+
+        // 6x3 pixels need 3 bytes, 'A' only has 2.
+        let (mut buf, len) = encoded();
+        buf[width..width + 4].copy_from_slice(&6u32.to_le_bytes());
+        assert!(matches!(
+            Font::from_bytes(&buf[..len]),
+            Err(GlyphrError::InvalidFontData(
+                "bitmap smaller than the glyph"
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unsorted_glyphs() {
+        let (mut buf, len) = encoded();
+        buf[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&('Z' as u32).to_le_bytes());
+        assert!(Font::from_bytes(&buf[..len]).is_err());
+    }
+}
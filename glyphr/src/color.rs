@@ -0,0 +1,211 @@
+//! # color.rs
+//!
+//! Glyphs of the color formats (`BitmapFormat::ColorSDF` and `ColorBitmap`), generated from the
+//! COLR layers and CPAL palette of the font.
+//!
+//! The bitmap of a color glyph is a sequence of layers, drawn in order. Every integer is
+//! little-endian:
+//!
+//! | offset | size | content                                                       |
+//! |--------|------|---------------------------------------------------------------|
+//! | 0      | 2    | palette index (`u16`), `0xFFFF` for the text color            |
+//! | 2      | 4    | color of the index in the font's first palette, `0xAARRGGBB`  |
+//! | 6      | 2    | x of the layer from the left of the glyph box (`i16`)         |
+//! | 8      | 2    | y of the layer from the top of the glyph box (`i16`)          |
+//! | 10     | 2    | width (`u16`)                                                 |
+//! | 12     | 2    | height (`u16`)                                                |
+//! | 14     | 4    | length of the data (`u32`)                                    |
+//! | 18     | ..   | data, encoded as the glyphs of the SDF or Bitmap format       |
+
+use crate::GlyphrError;
+
+/// Palette index of the layers drawn in the text color.
+pub const FOREGROUND: u16 = 0xFFFF;
+const LAYER_HEADER_LEN: usize = 18;
+
+/// A layer of a color glyph
+#[derive(Clone, Copy)]
+pub(crate) struct Layer<'a> {
+    pub palette_index: u16,
+    pub color: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub data: &'a [u8],
+}
+
+/// Iterator over the layers of the bitmap of a color glyph
+pub(crate) struct Layers<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Layers<'a> {
+    pub fn new(bitmap: &'a [u8]) -> Self {
+        Self { data: bitmap }
+    }
+}
+
+impl<'a> Iterator for Layers<'a> {
+    type Item = Result<Layer<'a>, GlyphrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = self.data;
+        let u16_at = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+        let truncated = Err(GlyphrError::InvalidFontData("truncated color layer"));
+
+        if data.len() < LAYER_HEADER_LEN {
+            self.data = &[];
+            return Some(truncated);
+        }
+        let len = u32::from_le_bytes([data[14], data[15], data[16], data[17]]) as usize;
+        let Some(layer_data) = data[LAYER_HEADER_LEN..].get(..len) else {
+            self.data = &[];
+            return Some(truncated);
+        };
+        self.data = &data[LAYER_HEADER_LEN + len..];
+
+        Some(Ok(Layer {
+            palette_index: u16_at(0),
+            color: u32::from_le_bytes([data[2], data[3], data[4], data[5]]),
+            x: u16_at(6) as i16 as i32,
+            y: u16_at(8) as i16 as i32,
+            width: u16_at(10) as i32,
+            height: u16_at(12) as i32,
+            data: layer_data,
+        }))
+    }
+}
+
+/// Color of `layer`, `0xAARRGGBB`: the text color, the entry of `palette` overriding the font's
+/// one, or the font's one.
+pub(crate) fn layer_color(layer: &Layer, text_color: u32, palette: &[u32]) -> u32 {
+    match layer.palette_index {
+        FOREGROUND => 0xff00_0000 | (text_color & 0x00ff_ffff),
+        index => palette.get(index as usize).copied().unwrap_or(layer.color),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferTarget, Glyphr, RenderConfig, SdfConfig, TextAlign};
+
+    // 'A' over a frame in palette entry 0 (opaque red).
+    crate::generate_font! {
+        name: COLOR,
+        path: "../glyphr-gen/tests/fonts/color.ttf",
+        size: 64,
+        characters: "A",
+        format: Bitmap { spread: 1.0, padding: 0 },
+        color: true,
+    }
+
+    crate::generate_font! {
+        name: COLOR_SDF,
+        path: "../glyphr-gen/tests/fonts/color.ttf",
+        size: 64,
+        characters: "A",
+        format: SDF { spread: 4.0, padding: 2 },
+        color: true,
+    }
+
+    const W: u32 = 96;
+    const H: u32 = 96;
+
+    fn render(font: crate::Font<'static>, config: RenderConfig) -> [u32; (W * H) as usize] {
+        let mut buffer = [0u32; (W * H) as usize];
+        Glyphr::with_config(config)
+            .render(
+                &mut BufferTarget::new(&mut buffer, W, H),
+                "A",
+                font,
+                4,
+                4,
+                TextAlign::default(),
+            )
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut bitmap = [0u8; 2 * LAYER_HEADER_LEN + 3];
+        bitmap[..LAYER_HEADER_LEN].copy_from_slice(&[
+            1, 0, 0x56, 0x34, 0x12, 0xff, 0xfe, 0xff, 3, 0, 4, 0, 5, 0, 2, 0, 0, 0,
+        ]);
+        bitmap[LAYER_HEADER_LEN..LAYER_HEADER_LEN + 2].copy_from_slice(&[7, 8]);
+        bitmap[LAYER_HEADER_LEN + 2..2 * LAYER_HEADER_LEN + 2]
+            .copy_from_slice(&[0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0]);
+        bitmap[2 * LAYER_HEADER_LEN + 2] = 9;
+
+        let mut layers = Layers::new(&bitmap);
+        let first = layers.next().unwrap().unwrap();
+        assert_eq!(first.palette_index, 1);
+        assert_eq!(first.color, 0xff12_3456);
+        assert_eq!((first.x, first.y, first.width, first.height), (-2, 3, 4, 5));
+        assert_eq!(first.data, [7, 8]);
+        let second = layers.next().unwrap().unwrap();
+        assert_eq!(second.palette_index, FOREGROUND);
+        assert_eq!(second.data, [9]);
+        assert!(layers.next().is_none());
+
+        assert!(matches!(
+            Layers::new(&bitmap[..LAYER_HEADER_LEN + 1]).next(),
+            Some(Err(GlyphrError::InvalidFontData(_)))
+        ));
+        assert!(Layers::new(&[]).next().is_none());
+    }
+
+    #[test]
+    fn test_layer_color() {
+        let layer = |palette_index| Layer {
+            palette_index,
+            color: 0x80ff_0000,
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+            data: &[],
+        };
+        assert_eq!(layer_color(&layer(FOREGROUND), 0x123456, &[]), 0xff12_3456);
+        assert_eq!(layer_color(&layer(1), 0x123456, &[]), 0x80ff_0000);
+        assert_eq!(layer_color(&layer(1), 0, &[1, 0xff00_ff00]), 0xff00_ff00);
+        assert_eq!(layer_color(&layer(2), 0, &[1, 0xff00_ff00]), 0x80ff_0000);
+    }
+
+    #[test]
+    fn test_color_glyph_composites_layers() {
+        let config = RenderConfig {
+            color: 0x0000ff,
+            sdf: SdfConfig {
+                size: 64,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for font in [COLOR, COLOR_SDF] {
+            let buffer = render(font, config);
+            assert!(buffer.contains(&0xffff0000));
+            assert!(buffer.contains(&0xff0000ff));
+            assert!(buffer.iter().all(|&p| p & 0x00ff00 == 0));
+        }
+    }
+
+    #[test]
+    fn test_palette_override() {
+        static THEME: [u32; 1] = [0xff00ff00];
+        let config = RenderConfig {
+            color: 0x0000ff,
+            palette: &THEME,
+            ..Default::default()
+        };
+        let buffer = render(COLOR, config);
+        assert!(buffer.contains(&0xff00ff00));
+        assert!(buffer.contains(&0xff0000ff));
+        assert!(!buffer.iter().any(|&p| p & 0xff0000 != 0));
+    }
+}
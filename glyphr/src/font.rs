@@ -3,15 +3,26 @@
 //! Contains structures used to describe generated fonts
 
 use crate::GlyphrError;
+use crate::blob::{self, GlyphTable};
+
+/// Character the `.notdef` glyph is stored as. It's a noncharacter, which fonts aren't meant to
+/// map but still can, so the generator refuses to generate it as a regular character. When a
+/// font contains it, it's drawn in place of every character the font doesn't have.
+pub const NOTDEF: char = '\u{10FFFF}';
 
 /// Defines how the glyphs are stored in the bitmaps
 #[derive(Clone, Copy)]
 pub enum BitmapFormat {
     SDF,
     Bitmap,
+    /// Glyphs made of colored layers (see `color.rs`) encoded as SDF glyphs
+    ColorSDF,
+    /// Glyphs made of colored layers (see `color.rs`) encoded as Bitmap glyphs
+    ColorBitmap,
 }
 
 /// Contains informations that are bound to the single glyph
+#[derive(Clone, Copy)]
 pub struct Glyph<'a> {
     pub character: char,
     pub bitmap: &'a [u8],
@@ -22,10 +33,19 @@ pub struct Glyph<'a> {
     pub advance_width: i32,
 }
 
+/// Where the glyphs of a font are stored
+#[derive(Clone, Copy)]
+pub enum Glyphs<'a> {
+    /// Glyphs generated at compile time, sorted by character
+    Static(&'a [Glyph<'a>]),
+    /// Glyph table of a binary font loaded with `Font::from_bytes`
+    Blob(GlyphTable<'a>),
+}
+
 /// Contains informations that are useful for every glyph
 #[derive(Clone, Copy)]
 pub struct Font<'a> {
-    pub glyphs: &'a [Glyph<'a>],
+    pub glyphs: Glyphs<'a>,
     pub size: i32,
     pub ascent: i32,
     pub descent: i32,
@@ -33,12 +53,84 @@ pub struct Font<'a> {
 }
 
 impl<'a> Font<'a> {
-    /// Returns a Result, Glyph if it's Ok, Err if the glyph is not found
-    pub fn find_glyph(&self, ch: char) -> Result<&Glyph<'a>, GlyphrError> {
-        self.glyphs
-            .binary_search_by_key(&ch, |g| g.character)
-            .map(|idx| &self.glyphs[idx])
-            .map_err(|_| GlyphrError::InvalidGlyph(ch))
+    /// Loads a font serialized in the binary format described in `blob.rs`. The data is
+    /// validated once and then borrowed, nothing is copied.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, GlyphrError> {
+        blob::parse(data)
+    }
+
+    /// Serializes the font in the binary format read by `from_bytes`, returning the number of
+    /// bytes written. `out` must be at least `encoded_len()` bytes long.
+    pub fn encode(&self, out: &mut [u8]) -> Result<usize, GlyphrError> {
+        blob::encode(self, out)
+    }
+
+    /// Number of bytes needed by `encode`.
+    pub fn encoded_len(&self) -> usize {
+        blob::encoded_len(self)
+    }
+
+    /// Returns a Result, Glyph if it's Ok, Err if the glyph is not found. Characters without a
+    /// glyph get the `.notdef` glyph, if the font has one.
+    pub fn find_glyph(&self, ch: char) -> Result<Glyph<'a>, GlyphrError> {
+        self.lookup(ch)
+            .or_else(|| self.lookup(NOTDEF))
+            .ok_or(GlyphrError::InvalidGlyph(ch))
+    }
+
+    fn lookup(&self, ch: char) -> Option<Glyph<'a>> {
+        match self.glyphs {
+            Glyphs::Static(glyphs) => glyphs
+                .binary_search_by_key(&ch, |g| g.character)
+                .ok()
+                .map(|idx| glyphs[idx]),
+            Glyphs::Blob(table) => table.position(ch).map(|idx| table.glyph(idx)),
+        }
+    }
+
+    /// Number of glyphs in the font
+    pub fn glyph_count(&self) -> usize {
+        match self.glyphs {
+            Glyphs::Static(glyphs) => glyphs.len(),
+            Glyphs::Blob(table) => table.len(),
+        }
+    }
+
+    /// Returns the glyph at `index`, glyphs are sorted by character
+    pub fn glyph(&self, index: usize) -> Option<Glyph<'a>> {
+        match self.glyphs {
+            Glyphs::Static(glyphs) => glyphs.get(index).copied(),
+            Glyphs::Blob(table) => (index < table.len()).then(|| table.glyph(index)),
+        }
+    }
+}
+
+/// Same typeface generated at several sizes (and possibly formats), picked at render time
+#[derive(Clone, Copy)]
+pub struct FontFamily<'a> {
+    pub fonts: &'a [Font<'a>],
+}
+
+impl<'a> FontFamily<'a> {
+    /// Returns the font that best matches the requested pixel size: a Bitmap font of exactly
+    /// `px`, otherwise the smallest SDF font not smaller than `px`, otherwise the biggest SDF
+    /// font available. Color fonts count as SDF or Bitmap ones.
+    pub fn select(&self, px: u32) -> Option<Font<'a>> {
+        let px = px as i32;
+        let sdf = || {
+            self.fonts
+                .iter()
+                .filter(|f| matches!(f.format, BitmapFormat::SDF | BitmapFormat::ColorSDF))
+        };
+
+        self.fonts
+            .iter()
+            .find(|f| {
+                matches!(f.format, BitmapFormat::Bitmap | BitmapFormat::ColorBitmap) && f.size == px
+            })
+            .or_else(|| sdf().filter(|f| f.size >= px).min_by_key(|f| f.size))
+            .or_else(|| sdf().max_by_key(|f| f.size))
+            .copied()
     }
 }
 
@@ -57,3 +149,77 @@ pub enum AlignV {
     Center,
     Baseline,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn font(size: i32, format: BitmapFormat) -> Font<'static> {
+        Font {
+            glyphs: Glyphs::Static(&[]),
+            size,
+            ascent: 0,
+            descent: 0,
+            format,
+        }
+    }
+
+    static FAMILY: [Font; 4] = [
+        font(16, BitmapFormat::Bitmap),
+        font(16, BitmapFormat::SDF),
+        font(32, BitmapFormat::SDF),
+        font(24, BitmapFormat::SDF),
+    ];
+
+    fn selected(px: u32) -> (i32, bool) {
+        let f = FontFamily { fonts: &FAMILY }.select(px).unwrap();
+        (f.size, matches!(f.format, BitmapFormat::Bitmap))
+    }
+
+    #[test]
+    fn test_family_prefers_exact_bitmap() {
+        assert_eq!(selected(16), (16, true));
+    }
+
+    #[test]
+    fn test_family_picks_nearest_larger_sdf() {
+        assert_eq!(selected(12), (16, false));
+        assert_eq!(selected(17), (24, false));
+        assert_eq!(selected(24), (24, false));
+        assert_eq!(selected(25), (32, false));
+    }
+
+    #[test]
+    fn test_family_falls_back_to_biggest_sdf() {
+        assert_eq!(selected(48), (32, false));
+    }
+
+    #[test]
+    fn test_family_without_match() {
+        let bitmaps = [font(16, BitmapFormat::Bitmap)];
+        assert!(FontFamily { fonts: &bitmaps }.select(20).is_none());
+    }
+
+    const fn glyph(character: char, advance_width: i32) -> Glyph<'static> {
+        Glyph {
+            character,
+            bitmap: &[],
+            width: 0,
+            height: 0,
+            xmin: 0,
+            ymin: 0,
+            advance_width,
+        }
+    }
+
+    #[test]
+    fn test_unknown_characters_use_notdef() {
+        static WITH_NOTDEF: [Glyph; 2] = [glyph('A', 5), glyph(NOTDEF, 7)];
+        let mut font = font(16, BitmapFormat::Bitmap);
+        assert!(font.find_glyph('A').is_err());
+
+        font.glyphs = Glyphs::Static(&WITH_NOTDEF);
+        assert_eq!(font.find_glyph('A').unwrap().advance_width, 5);
+        assert_eq!(font.find_glyph('Z').unwrap().character, NOTDEF);
+    }
+}
@@ -4,16 +4,33 @@
 
 #![no_std]
 
+#[cfg(feature = "runtime")]
+extern crate std;
+
+// Lets the tests use `text!`, which refers to the crate as `::glyphr`.
+#[cfg(test)]
+extern crate self as glyphr;
+
 mod api;
+mod blob;
+mod color;
 mod font;
 mod renderer;
+#[cfg(feature = "runtime")]
+pub mod runtime;
+mod text;
+mod text_image;
 mod utils;
 
 pub use api::{
-    BufferTarget, Glyphr, GlyphrError, RenderConfig, RenderTarget, SdfConfig, TextAlign,
+    BitmapConfig, BufferTarget, Glyphr, GlyphrError, RenderConfig, RenderTarget, SdfConfig,
+    TextAlign,
 };
-pub use font::{AlignH, AlignV, BitmapFormat, Font, Glyph};
-pub use glyphr_macros::generate_font;
+pub use blob::GlyphTable;
+pub use font::{AlignH, AlignV, BitmapFormat, Font, FontFamily, Glyph, Glyphs, NOTDEF};
+pub use glyphr_macros::{generate_font, generate_text_image, text};
+pub use text::{IndexedText, Text};
+pub use text_image::{ImageFormat, TextImage};
 
 #[cfg(feature = "toml")]
 pub use glyphr_macros::generate_fonts_from_toml;
@@ -6,6 +6,7 @@
 #[allow(unused_imports)]
 use crate::{
     BitmapFormat, Glyphr, GlyphrError, RenderTarget,
+    color::{self, Layers},
     font::{Font, Glyph},
     utils::{ExtFloor, smoothstep},
 };
@@ -16,17 +17,56 @@ use core::cmp::{max as cmax, min as cmin};
 pub fn render_glyph<T: RenderTarget>(
     x: i32,
     y: i32,
-    value: char,
+    glyph: &Glyph,
     font: Font,
     state: &Glyphr,
     scale: f32,
     target: &mut T,
 ) -> Result<(), GlyphrError> {
-    let glyph = &font.find_glyph(value)?;
-
+    let text_color = 0xff00_0000 | (state.config().color & 0x00ff_ffff);
     match font.format {
-        BitmapFormat::SDF => render_glyph_sdf(x, y, glyph, state, scale, target)?,
-        BitmapFormat::Bitmap => render_glyph_bitmap(x, y, glyph, state, target)?,
+        BitmapFormat::SDF => render_glyph_sdf(x, y, glyph, text_color, state, scale, target)?,
+        BitmapFormat::Bitmap => render_glyph_bitmap(x, y, glyph, text_color, scale as i32, target)?,
+        BitmapFormat::ColorSDF | BitmapFormat::ColorBitmap => {
+            render_color_glyph(x, y, glyph, font, state, scale, target)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the layers of a color glyph on top of each other, each in its palette color.
+fn render_color_glyph<T: RenderTarget>(
+    x: i32,
+    y: i32,
+    glyph: &Glyph,
+    font: Font,
+    state: &Glyphr,
+    scale: f32,
+    target: &mut T,
+) -> Result<(), GlyphrError> {
+    let cfg = state.config();
+    for layer in Layers::new(glyph.bitmap) {
+        let layer = layer?;
+        let color = color::layer_color(&layer, cfg.color, cfg.palette);
+        let layer_glyph = Glyph {
+            bitmap: layer.data,
+            width: layer.width,
+            height: layer.height,
+            ..*glyph
+        };
+        match font.format {
+            BitmapFormat::ColorBitmap => {
+                let scale = scale as i32;
+                let (lx, ly) = (x + layer.x * scale, y + layer.y * scale);
+                render_glyph_bitmap(lx, ly, &layer_glyph, color, scale, target)?
+            }
+            _ => {
+                let lx = x + (layer.x as f32 * scale) as i32;
+                let ly = y + (layer.y as f32 * scale) as i32;
+                render_glyph_sdf(lx, ly, &layer_glyph, color, state, scale, target)?
+            }
+        }
     }
 
     Ok(())
@@ -114,10 +154,12 @@ impl<'a> RleCursor<'a> {
 }
 
 /// Renders an SDF-encoded glyph applying smoothing (Y-major scan, RLE-cursor optimized).
+/// `color` is `0xAARRGGBB`, its alpha scales the coverage.
 fn render_glyph_sdf<T: RenderTarget>(
     dst_x: i32,
     dst_y: i32,
     glyph: &Glyph,
+    color: u32,
     state: &Glyphr,
     scale: f32,
     target: &mut T,
@@ -137,8 +179,8 @@ fn render_glyph_sdf<T: RenderTarget>(
     // Clipping to target bounds (early reject off-screen regions)
     let x0 = cmax(0, dst_x);
     let y0 = cmax(0, dst_y);
-    let x1 = cmin(dst_x + out_w, tgt_w);
-    let y1 = cmin(dst_y + out_h, tgt_h);
+    let x1 = cmin(dst_x.saturating_add(out_w), tgt_w);
+    let y1 = cmin(dst_y.saturating_add(out_h), tgt_h);
     if x0 >= x1 || y0 >= y1 {
         return Ok(());
     }
@@ -230,9 +272,9 @@ fn render_glyph_sdf<T: RenderTarget>(
                 0
             };
 
-            if alpha_u8 != 0 {
-                let alpha = (alpha_u8 as u32) << 24;
-                let blended_color = alpha | (cfg.color & 0x00ff_ffff);
+            let alpha = alpha_u8 as u32 * (color >> 24) / 255;
+            if alpha != 0 {
+                let blended_color = (alpha << 24) | (color & 0x00ff_ffff);
                 if !target.write_pixel(ox as u32, oy as u32, blended_color) {
                     return Err(GlyphrError::InvalidTarget);
                 }
@@ -244,15 +286,23 @@ fn render_glyph_sdf<T: RenderTarget>(
 }
 
 /// Renders a Bitmap-encoded glyph (bit-packed): Y-major, early clipping, fewer repeated checks.
+/// Every source pixel is replicated into a `scale`x`scale` block (nearest-neighbor), in `color`
+/// (`0xAARRGGBB`).
 fn render_glyph_bitmap<T: RenderTarget>(
     dst_x: i32,
     dst_y: i32,
     glyph: &Glyph,
-    state: &Glyphr,
+    color: u32,
+    scale: i32,
     target: &mut T,
 ) -> Result<(), GlyphrError> {
-    let w = glyph.width;
-    let h = glyph.height;
+    let scale = cmax(1, scale);
+    let (Some(w), Some(h)) = (
+        glyph.width.checked_mul(scale),
+        glyph.height.checked_mul(scale),
+    ) else {
+        return Err(GlyphrError::InvalidFontData("glyph too large"));
+    };
 
     if w <= 0 || h <= 0 {
         return Ok(());
@@ -264,18 +314,20 @@ fn render_glyph_bitmap<T: RenderTarget>(
 
     let x0 = cmax(0, dst_x);
     let y0 = cmax(0, dst_y);
-    let x1 = cmin(dst_x + w, tgt_w);
-    let y1 = cmin(dst_y + h, tgt_h);
+    let x1 = cmin(dst_x.saturating_add(w), tgt_w);
+    let y1 = cmin(dst_y.saturating_add(h), tgt_h);
     if x0 >= x1 || y0 >= y1 {
         return Ok(());
     }
 
-    let color = (0xffu32 << 24) | (state.config().color & 0x00ff_ffff);
+    if color >> 24 == 0 {
+        return Ok(());
+    }
 
     for oy in y0..y1 {
-        let y_src = oy - dst_y;
+        let y_src = (oy - dst_y) / scale;
         for ox in x0..x1 {
-            let x_src = ox - dst_x;
+            let x_src = (ox - dst_x) / scale;
             if bitmap_value_at(glyph, x_src, y_src)?
                 && !target.write_pixel(ox as u32, oy as u32, color)
             {
@@ -287,11 +339,6 @@ fn render_glyph_bitmap<T: RenderTarget>(
     Ok(())
 }
 
-/// Returns the advance width for a character.
-pub fn advance(c: char, font: Font) -> Result<i32, GlyphrError> {
-    Ok(font.find_glyph(c)?.advance_width)
-}
-
 /// Return a bit from a packed 1bpp bitmap.
 fn bitmap_value_at(glyph: &Glyph, x: i32, y: i32) -> Result<bool, GlyphrError> {
     if x < 0 || y < 0 || x >= glyph.width || y >= glyph.height {
@@ -311,7 +358,29 @@ fn bitmap_value_at(glyph: &Glyph, x: i32, y: i32) -> Result<bool, GlyphrError> {
 
 #[cfg(test)]
 mod tests {
-    use super::RleCursor;
+    use super::{RleCursor, render_glyph_bitmap};
+    use crate::{BufferTarget, Glyph, GlyphrError};
+
+    #[test]
+    fn oversized_bitmap_glyph_is_an_error() {
+        let glyph = Glyph {
+            character: 'A',
+            bitmap: &[0xff],
+            width: 0x7fff_ffff,
+            height: 1,
+            xmin: 0,
+            ymin: 0,
+            advance_width: 1,
+        };
+        let mut buffer = [0u32; 4];
+        let mut target = BufferTarget::new(&mut buffer, 2, 2);
+        assert!(matches!(
+            render_glyph_bitmap(0, 0, &glyph, 0xff00_0000, 2, &mut target),
+            Err(GlyphrError::InvalidFontData(_))
+        ));
+        // Unscaled, the box is clipped to the target instead of overflowing.
+        assert!(render_glyph_bitmap(1, 0, &glyph, 0xff00_0000, 1, &mut target).is_ok());
+    }
 
     #[test]
     fn single_run() {
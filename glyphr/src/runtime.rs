@@ -0,0 +1,266 @@
+//! # runtime.rs
+//!
+//! Generates fonts from TTF/OTF files at runtime, on targets with `std`. The glyphs are produced
+//! by the same generator used by the macros and kept in the binary font format, so the
+//! resulting `OwnedFont` hands out a regular `Font` view and rendering works exactly as with
+//! generated fonts.
+
+use std::path::Path;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use glyphr_gen::config::{FontLoaded, load_font, load_font_from_bytes, parse_char_set};
+use glyphr_gen::generator::GeneratedFont;
+use glyphr_gen::generator::font::Font as SourceFont;
+
+pub use glyphr_gen::{BitmapFormat, Error};
+
+use crate::blob;
+use crate::font::{BitmapFormat as RuntimeFormat, Font, Glyph, Glyphs};
+
+/// A font generated at runtime, owning its glyph data.
+pub struct OwnedFont {
+    data: Vec<u8>,
+}
+
+impl OwnedFont {
+    /// Loads a TTF/OTF file and generates `characters` (same syntax as the macros) at `size` px.
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        size: i32,
+        characters: &str,
+        format: BitmapFormat,
+    ) -> Result<Self, Error> {
+        Self::generate(load_font(path)?, size, characters, format)
+    }
+
+    /// Same as `from_file`, with the font file already in memory. WOFF and WOFF2 data is read
+    /// like it is by the macros.
+    pub fn from_ttf_bytes(
+        data: &[u8],
+        size: i32,
+        characters: &str,
+        format: BitmapFormat,
+    ) -> Result<Self, Error> {
+        let font = load_font_from_bytes(data, "<memory>", &Default::default(), Default::default())?;
+        Self::generate(font, size, characters, format)
+    }
+
+    fn generate(
+        font: SourceFont,
+        size: i32,
+        characters: &str,
+        format: BitmapFormat,
+    ) -> Result<Self, Error> {
+        let generated = GeneratedFont::new(FontLoaded {
+            name: "RUNTIME".into(),
+            font: Rc::new(font),
+            px: size,
+            char_range: parse_char_set(characters)?,
+            format,
+            options: Default::default(),
+            family: None,
+        })?;
+
+        Ok(Self {
+            data: encode(&generated),
+        })
+    }
+
+    /// Borrows the font, ready to be passed to `Glyphr::render`.
+    pub fn font(&self) -> Font<'_> {
+        // The blob is written by the generator and never modified, no need to validate it again.
+        blob::parse_header(&self.data).expect("generated font blob is valid")
+    }
+
+    /// The font in the binary format, e.g. to store it and later load it with `Font::from_bytes`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Serializes a font generated by `glyphr-gen` in the binary format loaded by
+/// `Font::from_bytes`, with the same encoder as `Font::encode`. Glyphs are written in codepoint
+/// order.
+pub fn encode(generated: &GeneratedFont) -> Vec<u8> {
+    let mut glyphs: Vec<Glyph> = generated
+        .glyphs
+        .iter()
+        .map(|(bitmap, entry)| Glyph {
+            character: entry.character,
+            bitmap,
+            width: entry.width,
+            height: entry.height,
+            xmin: entry.xmin,
+            ymin: entry.ymin,
+            advance_width: entry.advance_width,
+        })
+        .collect();
+    glyphs.sort_by_key(|glyph| glyph.character);
+
+    let font = Font {
+        glyphs: Glyphs::Static(&glyphs),
+        size: generated.font.px,
+        ascent: generated.ascent(),
+        descent: generated.descent(),
+        format: match (generated.font.format, generated.font.options.color) {
+            (BitmapFormat::SDF { .. }, false) => RuntimeFormat::SDF,
+            (BitmapFormat::Bitmap { .. }, false) => RuntimeFormat::Bitmap,
+            (BitmapFormat::SDF { .. }, true) => RuntimeFormat::ColorSDF,
+            (BitmapFormat::Bitmap { .. }, true) => RuntimeFormat::ColorBitmap,
+        },
+    };
+    let mut data = std::vec![0; font.encoded_len()];
+    blob::encode(&font, &mut data).expect("the buffer has the encoded length");
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferTarget, Glyphr, TextAlign};
+    use glyphr_gen::config::GenerationOptions;
+
+    const DEMO_TTF: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../glyphr-gen/tests/fonts/demo.ttf"
+    );
+
+    #[test]
+    fn test_runtime_font_renders() {
+        let owned = OwnedFont::from_file(
+            DEMO_TTF,
+            16,
+            "A",
+            BitmapFormat::Bitmap {
+                spread: 2.0,
+                padding: 0,
+            },
+        )
+        .unwrap();
+
+        let font = owned.font();
+        assert_eq!(font.size, 16);
+        assert!(Font::from_bytes(owned.as_bytes()).is_ok());
+
+        let mut buffer = [0u32; 32 * 32];
+        let mut target = BufferTarget::new(&mut buffer, 32, 32);
+        Glyphr::new()
+            .render(&mut target, "A", font, 0, 0, TextAlign::default())
+            .unwrap();
+        assert!(buffer.iter().any(|&p| p != 0));
+    }
+
+    #[test]
+    fn test_generated_blobs_roundtrip() {
+        let color_ttf = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../glyphr-gen/tests/fonts/color.ttf"
+        );
+        let fonts = [
+            (
+                DEMO_TTF,
+                BitmapFormat::Bitmap {
+                    spread: 2.0,
+                    padding: 0,
+                },
+                false,
+                1,
+            ),
+            (
+                DEMO_TTF,
+                BitmapFormat::SDF {
+                    spread: 4.0,
+                    padding: 1,
+                },
+                false,
+                0,
+            ),
+            (
+                color_ttf,
+                BitmapFormat::Bitmap {
+                    spread: 1.0,
+                    padding: 0,
+                },
+                true,
+                3,
+            ),
+            (
+                color_ttf,
+                BitmapFormat::SDF {
+                    spread: 4.0,
+                    padding: 2,
+                },
+                true,
+                2,
+            ),
+        ];
+        for (path, format, color, format_byte) in fonts {
+            let generated = GeneratedFont::new(FontLoaded {
+                name: "ROUNDTRIP".into(),
+                font: Rc::new(load_font(path).unwrap()),
+                px: 24,
+                char_range: std::vec!['A'],
+                format,
+                options: GenerationOptions {
+                    color,
+                    include_notdef: true,
+                    ..Default::default()
+                },
+                family: None,
+            })
+            .unwrap();
+            let data = encode(&generated);
+            assert_eq!(data[6], format_byte);
+
+            // `parse` accepts the generator output, and writes it back byte for byte.
+            let font = Font::from_bytes(&data).unwrap();
+            assert_eq!(font.glyph_count(), generated.glyphs.len());
+            for (bitmap, entry) in &generated.glyphs {
+                let glyph = font.find_glyph(entry.character).unwrap();
+                assert_eq!(glyph.bitmap, bitmap.as_slice());
+                assert_eq!(
+                    (glyph.xmin, glyph.ymin, glyph.width, glyph.height),
+                    (entry.xmin, entry.ymin, entry.width, entry.height)
+                );
+                assert_eq!(glyph.advance_width, entry.advance_width);
+            }
+            let mut written = std::vec![0; font.encoded_len()];
+            font.encode(&mut written).unwrap();
+            assert_eq!(written, data);
+        }
+    }
+
+    #[test]
+    fn test_runtime_font_missing_glyph() {
+        let data = std::fs::read(DEMO_TTF).unwrap();
+        let result = OwnedFont::from_ttf_bytes(
+            &data,
+            16,
+            "B",
+            BitmapFormat::SDF {
+                spread: 20.0,
+                padding: 0,
+            },
+        );
+        assert!(matches!(result, Err(Error::MissingGlyphs { .. })));
+    }
+
+    #[test]
+    fn test_runtime_font_from_web_font_bytes() {
+        let fonts = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../glyphr-gen/tests/fonts"
+        ));
+        let format = BitmapFormat::Bitmap {
+            spread: 2.0,
+            padding: 0,
+        };
+        let ttf = OwnedFont::from_file(fonts.join("web.ttf"), 16, "A\u{c4}", format).unwrap();
+        for file in ["web.woff", "web.woff2"] {
+            let data = std::fs::read(fonts.join(file)).unwrap();
+            let owned = OwnedFont::from_ttf_bytes(&data, 16, "A\u{c4}", format).unwrap();
+            assert_eq!(owned.as_bytes(), ttf.as_bytes(), "{file}");
+        }
+    }
+}
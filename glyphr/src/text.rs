@@ -0,0 +1,231 @@
+//! # text.rs
+//!
+//! Text accepted by the renderer: plain strings, or texts whose glyphs were looked up at compile
+//! time by `text!`.
+
+use core::slice;
+use core::str::Chars;
+
+use crate::GlyphrError;
+use crate::font::{Font, Glyph, Glyphs};
+
+/// Text checked against a generated font and resolved to its glyph indices at compile time,
+/// made with `glyphr::text!(FONT, "Hello")`. Rendering it with that font needs no glyph lookups,
+/// with any other font the characters are looked up as for a `&str`.
+///
+/// ```
+/// glyphr::generate_font! {
+///     name: DEMO,
+///     path: "../glyphr-gen/tests/fonts/demo.ttf",
+///     size: 24,
+///     characters: "A",
+///     format: Bitmap { spread: 1.0, padding: 0 },
+/// }
+///
+/// static TEXT: glyphr::IndexedText = glyphr::text!(DEMO, "AA");
+/// ```
+///
+/// A character the font doesn't have is a compile error:
+///
+/// ```compile_fail
+/// glyphr::generate_font! {
+///     name: DEMO,
+///     path: "../glyphr-gen/tests/fonts/demo.ttf",
+///     size: 24,
+///     characters: "A",
+///     format: Bitmap { spread: 1.0, padding: 0 },
+/// }
+///
+/// static TEXT: glyphr::IndexedText = glyphr::text!(DEMO, "AB");
+/// ```
+#[derive(Clone, Copy)]
+pub struct IndexedText<'a> {
+    text: &'a str,
+    /// Glyphs of the font the text was indexed for.
+    glyphs: &'a [Glyph<'a>],
+    indices: &'a [u16],
+}
+
+impl<'a> IndexedText<'a> {
+    /// Used by `text!`, `indices` must hold the glyph index in `font` of every character of
+    /// `text`.
+    #[doc(hidden)]
+    pub const fn new(text: &'a str, font: &Font<'a>, indices: &'a [u16]) -> Self {
+        Self {
+            text,
+            glyphs: static_glyphs(font),
+            indices,
+        }
+    }
+
+    /// Used by `text!`: index of the glyph of `ch` in `font`, without `.notdef` fallback.
+    #[doc(hidden)]
+    pub const fn index(font: &Font, ch: char) -> Option<u16> {
+        let glyphs = static_glyphs(font);
+        let (mut low, mut high) = (0, glyphs.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let character = glyphs[mid].character as u32;
+            if character == ch as u32 {
+                assert!(
+                    mid <= u16::MAX as usize,
+                    "text! supports fonts of up to 65536 glyphs"
+                );
+                return Some(mid as u16);
+            } else if character < ch as u32 {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        None
+    }
+
+    /// The text, as written in `text!`.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Index of the glyph of every character, as in `Font::glyph`.
+    pub fn indices(&self) -> &'a [u16] {
+        self.indices
+    }
+}
+
+const fn static_glyphs<'a>(font: &Font<'a>) -> &'a [Glyph<'a>] {
+    match font.glyphs {
+        Glyphs::Static(glyphs) => glyphs,
+        Glyphs::Blob(_) => panic!("text! needs a font generated at compile time"),
+    }
+}
+
+/// Text to render, either a `&str` or an `IndexedText`.
+#[derive(Clone, Copy)]
+pub enum Text<'a> {
+    Str(&'a str),
+    Indexed(IndexedText<'a>),
+}
+
+impl<'a> Text<'a> {
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Text::Str(text) => text,
+            Text::Indexed(text) => text.text,
+        }
+    }
+
+    /// Glyphs of the text in `font`, in order.
+    pub(crate) fn glyphs(self, font: Font<'a>) -> TextGlyphs<'a> {
+        let indexed = match (self, font.glyphs) {
+            (Text::Indexed(text), Glyphs::Static(glyphs)) if core::ptr::eq(glyphs, text.glyphs) => {
+                Some((glyphs, text.indices.iter()))
+            }
+            _ => None,
+        };
+        TextGlyphs {
+            chars: self.as_str().chars(),
+            indexed,
+            font,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Text<'a> {
+    fn from(text: &'a str) -> Self {
+        Text::Str(text)
+    }
+}
+
+impl<'a> From<IndexedText<'a>> for Text<'a> {
+    fn from(text: IndexedText<'a>) -> Self {
+        Text::Indexed(text)
+    }
+}
+
+impl<'a> From<&IndexedText<'a>> for Text<'a> {
+    fn from(text: &IndexedText<'a>) -> Self {
+        Text::Indexed(*text)
+    }
+}
+
+/// Iterator over the glyphs of a `Text`, taking them by index when the text was indexed for the
+/// font.
+pub(crate) struct TextGlyphs<'a> {
+    chars: Chars<'a>,
+    indexed: Option<(&'a [Glyph<'a>], slice::Iter<'a, u16>)>,
+    font: Font<'a>,
+}
+
+impl<'a> Iterator for TextGlyphs<'a> {
+    type Item = Result<Glyph<'a>, GlyphrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.indexed {
+            Some((glyphs, indices)) => indices.next().map(|&index| Ok(glyphs[index as usize])),
+            None => self.chars.next().map(|ch| self.font.find_glyph(ch)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::BitmapFormat;
+
+    const fn glyph(character: char, advance_width: i32) -> Glyph<'static> {
+        Glyph {
+            character,
+            bitmap: &[],
+            width: 0,
+            height: 0,
+            xmin: 0,
+            ymin: 0,
+            advance_width,
+        }
+    }
+
+    static GLYPHS: [Glyph; 3] = [glyph('a', 1), glyph('b', 2), glyph('c', 3)];
+    static FONT: Font = Font {
+        glyphs: Glyphs::Static(&GLYPHS),
+        size: 8,
+        ascent: 8,
+        descent: 0,
+        format: BitmapFormat::Bitmap,
+    };
+    static OTHER: Font = Font {
+        glyphs: Glyphs::Static(&GLYPHS_OTHER),
+        ..FONT
+    };
+    static GLYPHS_OTHER: [Glyph; 2] = [glyph('b', 20), glyph('c', 30)];
+
+    /// Whether the advances of the glyphs of `text` are `expected`, -1 for missing glyphs.
+    fn advances(text: impl Into<Text<'static>>, font: Font<'static>, expected: &[i32]) -> bool {
+        text.into()
+            .glyphs(font)
+            .map(|glyph| glyph.map_or(-1, |glyph| glyph.advance_width))
+            .eq(expected.iter().copied())
+    }
+
+    #[test]
+    fn test_text_macro_indexes_glyphs() {
+        static TEXT: IndexedText = crate::text!(FONT, "cab");
+        assert_eq!(TEXT.as_str(), "cab");
+        assert_eq!(TEXT.indices(), [2, 0, 1]);
+        assert!(advances(TEXT, FONT, &[3, 1, 2]));
+        assert!(advances("cab", FONT, &[3, 1, 2]));
+    }
+
+    #[test]
+    fn test_indexed_text_with_other_font() {
+        let text = crate::text!(FONT, "cab");
+        assert!(advances(text, OTHER, &[30, -1, 20]));
+    }
+
+    #[test]
+    fn test_index_lookup() {
+        assert_eq!(IndexedText::index(&FONT, 'a'), Some(0));
+        assert_eq!(IndexedText::index(&FONT, 'c'), Some(2));
+        assert_eq!(IndexedText::index(&FONT, 'd'), None);
+        assert_eq!(IndexedText::index(&OTHER, 'a'), None);
+    }
+}
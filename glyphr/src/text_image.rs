@@ -0,0 +1,167 @@
+//! # text_image.rs
+//!
+//! Texts rendered at compile time by `generate_text_image!`, drawn without fonts nor layout.
+
+use crate::{GlyphrError, RenderTarget};
+
+/// How the pixels of a `TextImage` are stored
+#[derive(Clone, Copy)]
+pub enum ImageFormat {
+    /// Anti-aliased 8 bit alpha, RLE encoded in `[count, value]` pairs like SDF glyphs
+    Alpha,
+    /// 1 bit per pixel, packed like Bitmap glyphs
+    Mono,
+}
+
+/// A static text laid out and rasterized at compile time
+#[derive(Clone, Copy)]
+pub struct TextImage<'a> {
+    pub width: i32,
+    pub height: i32,
+    /// Position of the image relative to the point `Glyphr::render` would draw the text at,
+    /// with the default alignment.
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub format: ImageFormat,
+    pub data: &'a [u8],
+}
+
+impl TextImage<'_> {
+    /// Draws the text in `color` (the alpha comes from the image) where `Glyphr::render` would
+    /// draw it at (`x`, `y`) with the default alignment. Pixels out of the target are skipped.
+    pub fn blit<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        x: i32,
+        y: i32,
+        color: u32,
+    ) -> Result<(), GlyphrError> {
+        if self.width <= 0 || self.height <= 0 {
+            return Ok(());
+        }
+
+        let (target_w, target_h) = target.dimensions();
+        let (left, top) = (x + self.x_offset, y + self.y_offset);
+        let width = self.width as usize;
+        let pixels = width * self.height as usize;
+        let color = color & 0x00ff_ffff;
+
+        let mut plot = |index: usize, alpha: u8| {
+            let px = left + (index % width) as i32;
+            let py = top + (index / width) as i32;
+            if px < 0 || py < 0 || px >= target_w as i32 || py >= target_h as i32 {
+                return Ok(());
+            }
+            match target.write_pixel(px as u32, py as u32, ((alpha as u32) << 24) | color) {
+                true => Ok(()),
+                false => Err(GlyphrError::InvalidTarget),
+            }
+        };
+
+        match self.format {
+            ImageFormat::Alpha => {
+                let mut index = 0;
+                for run in self.data.chunks_exact(2) {
+                    let (count, alpha) = (run[0] as usize, run[1]);
+                    if alpha != 0 {
+                        for i in index..(index + count).min(pixels) {
+                            plot(i, alpha)?;
+                        }
+                    }
+                    index += count;
+                }
+            }
+            ImageFormat::Mono => {
+                for (byte_index, &byte) in self.data.iter().enumerate() {
+                    if byte == 0 {
+                        continue;
+                    }
+                    for bit in 0..8 {
+                        let index = byte_index * 8 + bit;
+                        if byte & (0x80 >> bit) != 0 && index < pixels {
+                            plot(index, 0xff)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BitmapConfig, BufferTarget, Glyphr, RenderConfig, TextAlign};
+
+    crate::generate_font! {
+        name: DEMO,
+        path: "../glyphr-gen/tests/fonts/demo.ttf",
+        size: 24,
+        characters: "A",
+        format: Bitmap { spread: 1.0, padding: 0 },
+    }
+
+    crate::generate_text_image! {
+        name: DEMO_MONO,
+        path: "../glyphr-gen/tests/fonts/demo.ttf",
+        size: 24,
+        text: "AA",
+        format: Mono,
+    }
+
+    crate::generate_text_image! {
+        name: DEMO_ALPHA,
+        path: "../glyphr-gen/tests/fonts/demo.ttf",
+        size: 24,
+        text: "AA",
+    }
+
+    const W: u32 = 64;
+    const H: u32 = 48;
+
+    #[test]
+    fn test_mono_image_matches_render() {
+        let mut rendered = [0u32; (W * H) as usize];
+        let renderer = Glyphr::with_config(RenderConfig {
+            color: 0x123456,
+            bitmap: BitmapConfig { scale: 1 },
+            ..Default::default()
+        });
+        renderer
+            .render(
+                &mut BufferTarget::new(&mut rendered, W, H),
+                "AA",
+                DEMO,
+                5,
+                10,
+                TextAlign::default(),
+            )
+            .unwrap();
+
+        let mut blitted = [0u32; (W * H) as usize];
+        DEMO_MONO
+            .blit(&mut BufferTarget::new(&mut blitted, W, H), 5, 10, 0x123456)
+            .unwrap();
+        assert!(rendered.iter().any(|&p| p != 0));
+        assert_eq!(rendered, blitted);
+    }
+
+    #[test]
+    fn test_alpha_image_blends() {
+        let mut buffer = [0u32; (W * H) as usize];
+        DEMO_ALPHA
+            .blit(&mut BufferTarget::new(&mut buffer, W, H), 5, 10, 0xffffff)
+            .unwrap();
+        assert!(buffer.contains(&0xffffffff));
+        assert!(buffer.iter().any(|&p| p & 0xff != 0 && p & 0xff != 0xff));
+    }
+
+    #[test]
+    fn test_blit_clips_to_target() {
+        let mut buffer = [0u32; 16];
+        let mut target = BufferTarget::new(&mut buffer, 4, 4);
+        DEMO_ALPHA.blit(&mut target, -30, -30, 0xffffff).unwrap();
+        DEMO_MONO.blit(&mut target, 3, 3, 0xffffff).unwrap();
+    }
+}